@@ -0,0 +1,215 @@
+pub use terminal::TerminalPlotter;
+pub use svg::SvgPlotter;
+
+mod terminal;
+mod svg;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChartKind {
+    // Vertical bars, one per label. Only the first series is drawn.
+    Columns,
+    // Horizontal bars with each value's share of the total. Only the first series is drawn.
+    Bars,
+    // One point per label for every series, joined in order.
+    Line,
+    // Horizontal bars, one per label, with every series stacked on top of the previous one.
+    Stacked,
+}
+
+#[derive(Debug, Clone)]
+pub struct Series {
+    pub name: String,
+    pub values: Vec<f32>,
+}
+
+impl Series {
+    pub fn new(name: &str, values: Vec<f32>) -> Self {
+        Self { name: String::from(name), values }
+    }
+}
+
+// Plain data describing a chart, so the same series can be drawn in the terminal or exported.
+#[derive(Debug, Clone)]
+pub struct Chart {
+    pub title: String,
+    pub kind: ChartKind,
+    pub labels: Vec<String>,
+    pub series: Vec<Series>,
+    // Label index and text of notable points, like a change of company.
+    pub markers: Vec<(usize, String)>,
+    pub unit: String,
+}
+
+impl Chart {
+    pub fn new(title: &str, kind: ChartKind, labels: Vec<String>, series: Vec<Series>) -> Self {
+        Self {
+            title: String::from(title),
+            kind,
+            labels,
+            series,
+            markers: Vec::new(),
+            unit: String::new(),
+        }
+    }
+
+    pub fn with_markers(mut self, markers: Vec<(usize, String)>) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    pub fn with_unit(mut self, unit: &str) -> Self {
+        self.unit = String::from(unit);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty() || self.series.iter().all(|s| s.values.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Charset {
+    Unicode,
+    Ascii,
+}
+
+impl Charset {
+    pub fn detect() -> Self {
+        let term = std::env::var("TERM").unwrap_or_default();
+        if term == "dumb" {
+            return Charset::Ascii;
+        }
+
+        // The first locale variable that is set wins, same as the C library does.
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+            .filter_map(|v| std::env::var(v).ok())
+            .find(|v| !v.is_empty());
+
+        match locale {
+            Some(l) if !l.to_lowercase().replace('-', "").contains("utf8") => Charset::Ascii,
+            _ => Charset::Unicode,
+        }
+    }
+}
+
+// One character per value scaled between the smallest and largest one, to show a trend inside a table cell.
+pub fn sparkline(values: &[f32], charset: Charset) -> String {
+    let levels: Vec<char> = match charset {
+        Charset::Unicode => "▁▂▃▄▅▆▇█".chars().collect(),
+        Charset::Ascii => "_.-=*#".chars().collect(),
+    };
+    
+    let min = values.iter().cloned().fold(f32::MAX, f32::min);
+    let max = values.iter().cloned().fold(f32::MIN, f32::max);
+    let top = (levels.len() - 1) as f32;
+    
+    values.iter().map(|v| {
+        let level = if max - min > f32::EPSILON { ((v - min) / (max - min) * top).round() } else { 0.0 };
+        levels[level as usize]
+    }).collect()
+}
+
+// Groups dates (YYYY-MM-DD) by month, filling the gaps between the first and last month with zeroes.
+pub fn monthly_totals<'a, I>(values: I) -> (Vec<String>, Vec<f32>)
+    where I: Iterator<Item = (&'a str, f32)> {
+    let mut totals: std::collections::BTreeMap<(i32, u32), f32> = std::collections::BTreeMap::new();
+
+    for (date, amount) in values {
+        if let Some(key) = parse_month(date) {
+            *totals.entry(key).or_insert(0.0) += amount;
+        }
+    }
+
+    let (first, last) = match (totals.keys().next(), totals.keys().next_back()) {
+        (Some(f), Some(l)) => (*f, *l),
+        _ => return (Vec::new(), Vec::new()),
+    };
+
+    let mut labels = Vec::new();
+    let mut ret = Vec::new();
+    let (mut y, mut m) = first;
+    while (y, m) <= last {
+        labels.push(format!("{:04}-{:02}", y, m));
+        ret.push(*totals.get(&(y, m)).unwrap_or(&0.0));
+
+        m += 1;
+        if m > 12 {
+            m = 1;
+            y += 1;
+        }
+    }
+
+    (labels, ret)
+}
+
+// Same as monthly_totals but for a fixed set of month labels, so several series share the x axis.
+pub fn monthly_values<'a, I>(labels: &[String], values: I) -> Vec<f32>
+    where I: Iterator<Item = (&'a str, f32)> {
+    let mut ret = vec![0.0; labels.len()];
+
+    for (date, amount) in values {
+        let month = month_label(date);
+        if let Some(i) = labels.iter().position(|l| Some(l) == month.as_ref()) {
+            ret[i] += amount;
+        }
+    }
+
+    ret
+}
+
+pub fn month_label(date: &str) -> Option<String> {
+    parse_month(date).map(|(y, m)| format!("{:04}-{:02}", y, m))
+}
+
+fn parse_month(date: &str) -> Option<(i32, u32)> {
+    let mut parts = date.split(['-', '/']);
+    let year = parts.next()?.trim().parse::<i32>().ok()?;
+    let month = parts.next()?.trim().parse::<u32>().ok()?;
+
+    if (1..=12).contains(&month) {
+        Some((year, month))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn months_are_bucketed_and_gaps_filled() {
+        let values = vec![("2021-11-30", 10.0), ("2021/11/02", 5.0), ("2022-02-01", -20.0), ("not a date", 99.0), ("2022-13-01", 99.0)];
+        let (labels, totals) = monthly_totals(values.into_iter());
+
+        assert_eq!(labels, vec!["2021-11", "2021-12", "2022-01", "2022-02"]);
+        assert_eq!(totals, vec![15.0, 0.0, 0.0, -20.0]);
+
+        let (labels, totals) = monthly_totals(std::iter::empty());
+        assert!(labels.is_empty() && totals.is_empty());
+    }
+
+    #[test]
+    fn values_follow_the_given_labels() {
+        let labels = vec![String::from("2022-01"), String::from("2022-02")];
+        let values = vec![("2022-02-10", 3.0), ("2022-02-11", 4.0), ("2022-05-01", 99.0)];
+        assert_eq!(monthly_values(&labels, values.into_iter()), vec![0.0, 7.0]);
+    }
+
+    #[test]
+    fn month_labels() {
+        assert_eq!(month_label("2022-3-15").as_deref(), Some("2022-03"));
+        assert_eq!(month_label("2022/12/01").as_deref(), Some("2022-12"));
+        assert_eq!(month_label("2022-00-01"), None);
+        assert_eq!(month_label("2022"), None);
+    }
+
+    #[test]
+    fn sparklines_scale_between_the_extremes() {
+        assert_eq!(sparkline(&[-10.0, 0.0, 10.0], Charset::Unicode), "▁▅█");
+        assert_eq!(sparkline(&[0.0, 5.0, 10.0], Charset::Ascii), "_=#");
+        // Nothing to scale, everything at the bottom.
+        assert_eq!(sparkline(&[4.0, 4.0], Charset::Ascii), "__");
+        assert_eq!(sparkline(&[], Charset::Ascii), "");
+    }
+}
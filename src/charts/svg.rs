@@ -0,0 +1,270 @@
+use std::fmt::Write;
+use std::fs;
+use std::io;
+
+use crate::charts::{Chart, ChartKind};
+use crate::commons::{escape, XML_ESCAPES};
+
+const WIDTH: f32 = 800.0;
+const CHART_HEIGHT: f32 = 320.0;
+const TITLE_HEIGHT: f32 = 30.0;
+const BAR_HEIGHT: f32 = 22.0;
+const MARGIN_LEFT: f32 = 70.0;
+const MARGIN_RIGHT: f32 = 20.0;
+const MARGIN_BOTTOM: f32 = 50.0;
+const GRID_LINES: usize = 4;
+
+const PALETTE: [&str; 5] = ["#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f"];
+const TEXT_STYLE: &str = "font-family=\"sans-serif\" font-size=\"11\" fill=\"#333\"";
+
+// Renders charts as a single standalone SVG document, one chart below the other.
+pub struct SvgPlotter;
+
+impl SvgPlotter {
+    pub fn render(charts: &[Chart]) -> String {
+        format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}", SvgPlotter::render_inline(charts))
+    }
+
+    // Just the <svg> element, to embed in other documents.
+    pub fn render_inline(charts: &[Chart]) -> String {
+        let mut body = String::new();
+        let mut y = 0.0;
+
+        for chart in charts {
+            let height = SvgPlotter::height(chart);
+            let _ = writeln!(body, "<g transform=\"translate(0,{:.1})\">", y);
+            SvgPlotter::draw(chart, height, &mut body);
+            let _ = writeln!(body, "</g>");
+            y += height;
+        }
+
+        let mut out = String::new();
+        let _ = writeln!(out, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h:.0}\" viewBox=\"0 0 {w} {h:.0}\">", w = WIDTH, h = y);
+        let _ = writeln!(out, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>");
+        out += &body;
+        let _ = writeln!(out, "</svg>");
+        out
+    }
+
+    pub fn write(filename: &str, charts: &[Chart]) -> io::Result<()> {
+        fs::write(filename, SvgPlotter::render(charts))
+    }
+
+    fn height(chart: &Chart) -> f32 {
+        match chart.kind {
+            ChartKind::Bars if !chart.is_empty() => TITLE_HEIGHT + chart.labels.len() as f32 * BAR_HEIGHT + 20.0,
+            _ => CHART_HEIGHT,
+        }
+    }
+
+    fn draw(chart: &Chart, height: f32, out: &mut String) {
+        let _ = writeln!(out, "<text x=\"{}\" y=\"20\" font-family=\"sans-serif\" font-size=\"15\" font-weight=\"bold\" fill=\"#222\">{}</text>",
+                         MARGIN_LEFT, escape(&chart.title, &XML_ESCAPES));
+
+        if chart.is_empty() {
+            let _ = writeln!(out, "<text x=\"{}\" y=\"50\" {}>No data to plot</text>", MARGIN_LEFT, TEXT_STYLE);
+            return;
+        }
+
+        match chart.kind {
+            ChartKind::Columns => SvgPlotter::columns(chart, height, false, out),
+            ChartKind::Stacked => SvgPlotter::columns(chart, height, true, out),
+            ChartKind::Bars => SvgPlotter::bars(chart, out),
+            ChartKind::Line => SvgPlotter::line(chart, height, out),
+        }
+    }
+
+    fn columns(chart: &Chart, height: f32, stacked: bool, out: &mut String) {
+        let series = if stacked { &chart.series[..] } else { &chart.series[..1] };
+        let max = (0..chart.labels.len())
+            .map(|i| series.iter().map(|s| s.values.get(i).cloned().unwrap_or(0.0).max(0.0)).sum::<f32>())
+            .fold(0.0, f32::max);
+        let plot = Plot::new(height, 0.0, max);
+
+        plot.grid(&chart.unit, out);
+
+        let slot = plot.width() / chart.labels.len() as f32;
+        let bar = (slot * 0.7).max(1.0);
+        for i in 0..chart.labels.len() {
+            let x = MARGIN_LEFT + slot * i as f32 + (slot - bar) / 2.0;
+            let mut base = 0.0;
+
+            for (si, s) in series.iter().enumerate() {
+                let v = s.values.get(i).cloned().unwrap_or(0.0).max(0.0);
+                let (top, bottom) = (plot.y(base + v), plot.y(base));
+                let _ = writeln!(out, "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"><title>{} {}: {:.2}{}</title></rect>",
+                                 x, top, bar, bottom - top, PALETTE[si % PALETTE.len()], escape(&chart.labels[i], &XML_ESCAPES), escape(&s.name, &XML_ESCAPES), v, escape(&chart.unit, &XML_ESCAPES));
+                base += v;
+            }
+        }
+
+        plot.labels(&chart.labels, out);
+        plot.markers(&chart.markers, chart.labels.len(), out);
+        if stacked {
+            plot.legend(chart, out);
+        }
+    }
+
+    fn bars(chart: &Chart, out: &mut String) {
+        let values: Vec<f32> = chart.series[0].values.iter().map(|v| v.max(0.0)).collect();
+        let max = values.iter().cloned().fold(0.0, f32::max);
+        let total: f32 = values.iter().sum();
+
+        let label_width = 140.0;
+        let text_width = 130.0;
+        let bar_width = WIDTH - label_width - text_width - MARGIN_RIGHT;
+
+        for (i, (label, v)) in chart.labels.iter().zip(values.iter()).enumerate() {
+            let y = TITLE_HEIGHT + i as f32 * BAR_HEIGHT;
+            let length = if max > 0.0 { v / max * bar_width } else { 0.0 };
+            let share = if total > 0.0 { v / total * 100.0 } else { 0.0 };
+
+            let _ = writeln!(out, "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\" {}>{}</text>", label_width - 8.0, y + 15.0, TEXT_STYLE, escape(label, &XML_ESCAPES));
+            let _ = writeln!(out, "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"/>", label_width, y + 3.0, length, BAR_HEIGHT - 6.0, PALETTE[0]);
+            let _ = writeln!(out, "<text x=\"{:.1}\" y=\"{:.1}\" {}>{:.2}{} ({:.1}%)</text>", label_width + length + 6.0, y + 15.0, TEXT_STYLE, v, escape(&chart.unit, &XML_ESCAPES), share);
+        }
+    }
+
+    fn line(chart: &Chart, height: f32, out: &mut String) {
+        let all: Vec<f32> = chart.series.iter().flat_map(|s| s.values.iter().cloned()).collect();
+        let min = all.iter().cloned().fold(f32::MAX, f32::min);
+        let max = all.iter().cloned().fold(f32::MIN, f32::max);
+        // Leave some room below the lowest point without inventing negative values.
+        let min = (min - (max - min) * 0.1).max(min.min(0.0));
+        let plot = Plot::new(height, min, max);
+
+        plot.grid(&chart.unit, out);
+
+        let slot = plot.width() / chart.labels.len() as f32;
+        for (si, s) in chart.series.iter().enumerate() {
+            let color = PALETTE[si % PALETTE.len()];
+            let points: Vec<String> = s.values.iter().enumerate()
+                .map(|(i, v)| format!("{:.1},{:.1}", MARGIN_LEFT + slot * (i as f32 + 0.5), plot.y(*v)))
+                .collect();
+
+            let _ = writeln!(out, "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>", points.join(" "), color);
+            for (i, v) in s.values.iter().enumerate() {
+                let _ = writeln!(out, "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"{}\"><title>{} {}: {:.2}{}</title></circle>",
+                                 MARGIN_LEFT + slot * (i as f32 + 0.5), plot.y(*v), color, escape(&chart.labels[i], &XML_ESCAPES), escape(&s.name, &XML_ESCAPES), v, escape(&chart.unit, &XML_ESCAPES));
+            }
+        }
+
+        plot.labels(&chart.labels, out);
+        plot.markers(&chart.markers, chart.labels.len(), out);
+        if chart.series.len() > 1 {
+            plot.legend(chart, out);
+        }
+    }
+}
+
+struct Plot {
+    height: f32,
+    min: f32,
+    max: f32,
+}
+
+impl Plot {
+    fn new(height: f32, min: f32, max: f32) -> Self {
+        let max = if max - min > f32::EPSILON { max + (max - min) * 0.05 } else { min + 1.0 };
+        Self { height, min, max }
+    }
+
+    fn width(&self) -> f32 {
+        WIDTH - MARGIN_LEFT - MARGIN_RIGHT
+    }
+
+    fn bottom(&self) -> f32 {
+        self.height - MARGIN_BOTTOM
+    }
+
+    fn y(&self, value: f32) -> f32 {
+        let top = TITLE_HEIGHT + 10.0;
+        self.bottom() - (value - self.min) / (self.max - self.min) * (self.bottom() - top)
+    }
+
+    fn grid(&self, unit: &str, out: &mut String) {
+        for i in 0..=GRID_LINES {
+            let value = self.min + (self.max - self.min) * i as f32 / GRID_LINES as f32;
+            let y = self.y(value);
+            let _ = writeln!(out, "<line x1=\"{}\" y1=\"{:.1}\" x2=\"{}\" y2=\"{:.1}\" stroke=\"#ddd\"/>", MARGIN_LEFT, y, WIDTH - MARGIN_RIGHT, y);
+            let _ = writeln!(out, "<text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\" {}>{:.0}{}</text>", MARGIN_LEFT - 6.0, y + 4.0, TEXT_STYLE, value, escape(unit, &XML_ESCAPES));
+        }
+    }
+
+    fn labels(&self, labels: &[String], out: &mut String) {
+        let slot = self.width() / labels.len() as f32;
+        // Skip labels when they would overlap, always keeping the first one.
+        let step = ((50.0 / slot).ceil() as usize).max(1);
+
+        for (i, l) in labels.iter().enumerate().step_by(step) {
+            let _ = writeln!(out, "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\" {}>{}</text>",
+                             MARGIN_LEFT + slot * (i as f32 + 0.5), self.bottom() + 16.0, TEXT_STYLE, escape(l, &XML_ESCAPES));
+        }
+    }
+
+    fn markers(&self, markers: &[(usize, String)], count: usize, out: &mut String) {
+        let slot = self.width() / count as f32;
+
+        for (i, text) in markers {
+            let x = MARGIN_LEFT + slot * (*i as f32 + 0.5);
+            let _ = writeln!(out, "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"#999\" stroke-dasharray=\"4 3\"/>",
+                             x, TITLE_HEIGHT + 5.0, x, self.bottom());
+            let _ = writeln!(out, "<text x=\"{:.1}\" y=\"{:.1}\" {}>{}</text>", x + 4.0, TITLE_HEIGHT + 14.0, TEXT_STYLE, escape(text, &XML_ESCAPES));
+        }
+    }
+
+    fn legend(&self, chart: &Chart, out: &mut String) {
+        let mut x = MARGIN_LEFT;
+        let y = self.bottom() + 34.0;
+
+        for (i, s) in chart.series.iter().enumerate() {
+            let _ = writeln!(out, "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"10\" height=\"10\" fill=\"{}\"/>", x, y - 9.0, PALETTE[i % PALETTE.len()]);
+            let _ = writeln!(out, "<text x=\"{:.1}\" y=\"{:.1}\" {}>{}</text>", x + 14.0, y, TEXT_STYLE, escape(&s.name, &XML_ESCAPES));
+            x += 30.0 + s.name.chars().count() as f32 * 7.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::charts::Series;
+
+    #[test]
+    fn text_is_escaped() {
+        let chart = Chart::new("Food & <drinks>", ChartKind::Bars, vec![String::from("\"Bar\"")], vec![Series::new("Spent", vec![10.0])]);
+        let svg = SvgPlotter::render(&[chart]);
+
+        assert!(svg.contains(">Food &amp; &lt;drinks&gt;</text>"));
+        assert!(svg.contains(">&quot;Bar&quot;</text>"));
+        assert!(!svg.contains("<drinks>"));
+    }
+
+    #[test]
+    fn negative_values_draw_no_columns() {
+        let labels = vec![String::from("2022-01"), String::from("2022-02")];
+        let chart = Chart::new("Spent", ChartKind::Stacked, labels, vec![
+            Series::new("A", vec![-5.0, 10.0]),
+            Series::new("B", vec![5.0, 0.0]),
+        ]);
+        let svg = SvgPlotter::render_inline(&[chart]);
+
+        let heights: Vec<f32> = svg.lines()
+            .filter(|l| l.contains("<title>"))
+            .map(|l| l.split("height=\"").nth(1).unwrap().split('"').next().unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(heights.len(), 4);
+        assert_eq!(heights[0], 0.0);
+        assert_eq!(heights[3], 0.0);
+        // Heights are proportional to the values.
+        assert!((heights[2] / heights[1] - 2.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn empty_charts_say_so() {
+        let chart = Chart::new("Nothing", ChartKind::Line, Vec::new(), Vec::new());
+        let svg = SvgPlotter::render_inline(&[chart]);
+        assert!(svg.contains(">No data to plot</text>"));
+        assert!(svg.contains(&format!("height=\"{:.0}\"", CHART_HEIGHT)));
+    }
+}
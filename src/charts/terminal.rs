@@ -0,0 +1,396 @@
+use std::fmt::Write;
+
+use crate::charts::{Chart, ChartKind, Charset};
+
+const DEFAULT_WIDTH: u16 = 80;
+const COLUMNS_HEIGHT: usize = 10;
+const MAX_LABEL_WIDTH: usize = 20;
+
+const VERTICAL_EIGHTHS: [char; 8] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇'];
+const HORIZONTAL_EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+const UNICODE_POINTS: [char; 3] = ['●', '◆', '■'];
+const ASCII_POINTS: [char; 3] = ['*', 'o', '+'];
+const UNICODE_FILLS: [char; 4] = ['█', '▓', '░', '▒'];
+const ASCII_FILLS: [char; 4] = ['#', '=', '.', '+'];
+
+pub struct TerminalPlotter {
+    width: usize,
+    charset: Charset,
+}
+
+impl TerminalPlotter {
+    pub fn new(width: Option<u16>, charset: Charset) -> Self {
+        Self {
+            width: width.unwrap_or(DEFAULT_WIDTH).max(40) as usize,
+            charset,
+        }
+    }
+
+    pub fn draw_all(&self, charts: &[Chart]) -> String {
+        charts.iter().map(|c| self.draw(c)).collect::<Vec<String>>().join("\n")
+    }
+
+    pub fn draw(&self, chart: &Chart) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "{}:", chart.title);
+
+        if chart.is_empty() {
+            let _ = writeln!(out, "  No data to plot");
+            return out;
+        }
+
+        match chart.kind {
+            ChartKind::Columns => self.columns(chart, &mut out),
+            ChartKind::Bars => self.bars(chart, &mut out),
+            ChartKind::Line => self.line(chart, &mut out),
+            ChartKind::Stacked => self.stacked(chart, &mut out),
+        }
+
+        out
+    }
+
+    fn full_block(&self) -> char {
+        match self.charset {
+            Charset::Unicode => '█',
+            Charset::Ascii => '#',
+        }
+    }
+
+    fn columns(&self, chart: &Chart, out: &mut String) {
+        let values: Vec<f32> = chart.series[0].values.iter().map(|v| v.max(0.0)).collect();
+        let max = values.iter().cloned().fold(0.0, f32::max);
+
+        let axis_width = format!("{:.0}", max).len().max(1);
+        let available = self.width.saturating_sub(axis_width + 3).max(1);
+
+        // Keep the most recent columns when there are more than the terminal can fit.
+        let skip = values.len().saturating_sub(available);
+        let values = &values[skip..];
+        let labels = &chart.labels[skip..];
+
+        let column_width = (available / values.len()).clamp(1, 8);
+        let bar_width = if column_width > 1 { column_width - 1 } else { 1 };
+
+        for row in (0..COLUMNS_HEIGHT).rev() {
+            let axis = match row {
+                r if r == COLUMNS_HEIGHT - 1 => format!("{:.0}", max),
+                0 => String::from("0"),
+                _ => String::new(),
+            };
+            let _ = write!(out, "{:>width$} {} ", axis, self.vertical_axis(), width = axis_width);
+
+            for v in values {
+                let height = if max > 0.0 { v / max * COLUMNS_HEIGHT as f32 } else { 0.0 };
+                let c = self.column_cell(height, row);
+                for _ in 0..bar_width {
+                    out.push(c);
+                }
+                for _ in bar_width..column_width {
+                    out.push(' ');
+                }
+            }
+
+            let trimmed = out.trim_end_matches(' ').len();
+            out.truncate(trimmed);
+            out.push('\n');
+        }
+
+        let _ = writeln!(out, "{:>width$} {}{}", "", self.corner(), self.horizontal_axis().to_string().repeat(values.len() * column_width + 1), width = axis_width);
+
+        self.labels_line(labels, column_width, axis_width, out);
+    }
+
+    fn column_cell(&self, height: f32, row: usize) -> char {
+        let row = row as f32;
+        if height >= row + 1.0 {
+            return self.full_block();
+        }
+
+        if height <= row {
+            return ' ';
+        }
+
+        let fraction = height - row;
+        match self.charset {
+            Charset::Unicode => VERTICAL_EIGHTHS[((fraction * 8.0) as usize).min(7)],
+            Charset::Ascii => if fraction >= 0.5 { '#' } else { ' ' },
+        }
+    }
+
+    fn bars(&self, chart: &Chart, out: &mut String) {
+        let values: Vec<f32> = chart.series[0].values.iter().map(|v| v.max(0.0)).collect();
+        let max = values.iter().cloned().fold(0.0, f32::max);
+        let total: f32 = values.iter().sum();
+
+        let label_width = chart.labels.iter().map(|l| l.chars().count()).max().unwrap_or(0).min(MAX_LABEL_WIDTH);
+        let texts: Vec<String> = values.iter().map(|v| {
+            let share = if total > 0.0 { v / total * 100.0 } else { 0.0 };
+            format!("{:.2} ({:.1}%)", v, share)
+        }).collect();
+        let text_width = texts.iter().map(|t| t.len()).max().unwrap_or(0);
+        let bar_width = self.width.saturating_sub(label_width + text_width + 4).max(1);
+
+        for ((label, v), text) in chart.labels.iter().zip(values.iter()).zip(texts.iter()) {
+            let label: String = label.chars().take(label_width).collect();
+            let length = if max > 0.0 { v / max * bar_width as f32 } else { 0.0 };
+
+            let _ = writeln!(out, "{:<lw$} {} {:<bw$} {:>tw$}",
+                             label, self.vertical_axis(), self.horizontal_bar(length), text,
+                             lw = label_width, bw = bar_width, tw = text_width);
+        }
+    }
+
+    fn line(&self, chart: &Chart, out: &mut String) {
+        let all: Vec<f32> = chart.series.iter().flat_map(|s| s.values.iter().cloned()).collect();
+        let min = all.iter().cloned().fold(f32::MAX, f32::min);
+        let mut max = all.iter().cloned().fold(f32::MIN, f32::max);
+        if (max - min).abs() < f32::EPSILON {
+            max = min + 1.0;
+        }
+
+        let top = format!("{:.0}{}", max, chart.unit);
+        let bottom = format!("{:.0}{}", min, chart.unit);
+        let axis_width = top.len().max(bottom.len());
+        let available = self.width.saturating_sub(axis_width + 3).max(1);
+
+        let skip = chart.labels.len().saturating_sub(available);
+        let count = chart.labels.len() - skip;
+        let column_width = (available / count).clamp(1, 8);
+
+        let mut grid = vec![vec![' '; count * column_width]; COLUMNS_HEIGHT];
+        for (i, s) in chart.series.iter().enumerate() {
+            let symbol = self.point_symbol(i);
+            let mut previous: Option<(usize, f32)> = None;
+
+            for (x, v) in s.values.iter().skip(skip).enumerate() {
+                let x = x * column_width;
+                let row = (v - min) / (max - min) * (COLUMNS_HEIGHT - 1) as f32;
+
+                // Join with the previous point, filling every row crossed so steep changes stay continuous.
+                if let Some((px, prow)) = previous {
+                    let at = |cx: usize| prow + (row - prow) * (cx - px) as f32 / (x - px) as f32;
+                    for cx in (px + 1)..=x {
+                        let (a, b) = (at(cx - 1).round() as usize, at(cx).round() as usize);
+                        let column = if cx == x { cx - 1 } else { cx };
+                        for line in &mut grid[a.min(b)..=a.max(b)] {
+                            let cell = &mut line[column];
+                            if *cell == ' ' {
+                                *cell = self.connector();
+                            }
+                        }
+                    }
+                }
+
+                grid[row.round() as usize][x] = symbol;
+                previous = Some((x, row));
+            }
+        }
+
+        for (row, line) in grid.iter().enumerate().rev() {
+            let axis = match row {
+                r if r == COLUMNS_HEIGHT - 1 => top.as_str(),
+                0 => bottom.as_str(),
+                _ => "",
+            };
+            let line = format!("{:>width$} {} {}", axis, self.vertical_axis(), line.iter().collect::<String>(), width = axis_width);
+            let _ = writeln!(out, "{}", line.trim_end());
+        }
+
+        let _ = writeln!(out, "{:>width$} {}{}", "", self.corner(), self.horizontal_axis().to_string().repeat(count * column_width + 1), width = axis_width);
+
+        let markers: Vec<&(usize, String)> = chart.markers.iter().filter(|(i, _)| *i >= skip).collect();
+        if !markers.is_empty() {
+            let mut line = vec![' '; count * column_width];
+            for (i, _) in &markers {
+                line[(i - skip) * column_width] = self.marker_symbol();
+            }
+            let line: String = line.iter().collect();
+            let _ = writeln!(out, "{:>width$}   {}", "", line.trim_end(), width = axis_width);
+        }
+
+        self.labels_line(&chart.labels[skip..], column_width, axis_width, out);
+
+        if chart.series.len() > 1 {
+            let legend: Vec<String> = chart.series.iter().enumerate().map(|(i, s)| format!("{} {}", self.point_symbol(i), s.name)).collect();
+            let _ = writeln!(out, "{}", legend.join("  "));
+        }
+
+        for (i, text) in markers {
+            let _ = writeln!(out, "{} {}: {}", self.marker_symbol(), chart.labels[*i], text);
+        }
+    }
+
+    fn stacked(&self, chart: &Chart, out: &mut String) {
+        let totals: Vec<f32> = (0..chart.labels.len())
+            .map(|i| chart.series.iter().map(|s| s.values.get(i).cloned().unwrap_or(0.0).max(0.0)).sum())
+            .collect();
+        let max = totals.iter().cloned().fold(0.0, f32::max);
+
+        let label_width = chart.labels.iter().map(|l| l.chars().count()).max().unwrap_or(0).min(MAX_LABEL_WIDTH);
+        let texts: Vec<String> = totals.iter().map(|t| format!("{:.2}{}", t, chart.unit)).collect();
+        let text_width = texts.iter().map(|t| t.len()).max().unwrap_or(0);
+        let marker_width = chart.markers.iter().map(|(_, m)| m.chars().count() + 3).max().unwrap_or(0);
+        let bar_width = self.width.saturating_sub(label_width + text_width + marker_width + 4).max(1);
+
+        for (i, label) in chart.labels.iter().enumerate() {
+            let label: String = label.chars().take(label_width).collect();
+
+            // Round the running sum so the stacked segments always add up to the full bar.
+            let mut bar = String::new();
+            let mut sum = 0.0;
+            let mut drawn = 0;
+            for (si, s) in chart.series.iter().enumerate() {
+                sum += s.values.get(i).cloned().unwrap_or(0.0).max(0.0);
+                let end = if max > 0.0 { (sum / max * bar_width as f32).round() as usize } else { 0 };
+                bar += &self.fill_symbol(si).to_string().repeat(end.saturating_sub(drawn));
+                drawn = drawn.max(end);
+            }
+
+            let mut line = format!("{:<lw$} {} {:<bw$} {:>tw$}", label, self.vertical_axis(), bar, texts[i],
+                                   lw = label_width, bw = bar_width, tw = text_width);
+            for (_, text) in chart.markers.iter().filter(|(m, _)| *m == i) {
+                let _ = write!(line, " {} {}", self.marker_symbol(), text);
+            }
+            let _ = writeln!(out, "{}", line.trim_end());
+        }
+
+        let legend: Vec<String> = chart.series.iter().enumerate().map(|(i, s)| format!("{} {}", self.fill_symbol(i), s.name)).collect();
+        let _ = writeln!(out, "{}", legend.join("  "));
+    }
+
+    fn labels_line(&self, labels: &[String], column_width: usize, axis_width: usize, out: &mut String) {
+        let mut label_line = format!("{:>width$}   ", "", width = axis_width);
+        for l in labels {
+            // Months are labelled YYYY-MM, fall back to just the month when columns are narrow.
+            let text = if l.len() < column_width {
+                l.as_str()
+            } else {
+                l.rsplit('-').next().unwrap_or("")
+            };
+            let text: String = text.chars().take(column_width.saturating_sub(1).max(1)).collect();
+            let _ = write!(label_line, "{:<width$}", text, width = column_width);
+        }
+        let _ = writeln!(out, "{}", label_line.trim_end());
+    }
+
+    fn point_symbol(&self, index: usize) -> char {
+        match self.charset {
+            Charset::Unicode => UNICODE_POINTS[index % UNICODE_POINTS.len()],
+            Charset::Ascii => ASCII_POINTS[index % ASCII_POINTS.len()],
+        }
+    }
+
+    fn fill_symbol(&self, index: usize) -> char {
+        match self.charset {
+            Charset::Unicode => UNICODE_FILLS[index % UNICODE_FILLS.len()],
+            Charset::Ascii => ASCII_FILLS[index % ASCII_FILLS.len()],
+        }
+    }
+
+    fn connector(&self) -> char {
+        match self.charset {
+            Charset::Unicode => '·',
+            Charset::Ascii => '.',
+        }
+    }
+
+    fn marker_symbol(&self) -> char {
+        match self.charset {
+            Charset::Unicode => '▲',
+            Charset::Ascii => '^',
+        }
+    }
+
+    fn horizontal_bar(&self, length: f32) -> String {
+        let full = length as usize;
+        let mut bar = self.full_block().to_string().repeat(full);
+
+        let fraction = length - full as f32;
+        match self.charset {
+            Charset::Unicode => {
+                let c = HORIZONTAL_EIGHTHS[((fraction * 8.0) as usize).min(7)];
+                if c != ' ' {
+                    bar.push(c);
+                }
+            },
+            Charset::Ascii => {
+                if fraction >= 0.5 {
+                    bar.push('#');
+                }
+            },
+        }
+
+        bar
+    }
+
+    fn vertical_axis(&self) -> char {
+        match self.charset {
+            Charset::Unicode => '│',
+            Charset::Ascii => '|',
+        }
+    }
+
+    fn horizontal_axis(&self) -> char {
+        match self.charset {
+            Charset::Unicode => '─',
+            Charset::Ascii => '-',
+        }
+    }
+
+    fn corner(&self) -> char {
+        match self.charset {
+            Charset::Unicode => '└',
+            Charset::Ascii => '+',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::charts::Series;
+
+    fn labels() -> Vec<String> {
+        vec![String::from("2022-01"), String::from("2022-02")]
+    }
+
+    #[test]
+    fn stacked_bars_scale_to_the_largest_total() {
+        let chart = Chart::new("Spent", ChartKind::Stacked, labels(), vec![
+            Series::new("A", vec![20.0, -5.0]),
+            Series::new("B", vec![10.0, 10.0]),
+        ]);
+
+        // 40 columns leave 24 for the bars, negative values count as nothing.
+        assert_eq!(TerminalPlotter::new(Some(40), Charset::Ascii).draw(&chart), "Spent:\n\
+            2022-01 | ################======== 30.00\n\
+            2022-02 | ========                 10.00\n\
+            # A  = B\n");
+    }
+
+    #[test]
+    fn lines_span_from_the_lowest_to_the_highest_value() {
+        let chart = Chart::new("Balance", ChartKind::Line, labels(), vec![Series::new("Balance", vec![-5.0, 5.0])]);
+        let out = TerminalPlotter::new(Some(40), Charset::Ascii).draw(&chart);
+        let lines: Vec<&str> = out.lines().collect();
+
+        assert_eq!(lines.len(), 1 + COLUMNS_HEIGHT + 2);
+        assert!(lines[1].starts_with(" 5 |") && lines[1].ends_with('*'), "{}", out);
+        assert!(lines[COLUMNS_HEIGHT].starts_with("-5 | *"), "{}", out);
+    }
+
+    #[test]
+    fn flat_and_empty_series_do_not_break_the_scale() {
+        let plotter = TerminalPlotter::new(None, Charset::Unicode);
+
+        let flat = Chart::new("Flat", ChartKind::Line, labels(), vec![Series::new("Zero", vec![0.0, 0.0])]);
+        assert!(plotter.draw(&flat).contains("0 │ ●"));
+
+        let columns = Chart::new("Zero", ChartKind::Columns, labels(), vec![Series::new("Zero", vec![0.0, -3.0])]);
+        assert!(!plotter.draw(&columns).contains('█'));
+
+        let empty = Chart::new("Empty", ChartKind::Bars, Vec::new(), vec![Series::new("None", Vec::new())]);
+        assert_eq!(plotter.draw(&empty), "Empty:\n  No data to plot\n");
+    }
+}
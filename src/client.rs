@@ -0,0 +1,364 @@
+//! Library access to a GitGud-Greedy database.
+//!
+//! [`GreedyClient`] wraps the same [`Db`] the commands use and returns plain data instead of
+//! printing, so other Rust tools can script against a database.
+
+use std::fmt::Formatter;
+
+use chrono::NaiveDate;
+
+use crate::models::{Db, LoadError, Name};
+use crate::models::account::Account;
+use crate::models::payroll::Payroll;
+use crate::models::transaction::Transaction;
+use crate::models::validation::PayrollValidator;
+use crate::view_models::payroll_data_vm::PayrollDataVm;
+use crate::view_models::transaction_data_vm::TransactionDataVm;
+
+/// Errors returned by [`GreedyClient`].
+#[derive(Debug)]
+pub enum ClientError {
+    /// The database could not be opened, also when the passphrase is missing or wrong.
+    Open(LoadError),
+    /// The database failed or rejected the query.
+    Db(rusqlite::Error),
+    /// A tag, company, category or row looked up by the client does not exist.
+    NotFound(String),
+    /// The data was rejected before storing it, with every reason found.
+    Invalid(Vec<String>),
+}
+
+impl std::fmt::Display for ClientError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientError::Open(e) => write!(f, "Error opening database: {}", e),
+            ClientError::Db(e) => write!(f, "Database error: {}", e),
+            ClientError::NotFound(what) => write!(f, "{} not found", what),
+            ClientError::Invalid(errors) => write!(f, "Invalid data: {}", errors.join("; ")),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<LoadError> for ClientError {
+    fn from(e: LoadError) -> Self {
+        ClientError::Open(e)
+    }
+}
+
+impl From<rusqlite::Error> for ClientError {
+    fn from(e: rusqlite::Error) -> Self {
+        ClientError::Db(e)
+    }
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
+
+/// Totals for one tag, company or category.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupTotal {
+    pub id: i32,
+    pub name: String,
+    pub count: usize,
+    pub total: f32,
+    pub average: f32,
+}
+
+/// Aggregates of a set of transactions, as shown by `transaction-data`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionSummary {
+    pub count: usize,
+    pub total: f32,
+    pub average: f32,
+    /// Sorted by tag id.
+    pub by_tag: Vec<GroupTotal>,
+}
+
+/// Aggregates of a set of payrolls, as shown by `payroll-data`. Group totals add up the gross.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayrollSummary {
+    pub count: usize,
+    pub gross: f32,
+    pub net: f32,
+    pub ss: f32,
+    pub irpf: f32,
+    /// Sorted by company id.
+    pub by_company: Vec<GroupTotal>,
+    /// Sorted by category id.
+    pub by_category: Vec<GroupTotal>,
+}
+
+/// Net income against expenses, as shown by `balance-summary`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceSummary {
+    pub income: f32,
+    pub expenses: f32,
+    pub total: f32,
+    pub payrolls: PayrollSummary,
+    pub transactions: TransactionSummary,
+}
+
+/// Read and write access to a database without going through the command line.
+///
+/// Every list and report takes the same `year`/`month` filters as the commands: both optional,
+/// but a month needs a year.
+pub struct GreedyClient {
+    db: Db,
+}
+
+impl GreedyClient {
+    /// Opens the database file, creating the tables when missing. Encrypted files need [`GreedyClient::open_encrypted`].
+    pub fn open(path: &str) -> ClientResult<Self> {
+        Ok(Self { db: Db::load(path)? })
+    }
+
+    /// Opens a database that may be encrypted. Changes are written back to the file when the client is dropped.
+    pub fn open_encrypted(path: &str, passphrase: &str) -> ClientResult<Self> {
+        Ok(Self { db: Db::open(path, || Some(String::from(passphrase)))? })
+    }
+
+    /// A new empty database that only lives as long as the client.
+    pub fn in_memory() -> ClientResult<Self> {
+        Self::open(":memory:")
+    }
+
+    /// Wraps a database that is already open, e.g. one loaded by the command line.
+    pub fn from_db(db: Db) -> Self {
+        Self { db }
+    }
+
+    /// The underlying database, for anything the client does not cover.
+    pub fn db(&self) -> &Db {
+        &self.db
+    }
+
+    /// Transactions in the period, oldest first.
+    pub fn transactions(&self, year: Option<u32>, month: Option<u32>) -> ClientResult<Vec<Transaction>> {
+        Ok(self.db.get_transaction_data(year, month)?)
+    }
+
+    /// Payrolls in the period, oldest first.
+    pub fn payrolls(&self, year: Option<u32>, month: Option<u32>) -> ClientResult<Vec<Payroll>> {
+        Ok(self.db.get_payroll_data(year, month)?)
+    }
+
+    /// Every account with its current amount.
+    pub fn accounts(&self) -> ClientResult<Vec<Account>> {
+        Ok(self.db.get_all_accounts()?)
+    }
+
+    /// Every tag.
+    pub fn tags(&self) -> ClientResult<Vec<Name>> {
+        Ok(self.db.get_all_tags()?)
+    }
+
+    /// Every company.
+    pub fn companies(&self) -> ClientResult<Vec<Name>> {
+        Ok(self.db.get_all_companies()?)
+    }
+
+    /// Every payroll category.
+    pub fn categories(&self) -> ClientResult<Vec<Name>> {
+        Ok(self.db.get_all_categories()?)
+    }
+
+    /// Id of the tag with that name, or [`ClientError::NotFound`].
+    pub fn tag_id(&self, name: &str) -> ClientResult<i32> {
+        self.db.get_tag_id(name).map_err(|_| ClientError::NotFound(format!("Tag [{}]", name)))
+    }
+
+    /// Id of the company with that name, or [`ClientError::NotFound`].
+    pub fn company_id(&self, name: &str) -> ClientResult<i32> {
+        self.db.get_company_id(name).map_err(|_| ClientError::NotFound(format!("Company [{}]", name)))
+    }
+
+    /// Id of the category with that name, or [`ClientError::NotFound`].
+    pub fn category_id(&self, name: &str) -> ClientResult<i32> {
+        self.db.get_category_id(name).map_err(|_| ClientError::NotFound(format!("Category [{}]", name)))
+    }
+
+    /// Adds a tag and returns it with its new id. Names are unique.
+    pub fn add_tag(&self, name: &str, description: &str) -> ClientResult<Name> {
+        self.db.insert_tag(name, description)?;
+        Ok(Name::new(self.db.last_insert_id(), String::from(name), String::from(description)))
+    }
+
+    /// Adds a company and returns it with its new id. Names are unique.
+    pub fn add_company(&self, name: &str, description: &str) -> ClientResult<Name> {
+        self.db.insert_company(name, description)?;
+        Ok(Name::new(self.db.last_insert_id(), String::from(name), String::from(description)))
+    }
+
+    /// Adds a payroll category and returns it with its new id. Names are unique.
+    pub fn add_category(&self, name: &str, description: &str) -> ClientResult<Name> {
+        self.db.insert_category(name, description)?;
+        Ok(Name::new(self.db.last_insert_id(), String::from(name), String::from(description)))
+    }
+
+    /// Stores a transaction under an existing tag. Dates are `YYYY-MM-DD` (or with slashes).
+    pub fn add_transaction(&self, name: &str, date: &str, amount: f32, tag: &str) -> ClientResult<Transaction> {
+        let date = NaiveDate::parse_from_str(&date.trim().replace('/', "-"), "%Y-%m-%d")
+            .map_err(|e| ClientError::Invalid(vec![format!("Invalid date [{}], expected YYYY-MM-DD. E: {}", date, e)]))?;
+        if !amount.is_finite() {
+            return Err(ClientError::Invalid(vec![format!("Invalid amount [{}]", amount)]));
+        }
+
+        let transaction = Transaction::new(&String::from(name), &date.format("%Y-%m-%d").to_string(), amount, self.tag_id(tag)?);
+        self.db.insert_transaction(&transaction)?;
+
+        Ok(self.db.get_transaction(self.db.last_insert_id())?)
+    }
+
+    /// Validates the payroll like `add-payroll` does and stores it. Returns the stored payroll and
+    /// the validation warnings; rejected payrolls come back as [`ClientError::Invalid`].
+    pub fn add_payroll(&self, mut payroll: Payroll, tolerance: f32) -> ClientResult<(Payroll, Vec<String>)> {
+        let validator = PayrollValidator { tolerance, record_other: false };
+        let warnings = validator.validate(&self.db, &mut payroll).map_err(ClientError::Invalid)?;
+
+        self.db.insert_payroll(&payroll)?;
+        Ok((self.db.get_payroll(self.db.last_insert_id())?, warnings))
+    }
+
+    /// Adds an account with its starting amount and returns it as stored.
+    pub fn add_account(&self, name: &str, amount: f32, description: &str) -> ClientResult<Account> {
+        self.db.insert_account(&Account::new(name, amount, description))?;
+        Ok(self.db.get_account_by_id(self.db.last_insert_id())?)
+    }
+
+    /// Count, total and average of the transactions in the period, also per tag.
+    pub fn transaction_summary(&self, year: Option<u32>, month: Option<u32>) -> ClientResult<TransactionSummary> {
+        let transactions = self.transactions(year, month)?;
+        Ok(self.summarize_transactions(&TransactionDataVm::generate(&transactions)))
+    }
+
+    /// Totals of the payrolls in the period, also per company and per category.
+    pub fn payroll_summary(&self, year: Option<u32>, month: Option<u32>) -> ClientResult<PayrollSummary> {
+        let payrolls = self.payrolls(year, month)?;
+        Ok(self.summarize_payrolls(&PayrollDataVm::generate(&payrolls)))
+    }
+
+    /// Net payroll income against transaction expenses for the period.
+    pub fn balance_summary(&self, year: Option<u32>, month: Option<u32>) -> ClientResult<BalanceSummary> {
+        let payrolls = self.payroll_summary(year, month)?;
+        let transactions = self.transaction_summary(year, month)?;
+
+        Ok(BalanceSummary {
+            income: payrolls.net,
+            expenses: transactions.total,
+            total: payrolls.net - transactions.total,
+            payrolls,
+            transactions,
+        })
+    }
+
+    fn summarize_transactions(&self, vm: &TransactionDataVm) -> TransactionSummary {
+        TransactionSummary {
+            count: vm.count(),
+            total: vm.total(),
+            average: vm.average(),
+            by_tag: group_totals(vm.tag_groups(), |id| self.db.get_tag_str(id)),
+        }
+    }
+
+    fn summarize_payrolls(&self, vm: &PayrollDataVm) -> PayrollSummary {
+        let (gross, net, ss, irpf) = vm.totals();
+
+        PayrollSummary {
+            count: vm.count(),
+            gross,
+            net,
+            ss,
+            irpf,
+            by_company: group_totals(vm.company_groups(), |id| self.db.get_company_str(id)),
+            by_category: group_totals(vm.category_groups(), |id| self.db.get_category_str(id)),
+        }
+    }
+}
+
+fn group_totals(groups: Vec<(i32, usize, f32)>, name: impl Fn(i32) -> rusqlite::Result<String>) -> Vec<GroupTotal> {
+    groups.into_iter().map(|(id, count, total)| GroupTotal {
+        id,
+        name: name(id).unwrap_or_else(|_| String::from("Unknown")),
+        count,
+        total,
+        average: total / count as f32,
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> GreedyClient {
+        let client = GreedyClient::in_memory().unwrap();
+        client.add_tag("Food", "").unwrap();
+        client.add_tag("Rent", "").unwrap();
+        client.add_company("Acme", "").unwrap();
+        client.add_company("Initech", "").unwrap();
+        client.add_category("Engineer", "").unwrap();
+
+        client.add_transaction("Market", "2022-03-05", 40.0, "Food").unwrap();
+        client.add_transaction("Bakery", "2022/03/20", 10.0, "Food").unwrap();
+        client.add_transaction("Flat", "2022-03-01", 700.0, "Rent").unwrap();
+        client.add_transaction("Market", "2022-04-02", 30.0, "Food").unwrap();
+
+        for (date, gross, net, company) in [("2022-03-31", 2000.0, 1500.0, 1), ("2022-03-31", 1000.0, 800.0, 2), ("2022-04-30", 2000.0, 1500.0, 1)] {
+            let ss = gross * 0.05;
+            let payroll = Payroll::new(&String::from(date), gross, net, ss, gross - net - ss, company, 1);
+            client.add_payroll(payroll, 0.01).unwrap();
+        }
+        client
+    }
+
+    #[test]
+    fn empty_period_has_no_totals_nor_groups() {
+        let client = client();
+
+        let transactions = client.transaction_summary(Some(2021), None).unwrap();
+        assert_eq!(transactions.count, 0);
+        assert_eq!(transactions.total, 0.0);
+        assert!(transactions.by_tag.is_empty());
+
+        let balance = client.balance_summary(Some(2021), Some(1)).unwrap();
+        assert_eq!(balance.total, 0.0);
+        assert_eq!(balance.payrolls.count, 0);
+        assert!(balance.payrolls.by_company.is_empty());
+        assert!(balance.payrolls.by_category.is_empty());
+    }
+
+    #[test]
+    fn transactions_are_grouped_per_tag() {
+        let summary = client().transaction_summary(Some(2022), Some(3)).unwrap();
+        assert_eq!(summary.count, 3);
+        assert_eq!(summary.total, 750.0);
+        assert_eq!(summary.by_tag, vec![
+            GroupTotal { id: 1, name: String::from("Food"), count: 2, total: 50.0, average: 25.0 },
+            GroupTotal { id: 2, name: String::from("Rent"), count: 1, total: 700.0, average: 700.0 },
+        ]);
+    }
+
+    #[test]
+    fn payrolls_are_grouped_per_company_and_balanced_against_expenses() {
+        let client = client();
+
+        let summary = client.payroll_summary(Some(2022), None).unwrap();
+        assert_eq!((summary.count, summary.gross, summary.net), (3, 5000.0, 3800.0));
+        assert_eq!(summary.by_company, vec![
+            GroupTotal { id: 1, name: String::from("Acme"), count: 2, total: 4000.0, average: 2000.0 },
+            GroupTotal { id: 2, name: String::from("Initech"), count: 1, total: 1000.0, average: 1000.0 },
+        ]);
+        assert_eq!(summary.by_category.len(), 1);
+        assert_eq!(summary.by_category[0].total, 5000.0);
+
+        let balance = client.balance_summary(Some(2022), Some(3)).unwrap();
+        assert_eq!((balance.income, balance.expenses, balance.total), (2300.0, 750.0, 1550.0));
+    }
+
+    #[test]
+    fn unknown_names_are_not_found() {
+        let client = client();
+        assert!(matches!(client.tag_id("Travel"), Err(ClientError::NotFound(_))));
+        assert!(matches!(client.add_transaction("Train", "2022-03-05", 20.0, "Travel"), Err(ClientError::NotFound(_))));
+    }
+}
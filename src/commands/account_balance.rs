@@ -1,10 +1,12 @@
 use clap::Parser;
 use log;
 
-use crate::commands::sub_cmd::SubCmd;
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
 use crate::models::Db;
 use crate::commons::Opts;
 use crate::models::account::Account;
+use crate::renderer::Report;
+use crate::view_models::accounts_vm::AccountsVm;
 
 #[derive(Parser, Debug)]
 pub struct AddAccount {
@@ -15,14 +17,16 @@ pub struct AddAccount {
 }
 
 impl SubCmd for AddAccount {
-    fn execute(&self, db: &Db, _opts: &Opts) {
+    fn execute(&self, db: &Db, _opts: &Opts) -> CmdResult {
         let account = Account::new(&self.name, self.amount, &self.description);
-        db.insert_account(&account).unwrap_or_else(|e| {
+        db.insert_account(&account).map_err(|e| {
             log::error!("Error inserting account [{:?}]: {}", account, e);
-            std::process::exit(1)
-        });
+            CmdError(1)
+        })?;
         
         log::info!("Account [{:?}] inserted successfully", account);
+        
+        Ok(())
     }
 }
 
@@ -33,39 +37,27 @@ pub struct GetAccount {
 }
 
 impl SubCmd for GetAccount {
-    fn execute(&self, _db: &Db, _opts: &Opts) {
-        if self.name == "all" {
-            self.execute_all(_db);
-            return;
-        }
-
-        // TODO: Use a vm
-        let account = _db.get_account(&self.name).unwrap_or_else(|e| {
-            log::error!("Error getting account data ({}): {}", self.name, e);
-            std::process::exit(0)
-        });
-
-        log::info!("[{}]: {} - ({}) | {}", account._id, account.name, account.amount, account.description);
-    }
-}
-
-impl GetAccount {
-    fn execute_all(&self, db: &Db) {
-        let accounts = db.get_all_accounts().unwrap_or_else(|e| {
-            log::error!("Error getting all account data: {}", e);
-            std::process::exit(0)
-        });
-        
-        // TODO: Use a vm
-        
-        let mut total: f32 = 0.0;
-        for a in &accounts {
-            total += a.amount;
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let accounts = if self.name == "all" {
+            db.get_all_accounts().map_err(|e| {
+                log::error!("Error getting all account data: {}", e);
+                CmdError(1)
+            })?
+        } else {
+            let account = db.get_account(&self.name).map_err(|e| {
+                log::error!("Error getting account data ({}): {}", self.name, e);
+                CmdError(1)
+            })?;
             
-            log::info!("[{}]: {} - ({}) | {}", a._id, a.name, a.amount, a.description);
-        }
+            vec![account]
+        };
+        
+        let vm = AccountsVm::generate(&accounts);
+        let mut report = Report::new();
+        vm.render(&mut report);
+        opts.get_renderer().print(&report);
         
-        log::info!("Total: {}", total);
+        Ok(())
     }
 }
 
@@ -76,7 +68,7 @@ pub struct SetAccountBalance {
 }
 
 impl SubCmd for SetAccountBalance {
-    fn execute(&self, _db: &Db, _opts: &Opts) {
+    fn execute(&self, _db: &Db, _opts: &Opts) -> CmdResult {
         todo!()
     }
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
 use clap::Parser;
 use log;
 
-use crate::commands::sub_cmd::SubCmd;
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
 use crate::models::Db;
 use crate::commons::Opts;
 
@@ -13,13 +13,15 @@ pub struct AddTag {
 }
 
 impl SubCmd for AddTag {
-    fn execute(&self, db: &Db, _opts: &Opts) {
-        db.insert_tag(&self.name, &self.description).unwrap_or_else(|e| {
+    fn execute(&self, db: &Db, _opts: &Opts) -> CmdResult {
+        db.insert_tag(&self.name, &self.description).map_err(|e| {
             log::error!("Error inserting tag: {}", e);
-            std::process::exit(1);
-        });
+            CmdError(1)
+        })?;
         
         log::info!("Tag [{:?}] inserted successfully", self);
+        
+        Ok(())
     }
 }
 
@@ -31,13 +33,15 @@ pub struct AddCompany {
 }
 
 impl SubCmd for AddCompany {
-    fn execute(&self, db: &Db, _opts: &Opts) {
-        db.insert_company(&self.name, &self.description).unwrap_or_else(|e| {
+    fn execute(&self, db: &Db, _opts: &Opts) -> CmdResult {
+        db.insert_company(&self.name, &self.description).map_err(|e| {
             log::error!("Error inserting company: {}", e);
-            std::process::exit(1);
-        });
+            CmdError(1)
+        })?;
 
         log::info!("Company [{:?}] inserted successfully", self);
+        
+        Ok(())
     }
 }
 
@@ -49,12 +53,14 @@ pub struct AddCategory {
 }
 
 impl SubCmd for AddCategory {
-    fn execute(&self, db: &Db, _opts: &Opts) {
-        db.insert_category(&self.name, &self.description).unwrap_or_else(|e| {
+    fn execute(&self, db: &Db, _opts: &Opts) -> CmdResult {
+        db.insert_category(&self.name, &self.description).map_err(|e| {
             log::error!("Error inserting category: {}", e);
-            std::process::exit(1);
-        });
+            CmdError(1)
+        })?;
 
         log::info!("Category [{:?}] inserted successfully", self);
+        
+        Ok(())
     }
 }
@@ -1,10 +1,52 @@
 use clap::Parser;
 use log;
 
-use crate::commands::sub_cmd::{SubCmd, ask_parameter};
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::commands::prompt;
 use crate::models::Db;
 use crate::commons::Opts;
 use crate::models::payroll::Payroll;
+use crate::models::validation::PayrollValidator;
+use crate::renderer::Value;
+
+// Validation settings shared by every command that stores payrolls.
+#[derive(Parser, Debug)]
+pub struct PayrollChecks {
+    // Allowed difference between gross - ss - irpf and net. Defaults to payroll.tolerance from the config, or 0.01
+    #[clap(long)]
+    tolerance: Option<f32>,
+    // Store the difference between gross - ss - irpf and net as other deductions instead of rejecting
+    #[clap(long)]
+    other_deductions: bool,
+}
+
+impl PayrollChecks {
+    pub fn validator(&self, opts: &Opts) -> PayrollValidator {
+        PayrollValidator {
+            tolerance: self.tolerance.unwrap_or_else(|| opts.get_payroll_tolerance()),
+            record_other: self.other_deductions,
+        }
+    }
+    
+    // Logs the warnings and fails when the payroll is rejected.
+    pub fn validate(&self, db: &Db, opts: &Opts, payroll: &mut Payroll) -> CmdResult {
+        match self.validator(opts).validate(db, payroll) {
+            Ok(warnings) => {
+                for w in warnings {
+                    log::warn!("{}", w);
+                }
+                Ok(())
+            },
+            Err(errors) => {
+                for e in errors {
+                    log::error!("{}", e);
+                }
+                log::error!("Payroll rejected");
+                Err(CmdError(1))
+            },
+        }
+    }
+}
 
 #[derive(Parser, Debug)]
 pub struct AddPayroll {
@@ -15,56 +57,87 @@ pub struct AddPayroll {
     irpf: f32,
     company: String,
     category_id: i32,
+    #[clap(flatten)]
+    checks: PayrollChecks,
 }
 
 impl SubCmd for AddPayroll {
-    fn execute(&self, db: &Db, _opts: &Opts) {
-        let company_id = db.get_company_id(&self.company).unwrap_or_else(|e| {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let company_id = db.get_company_id(&self.company).map_err(|e| {
             log::error!("Could not find id for company {}. Error: {}", self.company, e);
-            std::process::exit(0);
-        });
+            CmdError(1)
+        })?;
         
-        // TODO: Validate date is properly set. YYYY-MM-DD
-        // TODO: Validate all arguments. Maybe things like values are positive and higher than 0
+        let mut model = Payroll::new(&self.date, self.gross, self.net, self.ss, self.irpf, company_id, self.category_id);
+        self.checks.validate(db, opts, &mut model)?;
         
-        let model = Payroll::new(&self.date, self.gross, self.net, self.ss, self.irpf, company_id, self.category_id);
-        db.insert_payroll(&model).unwrap_or_else(|e| {
+        db.insert_payroll(&model).map_err(|e| {
             log::error!("Error inserting payroll: {}", e);
-            std::process::exit(0);
-        });
+            CmdError(1)
+        })?;
         
         log::info!("Payroll [{:?}] inserted successfully", model);
+        
+        Ok(())
     }
 }
 
 #[derive(Parser, Debug)]
-pub struct AddPayrollP;
+pub struct AddPayrollP {
+    #[clap(flatten)]
+    checks: PayrollChecks,
+}
 
 impl SubCmd for AddPayrollP {
-    fn execute(&self, db: &Db, _opts: &Opts) {
-        let date = ask_parameter::<String>("date");
-        let gross = ask_parameter::<f32>("gross");
-        let net = ask_parameter::<f32>("net");
-        let ss = ask_parameter::<f32>("ss");
-        let irpf = ask_parameter::<f32>("irpf");
-        let company = ask_parameter::<String>("company");
-        let company = db.get_company_id(&company).unwrap_or_else(|e| {
-            log::error!("Could not find company id for company {}. Error: {}", company, e);
-            std::process::exit(0);
-        });
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let companies = db.get_all_companies().map_err(|e| {
+            log::error!("Error getting companies list. Error: {}", e);
+            CmdError(1)
+        })?;
+        let categories = db.get_all_categories().map_err(|e| {
+            log::error!("Error getting categories list. Error: {}", e);
+            CmdError(1)
+        })?;
+        
+        let company = prompt::pick("Company", &companies, None)?;
+        
+        // The last payslip from the same company is usually the best guess for the next one.
+        let previous = db.get_company_payrolls(company.id).ok().and_then(|mut p| p.pop());
+        let previous_category = previous.as_ref().and_then(|p| db.get_category_str(p.category_id).ok());
         
-        let category_id = ask_parameter::<i32>("category_id");
+        let date = prompt::ask_date("Date", None)?;
+        let gross = prompt::ask_amount("Gross", previous.as_ref().map(|p| p.gross))?;
+        let net = prompt::ask_amount("Net", previous.as_ref().map(|p| p.net))?;
+        let ss = prompt::ask_amount("SS", previous.as_ref().map(|p| p.ss))?;
+        let irpf = prompt::ask_amount("Irpf", previous.as_ref().map(|p| p.irpf))?;
+        let category = prompt::pick("Category", &categories, previous_category.as_deref())?;
 
-        // TODO: Validate date is properly set. YYYY-MM-DD
-        // TODO: Validate parameters
+        let mut payroll = Payroll::new(&date, gross, net, ss, irpf, company.id, category.id);
+        self.checks.validate(db, opts, &mut payroll)?;
         
-        let payroll = Payroll::new(&date, gross, net, ss, irpf, company, category_id);
-        db.insert_payroll(&payroll).unwrap_or_else(|e| {
+        let confirmed = prompt::confirm_summary(opts, "New payroll", vec![
+            ("Date", Value::date(&payroll.date)),
+            ("Company", Value::from(company.name.as_str())),
+            ("Category", Value::from(category.name.as_str())),
+            ("Gross", Value::from(payroll.gross)),
+            ("Net", Value::from(payroll.net)),
+            ("SS", Value::from(payroll.ss)),
+            ("Irpf", Value::from(payroll.irpf)),
+            ("Other", Value::from(payroll.other)),
+        ])?;
+        if !confirmed {
+            log::info!("Payroll discarded");
+            return Ok(());
+        }
+        
+        db.insert_payroll(&payroll).map_err(|e| {
             log::error!("Error inserting payroll: {}", e);
-            std::process::exit(0);
-        });
+            CmdError(1)
+        })?;
         
         log::info!("Payroll [{:?}] inserted successfully", payroll);
+        
+        Ok(())
     }
 }
 
@@ -74,7 +147,7 @@ pub struct RepeatPayroll {
 }
 
 impl SubCmd for RepeatPayroll {
-    fn execute(&self, _db: &Db, _opts: &Opts) {
+    fn execute(&self, _db: &Db, _opts: &Opts) -> CmdResult {
         todo!()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,87 @@
+use clap::Parser;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::commands::prompt;
+use crate::models::Db;
+use crate::commons::Opts;
+use crate::models::payroll_item::{PayrollItem, ItemKind};
+use crate::models::validation::PayrollValidator;
+
+#[derive(Parser, Debug)]
+pub struct AddPayrollItem {
+    payroll_id: i32,
+    concept: String,
+    amount: f32,
+    // earning or deduction
+    #[clap(short, long, default_value="earning")]
+    kind: ItemKind,
+    // The item does not count for IRPF, e.g. exempt meal vouchers
+    #[clap(long)]
+    no_irpf: bool,
+    // The item does not count for the SS contribution base
+    #[clap(long)]
+    no_ss: bool,
+    // Allowed difference between the items and the payroll totals. Defaults to payroll.tolerance from the config, or 0.01
+    #[clap(long)]
+    tolerance: Option<f32>,
+}
+
+impl SubCmd for AddPayrollItem {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let item = PayrollItem::new(self.payroll_id, &self.concept, self.kind, self.amount, !self.no_irpf, !self.no_ss);
+        insert_item(db, &item, self.tolerance.unwrap_or_else(|| opts.get_payroll_tolerance()))?;
+        
+        Ok(())
+    }
+}
+
+#[derive(Parser, Debug)]
+pub struct AddPayrollItemP;
+
+impl SubCmd for AddPayrollItemP {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let payroll_id = prompt::ask_with("Payroll id", None, |answer| {
+            let id = answer.parse::<i32>().map_err(|e| format!("Invalid id [{}]: {}", answer, e))?;
+            db.get_payroll(id).map(|p| p._id).map_err(|_| format!("Payroll {} not found", id))
+        })?;
+        let concept = prompt::ask_text("Concept", None)?;
+        let kind = prompt::ask::<ItemKind>("Type (earning/deduction)", Some(ItemKind::Earning))?;
+        let amount = prompt::ask_amount("Amount", None)?;
+        let irpf_taxable = prompt::confirm("Irpf taxable?", true)?;
+        let ss_taxable = prompt::confirm("SS taxable?", true)?;
+        
+        let item = PayrollItem::new(payroll_id, &concept, kind, amount, irpf_taxable, ss_taxable);
+        insert_item(db, &item, opts.get_payroll_tolerance())?;
+        
+        Ok(())
+    }
+}
+
+fn insert_item(db: &Db, item: &PayrollItem, tolerance: f32) -> CmdResult {
+    if item.amount < 0.0 {
+        log::error!("Item amount must be non-negative, got {}", item.amount);
+        return Err(CmdError(1));
+    }
+    
+    let payroll = db.get_payroll(item.payroll_id).map_err(|e| {
+        log::error!("Could not find payroll {}. Error: {}", item.payroll_id, e);
+        CmdError(1)
+    })?;
+    
+    // Items are usually added one by one, so a mismatch is only a warning until the payslip is complete.
+    let mut items = db.get_payroll_items(item.payroll_id).unwrap_or_default();
+    items.push(item.clone());
+    let validator = PayrollValidator { tolerance, record_other: false };
+    for w in validator.validate_items(&payroll, &items) {
+        log::warn!("{}", w);
+    }
+    
+    db.insert_payroll_item(item).map_err(|e| {
+        log::error!("Error inserting payroll item: {}", e);
+        CmdError(1)
+    })?;
+    
+    log::info!("Payroll item [{:?}] inserted successfully", item);
+    
+    Ok(())
+}
@@ -1,10 +1,12 @@
 use clap::Parser;
 use log;
 
-use crate::commands::sub_cmd::{SubCmd, ask_parameter};
-use crate::models::Db;
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::commands::prompt::{self, Selection};
+use crate::models::{Db, Name};
 use crate::commons::Opts;
 use crate::models::transaction::Transaction;
+use crate::renderer::Value;
 
 #[derive(Parser, Debug)]
 pub struct AddTransaction {
@@ -12,26 +14,31 @@ pub struct AddTransaction {
     date: String,
     amount: f32,
     tag: String,
+    // Free text, found by search along with the name
+    #[clap(long)]
+    notes: Option<String>,
 }
 
 impl SubCmd for AddTransaction {
-    fn execute(&self, db: &Db, _opts: &Opts) {
-        let tag_id = db.get_tag_id(&self.tag).unwrap_or_else(|e| {
+    fn execute(&self, db: &Db, _opts: &Opts) -> CmdResult {
+        let tag_id = db.get_tag_id(&self.tag).map_err(|e| {
             log::error!("Could not find id for tag {}. Error: {}", self.tag, e);
-            std::process::exit(0);
-        });
+            CmdError(1)
+        })?;
         
         // TODO: Validate date is properly set. YYYY-MM-DD
         // TODO: Validate all params
         
-        let transaction = Transaction::new(&self.name, &self.date, self.amount, tag_id);
-        db.insert_transaction(&transaction).unwrap_or_else(|e| {
-                log::error!("Error inserting transaction: {}", e);
-                std::process::exit(1);
-            }
-        );
+        let mut transaction = Transaction::new(&self.name, &self.date, self.amount, tag_id);
+        transaction.notes = self.notes.clone().filter(|n| !n.trim().is_empty());
+        db.insert_transaction(&transaction).map_err(|e| {
+            log::error!("Error inserting transaction: {}", e);
+            CmdError(1)
+        })?;
         
         log::info!("Transaction [{:?}] inserted successfully", transaction);
+        
+        Ok(())
     }
 }
 
@@ -39,25 +46,64 @@ impl SubCmd for AddTransaction {
 pub struct AddTransactionP;
 
 impl SubCmd for AddTransactionP {
-    fn execute(&self, db: &Db, _opts: &Opts) {
-        let name = ask_parameter::<String>("name");
-        let date = ask_parameter::<String>("date");
-        let amount = ask_parameter::<f32>("amount");
-        let tag = ask_parameter::<String>("tag");
-        let tag = db.get_tag_id(&tag).unwrap_or_else(|e| {
-            log::error!("Could not find id for tag {}. Error: {}", tag, e);
-            std::process::exit(0);
-        });
-
-        // TODO: Validate date is properly set. YYYY-MM-DD
-        // TODO: Validate parameters
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let tags = db.get_all_tags().map_err(|e| {
+            log::error!("Error getting tags list. Error: {}", e);
+            CmdError(1)
+        })?;
+        let last_tag = db.get_last_transaction().and_then(|t| db.get_tag_str(t.tag_id)).ok();
         
-        let transaction = Transaction::new(&name, &date, amount, tag);
-        db.insert_transaction(&transaction).unwrap_or_else(|e| {
+        let name = prompt::ask_text("Name", None)?;
+        let date = prompt::ask_date("Date", None)?;
+        let amount = prompt::ask_amount("Amount", None)?;
+        let tag = ask_tag(db, &tags, last_tag.as_deref())?;
+        
+        let confirmed = prompt::confirm_summary(opts, "New transaction", vec![
+            ("Name", Value::from(name.as_str())),
+            ("Date", Value::date(&date)),
+            ("Amount", Value::from(amount)),
+            ("Tag", Value::from(tag.1.as_str())),
+        ])?;
+        if !confirmed {
+            log::info!("Transaction discarded");
+            return Ok(());
+        }
+        
+        let transaction = Transaction::new(&name, &date, amount, tag.0);
+        db.insert_transaction(&transaction).map_err(|e| {
             log::error!("Error inserting transaction: {}", e);
-            std::process::exit(1);
-        });
+            CmdError(1)
+        })?;
         
         log::info!("Transaction [{:?}] inserted successfully", transaction);
+        
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+// Existing tags are picked fuzzily, a missing one can be created right away.
+fn ask_tag(db: &Db, tags: &[Name], default: Option<&str>) -> Result<(i32, String), CmdError> {
+    loop {
+        let name = match prompt::select("Tag", tags, default, true)? {
+            Selection::Existing(tag) => return Ok((tag.id, tag.name)),
+            Selection::New(name) => name,
+        };
+        
+        if !prompt::confirm(&format!("Tag [{}] does not exist. Create it?", name), false)? {
+            continue;
+        }
+        
+        let description = prompt::ask::<String>("Description", Some(String::new()))?;
+        db.insert_tag(&name, &description).map_err(|e| {
+            log::error!("Error inserting tag [{}]: {}", name, e);
+            CmdError(1)
+        })?;
+        log::info!("Tag [{}] inserted successfully", name);
+        
+        let id = db.get_tag_id(&name).map_err(|e| {
+            log::error!("Could not find id for tag {}. Error: {}", name, e);
+            CmdError(1)
+        })?;
+        return Ok((id, name));
+    }
+}
@@ -0,0 +1,242 @@
+use std::fs::{DirBuilder, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use clap::Parser;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::commons::Opts;
+use crate::models::Db;
+use crate::models::attachment::{self, Attachment};
+use crate::renderer::{Report, Value};
+
+#[derive(Parser, Debug)]
+pub struct Attach {
+    // transaction or payroll
+    entity: String,
+    id: i32,
+    file: String,
+}
+
+#[derive(Parser, Debug)]
+pub struct Open {
+    // Attachment id, as shown by --list
+    id: i32,
+}
+
+#[derive(Parser, Debug)]
+pub struct Extract {
+    // Attachment id, as shown by --list
+    id: i32,
+    // Where to write it. Defaults to the attached file name in the current directory
+    #[clap(short, long)]
+    out: Option<String>,
+    // Overwrite the file at the default path if it already exists
+    #[clap(short, long)]
+    force: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct CheckAttachments {}
+
+impl SubCmd for Attach {
+    fn execute(&self, db: &Db, _opts: &Opts) -> CmdResult {
+        let table = attachment::entity_table(&self.entity).ok_or_else(|| {
+            log::error!("Invalid entity [{}]. Available: transaction, payroll", self.entity);
+            CmdError(1)
+        })?;
+        let dir = store_dir(db)?;
+
+        let found = db.has_row(table, self.id).map_err(|e| {
+            log::error!("Error looking for {} {}: {}", self.entity, self.id, e);
+            CmdError(1)
+        })?;
+        if !found {
+            log::error!("There is no {} with id {}", self.entity, self.id);
+            return Err(CmdError(1));
+        }
+
+        let file = Path::new(&self.file);
+        let name = file.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| self.file.clone());
+        let (hash, size) = attachment::store(&dir, file).map_err(|e| {
+            log::error!("Error copying [{}] into {}: {}", self.file, dir.display(), e);
+            CmdError(1)
+        })?;
+
+        db.insert_attachment(&Attachment::new(table, self.id, &name, &hash, size as i64)).map_err(|e| {
+            log::error!("Error attaching [{}]: {}", self.file, e);
+            CmdError(1)
+        })?;
+
+        if db.is_encrypted() {
+            log::warn!("Attachments are not encrypted, {} is stored as is in {}", name, dir.display());
+        }
+        println!("Attached {} to {} {} as attachment {}", name, self.entity, self.id, db.last_insert_id());
+
+        Ok(())
+    }
+}
+
+impl SubCmd for Open {
+    fn execute(&self, db: &Db, _opts: &Opts) -> CmdResult {
+        let attachment = find(db, self.id)?;
+
+        // Viewers go by the extension, so the copy gets the attached name instead of the hash.
+        let dir = private_temp_dir().map_err(|e| {
+            log::error!("Error creating a temporary folder: {}", e);
+            CmdError(1)
+        })?;
+        let path = dir.join(&attachment.name);
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        extract(db, &attachment, &path, &options)?;
+
+        if db.is_encrypted() {
+            log::warn!("The opened copy is not encrypted, {} stays in {} until removed", attachment.name, dir.display());
+        }
+
+        open_file(&path).map_err(|e| {
+            log::error!("Could not start a viewer for {}: {}", path.display(), e);
+            CmdError(1)
+        })
+    }
+}
+
+impl SubCmd for Extract {
+    fn execute(&self, db: &Db, _opts: &Opts) -> CmdResult {
+        let attachment = find(db, self.id)?;
+        let path = PathBuf::from(self.out.clone().unwrap_or_else(|| attachment.name.clone()));
+
+        // An explicit --out is overwritten like any other output file, the default name only with --force.
+        let mut options = OpenOptions::new();
+        match self.out.is_some() || self.force {
+            true => options.write(true).create(true).truncate(true),
+            false => options.write(true).create_new(true),
+        };
+        extract(db, &attachment, &path, &options)?;
+        println!("Extracted attachment {} to {}", attachment._id, path.display());
+
+        Ok(())
+    }
+}
+
+impl SubCmd for CheckAttachments {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let dir = store_dir(db)?;
+        let attachments = db.get_all_attachments().map_err(|e| {
+            log::error!("Error getting attachments: {}", e);
+            CmdError(1)
+        })?;
+
+        let mut rows = Vec::new();
+        for a in &attachments {
+            let owner = db.has_row(&a.entity, a.entity_id).unwrap_or(false);
+            let problems: Vec<String> = (!owner).then(|| format!("The {} no longer exists", a.entity)).into_iter()
+                .chain(attachment::verify(&dir, a))
+                .collect();
+
+            if !problems.is_empty() {
+                rows.push(vec![
+                    Value::from(a._id),
+                    Value::from(a.entity.clone()),
+                    Value::from(a.entity_id),
+                    Value::from(a.name.clone()),
+                    Value::from(problems.join("; ")),
+                ]);
+            }
+        }
+
+        let hashes: Vec<String> = attachments.iter().map(|a| a.hash.clone()).collect();
+        let unreferenced = attachment::unreferenced(&dir, &hashes);
+        let failed = rows.len();
+
+        let mut report = Report::new();
+        report.add_summary("check", "Attachments", vec![
+            ("Store", Value::from(dir.display().to_string())),
+            ("Checked", Value::from(attachments.len())),
+            ("With problems", Value::from(failed)),
+            ("Unreferenced files", Value::from(unreferenced.len())),
+        ]);
+        if failed > 0 {
+            report.add_table("problems", "Problems", vec!["Id", "Entity", "Row", "Name", "Problem"], rows);
+        }
+        opts.get_renderer().print(&report);
+
+        match failed {
+            0 => Ok(()),
+            _ => Err(CmdError(1)),
+        }
+    }
+}
+
+fn store_dir(db: &Db) -> Result<PathBuf, CmdError> {
+    db.attachments_dir().ok_or_else(|| {
+        log::error!("Database [{}] is not a file, attachments need a folder next to it", db.get_name());
+        CmdError(1)
+    })
+}
+
+fn find(db: &Db, id: i32) -> Result<Attachment, CmdError> {
+    db.get_attachment(id).map_err(|e| {
+        match e {
+            rusqlite::Error::QueryReturnedNoRows => log::error!("There is no attachment with id {}", id),
+            e => log::error!("Error getting attachment {}: {}", id, e),
+        }
+        CmdError(1)
+    })
+}
+
+// Copies the stored file out, refusing to hand over one that no longer matches its hash.
+fn extract(db: &Db, attachment: &Attachment, path: &Path, options: &OpenOptions) -> CmdResult {
+    let dir = store_dir(db)?;
+    if let Some(problem) = attachment::verify(&dir, attachment) {
+        log::error!("Attachment {} is damaged: {}", attachment._id, problem);
+        return Err(CmdError(1));
+    }
+
+    // Written instead of copied, a copy would keep the read-only flag of the store.
+    let stored = attachment::stored_path(&dir, &attachment.hash);
+    std::fs::read(stored).and_then(|data| options.open(path)?.write_all(&data)).map_err(|e| {
+        match e.kind() {
+            std::io::ErrorKind::AlreadyExists => log::error!("{} already exists. Use --out to pick another file or --force to overwrite it", path.display()),
+            _ => log::error!("Error writing {}: {}", path.display(), e),
+        }
+        CmdError(1)
+    })
+}
+
+// A new folder for each open, only readable by the user, so nobody else can read the copy or plant a
+// link where it is going to be written.
+fn private_temp_dir() -> std::io::Result<PathBuf> {
+    let mut suffix = [0u8; 8];
+    getrandom::getrandom(&mut suffix).map_err(|e| std::io::Error::other(e.to_string()))?;
+    let name: String = suffix.iter().map(|b| format!("{:02x}", b)).collect();
+    let dir = std::env::temp_dir().join(format!("gitgud-greedy-{}", name));
+
+    let mut builder = DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(&dir)?;
+    Ok(dir)
+}
+
+fn open_file(path: &Path) -> std::io::Result<()> {
+    // Not through cmd /C start, which would run whatever follows a & or | in the file name.
+    #[cfg(target_os = "windows")]
+    let mut command = Command::new("explorer");
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = Command::new("xdg-open");
+
+    command.arg(path).spawn().map(|_| ())
+}
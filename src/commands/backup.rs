@@ -0,0 +1,261 @@
+use std::cell::RefCell;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+use chrono::{Local, NaiveDateTime};
+use clap::Parser;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::commands::prompt;
+use crate::commons::{self, Opts};
+use crate::models::Db;
+use crate::renderer::{Report, Value};
+
+const STAMP: &str = "%Y%m%d-%H%M%S";
+
+#[derive(Parser, Debug)]
+pub struct Backup {
+    // Directory for the copies. Defaults to backup.dir from the config
+    #[clap(short, long)]
+    dir: Option<String>,
+    // Keep every copy instead of rotating the old ones
+    #[clap(long)]
+    no_rotate: bool,
+    // List the copies instead of making a new one
+    #[clap(short, long)]
+    list: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct Restore {
+    // Backup to restore
+    file: String,
+    // File with the passphrase of the backup, when it is not the one of the db
+    #[clap(long)]
+    backup_key_file: Option<String>,
+    // Directory for the copy of the current db. Defaults to backup.dir from the config
+    #[clap(short, long)]
+    dir: Option<String>,
+    // Replace without asking
+    #[clap(short, long)]
+    yes: bool,
+}
+
+impl SubCmd for Backup {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let dir = backup_dir(&self.dir, opts)?;
+        let stem = db_stem(db)?;
+
+        if self.list {
+            return list(&dir, &stem, opts);
+        }
+
+        std::fs::create_dir_all(&dir).map_err(|e| {
+            log::error!("Error creating backup dir [{}]: {}", dir.display(), e);
+            CmdError(1)
+        })?;
+
+        let path = dir.join(format!("{}-{}.db", stem, Local::now().format(STAMP)));
+        db.backup_to(&path.to_string_lossy()).map_err(|e| {
+            log::error!("Error backing up to [{}]: {}", path.display(), e);
+            CmdError(1)
+        })?;
+        let size = std::fs::metadata(&path).map(|m| m.len() as usize).unwrap_or(0);
+
+        let removed = match self.no_rotate {
+            true => Vec::new(),
+            false => rotate(&dir, &stem, opts.get_backup_retention()),
+        };
+
+        let mut report = Report::new();
+        report.add_summary("backup", "Backup", vec![
+            ("File", Value::from(path.display().to_string())),
+            ("Size", Value::from(size)),
+            ("Encrypted", Value::from(if db.is_encrypted() { "yes" } else { "no" })),
+            ("Integrity", Value::from("ok")),
+            ("Rotated out", Value::from(removed.len())),
+        ]);
+        opts.get_renderer().print(&report);
+
+        Ok(())
+    }
+}
+
+impl SubCmd for Restore {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        // Backups made before a rekey need the old passphrase, asked once at most.
+        let cached = RefCell::new(None);
+        let passphrase = || {
+            if cached.borrow().is_none() {
+                cached.replace(backup_passphrase(&self.backup_key_file));
+            }
+            cached.borrow().clone()
+        };
+
+        let version = db.check_backup(&self.file, passphrase).map_err(|e| {
+            log::error!("Can not restore [{}]: {}", self.file, e);
+            CmdError(1)
+        })?;
+        println!("Backup {} passed the integrity check (schema version {})", self.file, version);
+
+        if !self.yes {
+            if !std::io::stdin().is_terminal() {
+                log::error!("Use --yes to restore without a terminal");
+                return Err(CmdError(1));
+            }
+            if !prompt::confirm(&format!("Replace {} with it?", db.get_name()), false)? {
+                return Ok(());
+            }
+        }
+
+        // The current data is kept aside first, out of the rotation since the name does not match.
+        let dir = backup_dir(&self.dir, opts)?;
+        let previous = dir.join(format!("{}-{}-pre-restore.db", db_stem(db)?, Local::now().format("%Y%m%d-%H%M%S-%3f")));
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| e.to_string())
+            .and_then(|_| db.backup_to(&previous.to_string_lossy()).map_err(|e| e.to_string()))
+            .map_err(|e| {
+                log::error!("Error saving the current db to [{}], nothing restored: {}", previous.display(), e);
+                CmdError(1)
+            })?;
+
+        db.restore_from(&self.file, passphrase).map_err(|e| {
+            log::error!("Error restoring [{}]: {}", self.file, e);
+            CmdError(1)
+        })?;
+
+        println!("Restored {} from {}. The previous data is in {}", db.get_name(), self.file, previous.display());
+        Ok(())
+    }
+}
+
+fn backup_dir(dir: &Option<String>, opts: &Opts) -> Result<PathBuf, CmdError> {
+    dir.as_ref().map(PathBuf::from).or_else(|| opts.get_backup_dir()).ok_or_else(|| {
+        log::error!("No backup dir, set backup.dir in the config or use --dir");
+        CmdError(1)
+    })
+}
+
+fn db_stem(db: &Db) -> Result<String, CmdError> {
+    match Path::new(db.get_name()).file_stem() {
+        Some(stem) if db.get_name() != ":memory:" => Ok(stem.to_string_lossy().into_owned()),
+        _ => {
+            log::error!("Database [{}] is not a file", db.get_name());
+            Err(CmdError(1))
+        },
+    }
+}
+
+fn backup_passphrase(key_file: &Option<String>) -> Option<String> {
+    match key_file {
+        Some(path) => commons::read_key_file(path).map_err(|e| log::error!("{}", e)).ok(),
+        None if std::io::stdin().is_terminal() => rpassword::prompt_password("Passphrase of the backup: ").ok(),
+        None => None,
+    }
+}
+
+// Copies of the db in the dir, newest first. Only names made by backup count.
+fn backups(dir: &Path, stem: &str) -> Vec<(NaiveDateTime, PathBuf)> {
+    let prefix = format!("{}-", stem);
+    let mut ret: Vec<(NaiveDateTime, PathBuf)> = std::fs::read_dir(dir).into_iter().flatten().flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let stamp = name.strip_prefix(&prefix)?.strip_suffix(".db")?;
+            NaiveDateTime::parse_from_str(stamp, STAMP).ok().map(|date| (date, entry.path()))
+        })
+        .collect();
+
+    ret.sort_by_key(|b| std::cmp::Reverse(b.0));
+    ret
+}
+
+fn rotate(dir: &Path, stem: &str, limits: (usize, usize, usize)) -> Vec<PathBuf> {
+    let all = backups(dir, stem);
+    let dates: Vec<NaiveDateTime> = all.iter().map(|(date, _)| *date).collect();
+    let keep = kept(&dates, limits);
+
+    let mut removed = Vec::new();
+    for ((_, path), _) in all.into_iter().zip(keep).filter(|(_, k)| !k) {
+        match std::fs::remove_file(&path) {
+            Ok(_) => {
+                log::info!("Rotated out backup {}", path.display());
+                removed.push(path);
+            },
+            Err(e) => log::warn!("Error removing old backup [{}]: {}", path.display(), e),
+        }
+    }
+    removed
+}
+
+// Which of the dates, newest first, to keep: the newest of each of the latest days, ISO weeks and
+// months, and always the newest one.
+fn kept(dates: &[NaiveDateTime], (daily, weekly, monthly): (usize, usize, usize)) -> Vec<bool> {
+    let mut keep = vec![false; dates.len()];
+    if let Some(k) = keep.first_mut() {
+        *k = true;
+    }
+
+    for (limit, format) in [(daily, "%Y-%m-%d"), (weekly, "%G-W%V"), (monthly, "%Y-%m")] {
+        let mut seen: Vec<String> = Vec::new();
+        for (i, date) in dates.iter().enumerate() {
+            let period = date.format(format).to_string();
+            if !seen.contains(&period) {
+                if seen.len() == limit {
+                    break;
+                }
+                seen.push(period);
+                keep[i] = true;
+            }
+        }
+    }
+
+    keep
+}
+
+fn list(dir: &Path, stem: &str, opts: &Opts) -> CmdResult {
+    let rows = backups(dir, stem).into_iter().map(|(date, path)| {
+        let size = std::fs::metadata(&path).map(|m| m.len() as usize).unwrap_or(0);
+        vec![
+            Value::from(path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()),
+            Value::from(date.format("%Y-%m-%d %H:%M:%S").to_string()),
+            Value::from(size),
+        ]
+    }).collect();
+
+    let mut report = Report::new();
+    report.add_table("backups", &format!("Backups in {}", dir.display()), vec!["File", "Date", "Size"], rows);
+    opts.get_renderer().print(&report);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dates(days: &[&str]) -> Vec<NaiveDateTime> {
+        days.iter().map(|d| NaiveDateTime::parse_from_str(&format!("{} 12:00:00", d), "%Y-%m-%d %H:%M:%S").unwrap()).collect()
+    }
+
+    #[test]
+    fn newest_of_each_day_is_kept() {
+        let all = dates(&["2022-03-05", "2022-03-05", "2022-03-04", "2022-03-02", "2022-03-01"]);
+        assert_eq!(kept(&all, (3, 0, 0)), vec![true, false, true, true, false]);
+    }
+
+    #[test]
+    fn weeks_follow_iso_years() {
+        // Sunday 2021-01-03 still belongs to 2020-W53, Monday 2021-01-04 starts 2021-W01.
+        let all = dates(&["2021-01-05", "2021-01-04", "2021-01-03", "2021-01-01", "2020-12-27"]);
+        assert_eq!(kept(&all, (0, 2, 0)), vec![true, false, true, false, false]);
+        assert_eq!(kept(&all, (0, 3, 0)), vec![true, false, true, false, true]);
+    }
+
+    #[test]
+    fn periods_add_up_and_the_newest_always_stays() {
+        let all = dates(&["2022-03-05", "2022-02-27", "2022-02-01", "2022-01-15"]);
+        assert_eq!(kept(&all, (1, 0, 3)), vec![true, true, false, true]);
+        assert_eq!(kept(&all, (0, 0, 0)), vec![true, false, false, false]);
+        assert!(kept(&[], (7, 4, 12)).is_empty());
+    }
+}
@@ -1,11 +1,11 @@
 use clap::Parser;
 use log;
 
-use crate::commands::sub_cmd::SubCmd;
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError, write_svg};
 use crate::models::Db;
 use crate::commons::Opts;
-use crate::models::account::Account;
 use crate::view_models::balance_summary_vm::BalanceSummaryVm;
+use crate::renderer::Report;
 
 #[derive(Parser, Debug)]
 pub struct BalanceSummary {
@@ -13,26 +13,36 @@ pub struct BalanceSummary {
     year: Option<u32>,
     #[clap(short, long)]
     month: Option<u32>,
+    #[clap(long)]
+    plot_out: Option<String>,
 }
 
 impl SubCmd for BalanceSummary {
-    fn execute(&self, db: &Db, _opts: &Opts) {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
         // TODO: If no date use current date
         
         let year = self.year;
         let month = self.month;
         
-        let payroll = db.get_payroll_data(year, month).unwrap_or_else(|e| {
+        let payroll = db.get_payroll_data(year, month).map_err(|e| {
             log::error!("Error getting payrolls: {}", e);
-            std::process::exit(0);
-        });
+            CmdError(1)
+        })?;
         
-        let transactions = db.get_transaction_data(year, month).unwrap_or_else(|e| {
+        let transactions = db.get_transaction_data(year, month).map_err(|e| {
             log::error!("Error getting transactions: {}", e);
-            std::process::exit(0);
-        });
+            CmdError(1)
+        })?;
         
         let vm = BalanceSummaryVm::generate(&payroll, &transactions);
-        vm.render(db);
+        let mut report = Report::new();
+        vm.render(db, &mut report);
+        opts.get_renderer().print(&report);
+        
+        if let Some(filename) = &self.plot_out {
+            write_svg(filename, &vm.charts())?;
+        }
+        
+        Ok(())
     }
 }
\ No newline at end of file
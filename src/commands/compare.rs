@@ -0,0 +1,57 @@
+use clap::Parser;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::models::Db;
+use crate::commons::{Opts, Period};
+use crate::models::payroll::Payroll;
+use crate::models::transaction::Transaction;
+use crate::renderer::Report;
+use crate::view_models::compare_vm::CompareVm;
+use crate::view_models::payroll_data_vm::PayrollDataVm;
+use crate::view_models::transaction_data_vm::TransactionDataVm;
+
+#[derive(Parser, Debug)]
+pub struct Compare {
+    // YYYY or YYYY-MM. When it is the only period it is compared against the previous one
+    from: Period,
+    to: Option<Period>,
+    // Number of tags listed as biggest movers. Defaults to report.top from the config, or 5
+    #[clap(short, long)]
+    top: Option<usize>,
+}
+
+impl SubCmd for Compare {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let (from, to) = match self.to {
+            Some(to) => (self.from, to),
+            None => (self.from.previous(), self.from),
+        };
+        
+        let (from_payrolls, from_transactions) = fetch(db, from)?;
+        let (to_payrolls, to_transactions) = fetch(db, to)?;
+        
+        let vm = CompareVm::generate(from, to,
+                                     &PayrollDataVm::generate(&from_payrolls), &PayrollDataVm::generate(&to_payrolls),
+                                     &TransactionDataVm::generate(&from_transactions), &TransactionDataVm::generate(&to_transactions));
+        
+        let mut report = Report::new();
+        vm.render(db, &mut report, self.top.or(opts.get_config().report.top).unwrap_or(5));
+        opts.get_renderer().print(&report);
+        
+        Ok(())
+    }
+}
+
+fn fetch(db: &Db, period: Period) -> Result<(Vec<Payroll>, Vec<Transaction>), CmdError> {
+    let payrolls = db.get_payroll_data(period.year(), period.month()).map_err(|e| {
+        log::error!("Error getting payrolls for {}: {}", period, e);
+        CmdError(1)
+    })?;
+    
+    let transactions = db.get_transaction_data(period.year(), period.month()).map_err(|e| {
+        log::error!("Error getting transactions for {}: {}", period, e);
+        CmdError(1)
+    })?;
+    
+    Ok((payrolls, transactions))
+}
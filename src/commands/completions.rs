@@ -0,0 +1,492 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use clap::{App, ArgSettings, IntoApp, Parser};
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::models::Db;
+use crate::commons::Opts;
+
+const BIN: &str = "gitgud-greedy";
+const FN: &str = "_gitgud_greedy";
+
+#[derive(Debug)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for CompletionShell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bash" => Ok(CompletionShell::Bash),
+            "zsh" => Ok(CompletionShell::Zsh),
+            "fish" => Ok(CompletionShell::Fish),
+            _ => Err(format!("Invalid shell [{}]. Available: bash, zsh, fish", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum NameKind {
+    Tags,
+    Companies,
+    Categories,
+    Accounts,
+    // Tags, companies and categories together
+    Names,
+}
+
+impl FromStr for NameKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "tags" => Ok(NameKind::Tags),
+            "companies" => Ok(NameKind::Companies),
+            "categories" => Ok(NameKind::Categories),
+            "accounts" => Ok(NameKind::Accounts),
+            "names" => Ok(NameKind::Names),
+            _ => Err(format!("Invalid name kind [{}]. Available: tags, companies, categories, accounts, names", s)),
+        }
+    }
+}
+
+impl NameKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            NameKind::Tags => "tags",
+            NameKind::Companies => "companies",
+            NameKind::Categories => "categories",
+            NameKind::Accounts => "accounts",
+            NameKind::Names => "names",
+        }
+    }
+}
+
+// Print a completion script. Source it or drop it in the shell completions folder, e.g.
+// `gitgud-greedy completions bash > ~/.local/share/bash-completion/completions/gitgud-greedy`
+#[derive(Parser, Debug)]
+pub struct Completions {
+    shell: CompletionShell,
+}
+
+// Used by the completion scripts to list names from the configured database.
+#[derive(Parser, Debug)]
+pub struct CompleteNames {
+    kind: NameKind,
+}
+
+impl SubCmd for Completions {
+    fn execute(&self, _db: &Db, _opts: &Opts) -> CmdResult {
+        let spec = CmdSpec::from_app(&Opts::into_app(), Vec::new());
+        let script = match self.shell {
+            CompletionShell::Bash => bash(&spec),
+            CompletionShell::Zsh => zsh(&spec),
+            CompletionShell::Fish => fish(&spec),
+        };
+        print!("{}", script);
+
+        Ok(())
+    }
+}
+
+impl SubCmd for CompleteNames {
+    fn execute(&self, db: &Db, _opts: &Opts) -> CmdResult {
+        let names: Vec<String> = match self.kind {
+            NameKind::Tags => db.get_all_tags().map(|n| n.into_iter().map(|n| n.name).collect()),
+            NameKind::Companies => db.get_all_companies().map(|n| n.into_iter().map(|n| n.name).collect()),
+            NameKind::Categories => db.get_all_categories().map(|n| n.into_iter().map(|n| n.name).collect()),
+            NameKind::Accounts => db.get_all_accounts().map(|a| a.into_iter().map(|a| a.name).collect()),
+            NameKind::Names => db.get_all_tags()
+                .and_then(|mut n| { n.extend(db.get_all_companies()?); Ok(n) })
+                .and_then(|mut n| { n.extend(db.get_all_categories()?); Ok(n) })
+                .map(|n| n.into_iter().map(|n| n.name).collect()),
+        }.map_err(|e| {
+            log::error!("Error getting {}: {}", self.kind.as_str(), e);
+            CmdError(1)
+        })?;
+
+        for name in names {
+            println!("{}", name);
+        }
+
+        Ok(())
+    }
+}
+
+// How the value of an argument is completed.
+#[derive(Clone)]
+enum ValueKind {
+    Names(NameKind),
+    Values(&'static [&'static str]),
+    Files,
+    Any,
+}
+
+impl ValueKind {
+    // Arguments are matched by name, which is all the scripts need to know about them.
+    fn of(path: &[String], arg: &str) -> ValueKind {
+        let command = path.first().map(|c| c.as_str()).unwrap_or("");
+        match (command, arg) {
+            ("get-account", "name") | ("set-account-balance", "name") => ValueKind::Names(NameKind::Accounts),
+            ("get-id", "name") => ValueKind::Names(NameKind::Names),
+            (_, "tag") => ValueKind::Names(NameKind::Tags),
+            (_, "company") => ValueKind::Names(NameKind::Companies),
+            (_, "category") => ValueKind::Names(NameKind::Categories),
+            (_, "table") => ValueKind::Values(&["tag", "company", "category"]),
+            (_, "output") => ValueKind::Values(&["table", "json", "csv", "markdown"]),
+            (_, "log") => ValueKind::Values(&["off", "error", "warn", "info", "debug", "trace"]),
+            (_, "dialect") => ValueKind::Values(&["ledger", "hledger", "beancount"]),
+            (_, "source") => ValueKind::Values(&["tags", "companies", "categories"]),
+            (_, "by") => ValueKind::Values(&["month", "quarter"]),
+            (_, "kind") => ValueKind::Values(&["earning", "deduction"]),
+            (_, "shell") => ValueKind::Values(&["bash", "zsh", "fish"]),
+            ("attach", "entity") | ("search", "entity") => ValueKind::Values(&["transaction", "payroll"]),
+            (_, "entity") => ValueKind::Values(&["transaction", "payroll", "payroll-item", "account", "tag", "company", "category", "attachment"]),
+            (_, "filename") | (_, "html") | (_, "plot-out") | (_, "history") | (_, "dir") | (_, "config") | (_, "db-name") | (_, "key-file") | (_, "new-key-file") | (_, "backup-key-file") | ("restore", "file") | ("attach", "file") | ("extract", "out") => ValueKind::Files,
+            _ => ValueKind::Any,
+        }
+    }
+
+    // Shared by the bash and zsh scripts, which dispatch on the first word.
+    fn describe(&self) -> String {
+        match self {
+            ValueKind::Names(kind) => format!("names {}", kind.as_str()),
+            ValueKind::Values(values) => format!("values {}", values.join(" ")),
+            ValueKind::Files => String::from("files"),
+            ValueKind::Any => String::new(),
+        }
+    }
+}
+
+struct OptSpec {
+    flags: Vec<String>,
+    takes_value: bool,
+    value: ValueKind,
+}
+
+struct CmdSpec {
+    path: Vec<String>,
+    about: String,
+    options: Vec<OptSpec>,
+    positionals: Vec<ValueKind>,
+    subcommands: Vec<CmdSpec>,
+}
+
+impl CmdSpec {
+    fn from_app(app: &App, path: Vec<String>) -> CmdSpec {
+        let mut options = vec![OptSpec {
+            flags: vec![String::from("-h"), String::from("--help")],
+            takes_value: false,
+            value: ValueKind::Any,
+        }];
+        let mut positionals = Vec::new();
+
+        for arg in app.get_arguments().filter(|a| !a.is_set(ArgSettings::Hidden)) {
+            // Clap only gives these their short flags when the app is built.
+            if arg.get_name() == "help" {
+                continue;
+            }
+            if arg.get_name() == "version" {
+                options.push(OptSpec { flags: vec![String::from("-V"), String::from("--version")], takes_value: false, value: ValueKind::Any });
+                continue;
+            }
+
+            let value = ValueKind::of(&path, arg.get_name());
+            if arg.is_positional() {
+                positionals.push(value);
+                continue;
+            }
+
+            let mut flags = Vec::new();
+            if let Some(s) = arg.get_short() {
+                flags.push(format!("-{}", s));
+            }
+            if let Some(l) = arg.get_long() {
+                flags.push(format!("--{}", l));
+            }
+            options.push(OptSpec { flags, takes_value: arg.is_set(ArgSettings::TakesValue), value });
+        }
+
+        let subcommands = app.get_subcommands()
+            .filter(|c| !c.is_set(clap::AppSettings::Hidden))
+            .map(|c| {
+                let mut sub_path = path.clone();
+                sub_path.push(c.get_name().to_string());
+                CmdSpec::from_app(c, sub_path)
+            })
+            .collect();
+
+        CmdSpec {
+            path,
+            about: app.get_about().unwrap_or("").to_string(),
+            options,
+            positionals,
+            subcommands,
+        }
+    }
+
+    fn key(&self) -> String {
+        self.path.join(" ")
+    }
+
+    fn all(&self) -> Vec<&CmdSpec> {
+        let mut all = vec![self];
+        for sub in &self.subcommands {
+            all.extend(sub.all());
+        }
+        all
+    }
+
+    fn flags(&self, only_values: bool) -> Vec<&str> {
+        self.options.iter()
+            .filter(|o| !only_values || o.takes_value)
+            .flat_map(|o| o.flags.iter().map(|f| f.as_str()))
+            .collect()
+    }
+}
+
+// Case functions answering what the drivers need to know for a command path. Valid in bash and zsh.
+fn lookup_functions(spec: &CmdSpec) -> String {
+    let mut out = String::new();
+    let all = spec.all();
+
+    let _ = writeln!(out, "{}_subcommands() {{\n    case \"$1\" in", FN);
+    for cmd in all.iter().filter(|c| !c.subcommands.is_empty()) {
+        let names: Vec<&str> = cmd.subcommands.iter().map(|s| s.path.last().unwrap().as_str()).collect();
+        let _ = writeln!(out, "        \"{}\") echo \"{}\" ;;", cmd.key(), names.join(" "));
+    }
+    let _ = writeln!(out, "    esac\n}}\n");
+
+    for (name, only_values) in [("options", false), ("value_options", true)].iter() {
+        let _ = writeln!(out, "{}_{}() {{\n    case \"$1\" in", FN, name);
+        for cmd in &all {
+            let _ = writeln!(out, "        \"{}\") echo \"{}\" ;;", cmd.key(), cmd.flags(*only_values).join(" "));
+        }
+        let _ = writeln!(out, "    esac\n}}\n");
+    }
+
+    // $1 is the command path, $2 an option or #N for the Nth positional argument.
+    let _ = writeln!(out, "{}_value() {{\n    case \"$1|$2\" in", FN);
+    for cmd in &all {
+        for opt in cmd.options.iter().filter(|o| o.takes_value) {
+            let description = opt.value.describe();
+            if description.is_empty() {
+                continue;
+            }
+            let keys: Vec<String> = opt.flags.iter().map(|f| format!("\"{}|{}\"", cmd.key(), f)).collect();
+            let _ = writeln!(out, "        {}) echo \"{}\" ;;", keys.join("|"), description);
+        }
+        for (i, value) in cmd.positionals.iter().enumerate() {
+            let description = value.describe();
+            if !description.is_empty() {
+                let _ = writeln!(out, "        \"{}|#{}\") echo \"{}\" ;;", cmd.key(), i + 1, description);
+            }
+        }
+    }
+    let _ = writeln!(out, "    esac\n}}\n");
+
+    out
+}
+
+// Walks the words before the cursor to find the command path, the positional index and the
+// database options, which the names helper needs to read the same database. Not named path
+// since zsh ties that one to PATH.
+const WALK: &str = r##"    local cmd="" pos=0 skip=0 keep=0 w
+    local -a globals
+    for w in "${line[@]}"; do
+        if ((skip)); then
+            ((keep)) && globals+=("$w")
+            skip=0
+            continue
+        fi
+        if [[ $w == -* ]]; then
+            if [[ " $(_gitgud_greedy_value_options "$cmd") " == *" $w "* ]]; then
+                skip=1
+                keep=0
+                if [[ -z $cmd && ( $w == -d || $w == --db-name || $w == -c || $w == --config || $w == --key-file ) ]]; then
+                    keep=1
+                    globals+=("$w")
+                fi
+            fi
+        elif ((pos == 0)) && [[ " $(_gitgud_greedy_subcommands "$cmd") " == *" $w "* ]]; then
+            cmd="${cmd:+$cmd }$w"
+        else
+            pos=$((pos + 1))
+        fi
+    done
+
+    local what
+    if [[ " $(_gitgud_greedy_value_options "$cmd") " == *" $prev "* ]]; then
+        what=$(_gitgud_greedy_value "$cmd" "$prev")
+    elif [[ $cur == -* ]]; then
+        what="values $(_gitgud_greedy_options "$cmd")"
+    elif ((pos == 0)) && [[ -n $(_gitgud_greedy_subcommands "$cmd") ]]; then
+        what="values $(_gitgud_greedy_subcommands "$cmd")"
+    else
+        what=$(_gitgud_greedy_value "$cmd" "#$((pos + 1))")
+    fi
+"##;
+
+fn bash(spec: &CmdSpec) -> String {
+    let mut out = format!("# bash completion for {}\n\n", BIN);
+    out += &lookup_functions(spec);
+    let _ = write!(out, r##"{fn}() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}" prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    local -a line=("${{COMP_WORDS[@]:1:COMP_CWORD-1}}")
+{walk}
+    COMPREPLY=()
+    case "$what" in
+        names\ *)
+            local name
+            while IFS= read -r name; do
+                [[ $name == "$cur"* ]] && COMPREPLY+=("$(printf '%q' "$name")")
+            done < <({bin} -l off "${{globals[@]}}" __complete "${{what#names }}" 2>/dev/null)
+            ;;
+        values\ *) COMPREPLY=($(compgen -W "${{what#values }}" -- "$cur")) ;;
+        files)
+            compopt -o filenames 2>/dev/null
+            COMPREPLY=($(compgen -f -- "$cur"))
+            ;;
+    esac
+}}
+
+complete -F {fn} {bin}
+"##, fn = FN, bin = BIN, walk = WALK);
+    out
+}
+
+fn zsh(spec: &CmdSpec) -> String {
+    let mut out = format!("#compdef {}\n\n", BIN);
+    out += &lookup_functions(spec);
+    let _ = write!(out, r##"{fn}() {{
+    local cur="${{words[CURRENT]}}" prev="${{words[CURRENT-1]}}"
+    local -a line=("${{(@)words[2,CURRENT-1]}}")
+{walk}
+    case "$what" in
+        names\ *)
+            local -a names
+            names=("${{(@f)$({bin} -l off "${{globals[@]}}" __complete "${{what#names }}" 2>/dev/null)}}")
+            compadd -a names
+            ;;
+        values\ *) compadd -- ${{=what#values }} ;;
+        files) _files ;;
+    esac
+}}
+
+if [[ $zsh_eval_context[-1] == loadautofunc ]]; then
+    {fn} "$@"
+else
+    compdef {fn} {bin}
+fi
+"##, fn = FN, bin = BIN, walk = WALK);
+    out
+}
+
+fn fish(spec: &CmdSpec) -> String {
+    let mut out = format!("# fish completion for {}\n\n", BIN);
+    let _ = writeln!(out, r##"function __gitgud_greedy_names
+    set -l args
+    set -l words (commandline -opc)
+    for i in (seq 2 (count $words))
+        switch $words[$i]
+            case -d --db-name -c --config --key-file
+                set -a args $words[$i] $words[(math $i + 1)]
+        end
+    end
+    {} -l off $args __complete $argv[1] 2>/dev/null
+end
+"##, BIN);
+
+    for cmd in spec.all() {
+        let condition = match cmd.path.len() {
+            0 => String::from("__fish_use_subcommand"),
+            _ => cmd.path.iter().map(|p| format!("__fish_seen_subcommand_from {}", p)).collect::<Vec<_>>().join("; and "),
+        };
+        let condition = match cmd.subcommands.is_empty() || cmd.path.is_empty() {
+            true => condition,
+            false => {
+                let names: Vec<&str> = cmd.subcommands.iter().map(|s| s.path.last().unwrap().as_str()).collect();
+                format!("{}; and not __fish_seen_subcommand_from {}", condition, names.join(" "))
+            },
+        };
+
+        for sub in &cmd.subcommands {
+            let mut line = format!("complete -c {} -f -n '{}' -a {}", BIN, condition, sub.path.last().unwrap());
+            if !sub.about.is_empty() {
+                line += &format!(" -d '{}'", sub.about.replace('\'', "\\'"));
+            }
+            let _ = writeln!(out, "{}", line);
+        }
+
+        for opt in cmd.options.iter() {
+            let mut line = format!("complete -c {} -n '{}'", BIN, condition);
+            for flag in &opt.flags {
+                match flag.strip_prefix("--") {
+                    Some(l) => line += &format!(" -l {}", l),
+                    None => line += &format!(" -s {}", &flag[1..]),
+                }
+            }
+            if opt.takes_value {
+                line += &fish_values(&opt.value);
+            }
+            let _ = writeln!(out, "{}", line);
+        }
+
+        // Fish can not tell positional arguments apart, so every value completion of the command is offered.
+        for value in &cmd.positionals {
+            let values = fish_values(value);
+            if values != " -r" {
+                let _ = writeln!(out, "complete -c {} -n '{}'{}", BIN, condition, values);
+            }
+        }
+    }
+
+    out
+}
+
+fn fish_values(value: &ValueKind) -> String {
+    match value {
+        ValueKind::Names(kind) => format!(" -x -a '(__gitgud_greedy_names {})'", kind.as_str()),
+        ValueKind::Values(values) => format!(" -x -a '{}'", values.join(" ")),
+        ValueKind::Files => String::from(" -F -r"),
+        ValueKind::Any => String::from(" -r"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value_cases() -> String {
+        lookup_functions(&CmdSpec::from_app(&Opts::into_app(), Vec::new()))
+    }
+
+    #[test]
+    fn arguments_complete_by_their_name() {
+        let cases = value_cases();
+        for case in [
+            r#""add-transaction|#4") echo "names tags" ;;"#,
+            r#""add-payroll|#6") echo "names companies" ;;"#,
+            r#""get-id|#2") echo "names names" ;;"#,
+            r#""restore|#1") echo "files" ;;"#,
+            r#""attach|#1") echo "values transaction payroll" ;;"#,
+            r#""attach|#3") echo "files" ;;"#,
+            r#""history|-e"|"history|--entity") echo "values transaction payroll payroll-item account tag company category attachment" ;;"#,
+            r#""|-o"|"|--output") echo "values table json csv markdown" ;;"#,
+        ] {
+            assert!(cases.contains(case), "missing completion case {}", case);
+        }
+    }
+
+    #[test]
+    fn subcommands_and_their_options_are_listed() {
+        let cases = value_cases();
+        assert!(cases.contains(r#""") echo "add-transaction add-transaction-p "#));
+        assert!(cases.lines().any(|l| l.starts_with(r#"        "undo") echo ""#) && l.contains("--dry-run")));
+    }
+}
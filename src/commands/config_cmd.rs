@@ -0,0 +1,47 @@
+use clap::Parser;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult};
+use crate::models::Db;
+use crate::commons::Opts;
+use crate::renderer::{Report, Value};
+
+#[derive(Parser, Debug)]
+pub struct ConfigCmd {
+    #[clap(subcommand)]
+    action: ConfigAction,
+}
+
+#[derive(Parser, Debug)]
+pub enum ConfigAction {
+    // Print the effective settings after merging the config file and the command line
+    Show,
+    // Print the path of the config file in use
+    Path,
+}
+
+impl SubCmd for ConfigCmd {
+    fn execute(&self, _db: &Db, opts: &Opts) -> CmdResult {
+        let path = opts.get_config_path().map(|p| p.display().to_string());
+        
+        match self.action {
+            ConfigAction::Show => {
+                let rows = opts.get_settings().into_iter()
+                    .map(|(name, value, source)| vec![Value::from(name), Value::from(value), Value::from(source)])
+                    .collect();
+                
+                let mut report = Report::new();
+                report.add_summary("config", "Config file", vec![
+                    ("Path", Value::from(path.unwrap_or_else(|| String::from("none")))),
+                ]);
+                report.add_table("settings", "Effective settings", vec!["Setting", "Value", "Source"], rows);
+                opts.get_renderer().print(&report);
+            },
+            ConfigAction::Path => match path {
+                Some(p) => println!("{}", p),
+                None => log::warn!("No config file in use"),
+            },
+        }
+        
+        Ok(())
+    }
+}
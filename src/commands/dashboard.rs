@@ -0,0 +1,37 @@
+use std::io::IsTerminal;
+
+use chrono::{Datelike, Local};
+use clap::Parser;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::commons::{Opts, Period};
+use crate::dashboard::{self, App};
+use crate::models::Db;
+
+#[derive(Parser, Debug)]
+pub struct Tui {
+    // Month shown at start (YYYY-MM). Defaults to the current month
+    #[clap(short, long)]
+    period: Option<Period>,
+}
+
+impl SubCmd for Tui {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        if !std::io::stdout().is_terminal() {
+            log::error!("The tui needs an interactive terminal");
+            return Err(CmdError(1));
+        }
+
+        let today = Local::now().date();
+        let period = self.period.unwrap_or(Period { year: today.year() as u32, month: Some(today.month()) });
+
+        let renderer = opts.get_renderer();
+        let mut app = App::new(db, period, opts.get_formats(), renderer.color());
+        dashboard::run(&mut app).map_err(|e| {
+            log::error!("Error running the tui: {}", e);
+            CmdError(1)
+        })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use std::io::IsTerminal;
+
+use clap::Parser;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::commands::prompt;
+use crate::commons::{self, Opts};
+use crate::models::Db;
+
+#[derive(Parser, Debug)]
+pub struct EncryptDb {
+    // File with the new passphrase. Asked twice when missing
+    #[clap(long)]
+    new_key_file: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct DecryptDb;
+
+#[derive(Parser, Debug)]
+pub struct Rekey {
+    // File with the new passphrase. Asked twice when missing
+    #[clap(long)]
+    new_key_file: Option<String>,
+}
+
+impl SubCmd for EncryptDb {
+    fn execute(&self, db: &Db, _opts: &Opts) -> CmdResult {
+        if db.is_encrypted() {
+            log::error!("Database [{}] is already encrypted, use rekey to change the passphrase", db.get_name());
+            return Err(CmdError(1));
+        }
+
+        let passphrase = new_passphrase(&self.new_key_file)?;
+        db.encrypt(&passphrase).map_err(|e| {
+            log::error!("Error encrypting [{}]: {}", db.get_name(), e);
+            CmdError(1)
+        })?;
+
+        println!("Encrypted {}. Older copies and backups of it are still plain text.", db.get_name());
+        Ok(())
+    }
+}
+
+impl SubCmd for DecryptDb {
+    fn execute(&self, db: &Db, _opts: &Opts) -> CmdResult {
+        if !db.is_encrypted() {
+            log::error!("Database [{}] is not encrypted", db.get_name());
+            return Err(CmdError(1));
+        }
+
+        db.decrypt().map_err(|e| {
+            log::error!("Error decrypting [{}]: {}", db.get_name(), e);
+            CmdError(1)
+        })?;
+
+        println!("Decrypted {}, it is stored as plain text now.", db.get_name());
+        Ok(())
+    }
+}
+
+impl SubCmd for Rekey {
+    fn execute(&self, db: &Db, _opts: &Opts) -> CmdResult {
+        if !db.is_encrypted() {
+            log::error!("Database [{}] is not encrypted, use encrypt-db", db.get_name());
+            return Err(CmdError(1));
+        }
+
+        let passphrase = new_passphrase(&self.new_key_file)?;
+        db.encrypt(&passphrase).map_err(|e| {
+            log::error!("Error changing the passphrase of [{}]: {}", db.get_name(), e);
+            CmdError(1)
+        })?;
+
+        println!("Changed the passphrase of {}.", db.get_name());
+        Ok(())
+    }
+}
+
+fn new_passphrase(key_file: &Option<String>) -> Result<String, CmdError> {
+    if let Some(path) = key_file {
+        return commons::read_key_file(path).map_err(|e| {
+            log::error!("{}", e);
+            CmdError(1)
+        });
+    }
+
+    if !std::io::stdin().is_terminal() {
+        log::error!("Use --new-key-file or run from a terminal to type the new passphrase");
+        return Err(CmdError(1));
+    }
+    prompt::ask_new_passphrase()
+}
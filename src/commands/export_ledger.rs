@@ -0,0 +1,59 @@
+use clap::Parser;
+
+use std::fs;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::models::Db;
+use crate::commons::Opts;
+use crate::view_models::ledger_export_vm::{LedgerExportVm, LedgerDialect};
+
+#[derive(Parser, Debug)]
+pub struct ExportLedger {
+    // Use '-' to write to stdout
+    filename: String,
+    #[clap(short, long, default_value="ledger")]
+    dialect: LedgerDialect,
+    // Defaults to default_account from the config, or Assets:Bank
+    #[clap(short, long)]
+    account: Option<String>,
+    // Defaults to currency from the config, or EUR
+    #[clap(short, long)]
+    currency: Option<String>,
+    #[clap(short, long)]
+    year: Option<u32>,
+    #[clap(short, long)]
+    month: Option<u32>,
+}
+
+impl SubCmd for ExportLedger {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let payrolls = db.get_payroll_data(self.year, self.month).map_err(|e| {
+            log::error!("Error getting payrolls: {}", e);
+            CmdError(1)
+        })?;
+
+        let transactions = db.get_transaction_data(self.year, self.month).map_err(|e| {
+            log::error!("Error getting transactions: {}", e);
+            CmdError(1)
+        })?;
+
+        let account = self.account.clone().unwrap_or_else(|| opts.get_default_account());
+        let currency = self.currency.clone().unwrap_or_else(|| opts.get_currency());
+        let vm = LedgerExportVm::generate(&payrolls, &transactions, db, self.dialect, &account, &currency);
+        let journal = vm.render();
+
+        if self.filename == "-" {
+            print!("{}", journal);
+            return Ok(());
+        }
+
+        fs::write(&self.filename, journal).map_err(|e| {
+            log::error!("Error writing journal to [{}]: {}", self.filename, e);
+            CmdError(1)
+        })?;
+
+        log::info!("Exported {} payrolls and {} transactions to {}", payrolls.len(), transactions.len(), self.filename);
+        
+        Ok(())
+    }
+}
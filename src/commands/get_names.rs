@@ -1,9 +1,10 @@
 use clap::Parser;
 use log;
 
-use crate::commands::sub_cmd::SubCmd;
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
 use crate::models::{Db, Name};
 use crate::commons::Opts;
+use crate::renderer::{Report, Value};
 
 #[derive(Parser, Debug)]
 pub struct GetName {
@@ -12,21 +13,23 @@ pub struct GetName {
 }
 
 impl SubCmd for GetName {
-    fn execute(&self, db: &Db, _opts: &Opts) {
+    fn execute(&self, db: &Db, _opts: &Opts) -> CmdResult {
         let name = match self.table.to_lowercase().as_str() { 
             "tag" => db.get_tag_str(self.id),
             "company" => db.get_company_str(self.id),
             "category" => db.get_category_str(self.id),
             _ => {
                 log::error!("Invalid table");
-                std::process::exit(0);
+                return Err(CmdError(1));
             },
-        }.unwrap_or_else(|e| {
+        }.map_err(|e| {
             log::error!("Error processing command {:?}. Error: {}", self, e);
-            std::process::exit(0);
-        });
+            CmdError(1)
+        })?;
         
         log::info!("{} name for {} is {}", self.table, self.id, name);
+        
+        Ok(())
     }
 }
 
@@ -37,21 +40,23 @@ pub struct GetId {
 }
 
 impl SubCmd for GetId {
-    fn execute(&self, db: &Db, _opts: &Opts) {
+    fn execute(&self, db: &Db, _opts: &Opts) -> CmdResult {
         let id = match self.table.to_lowercase().as_str() {
             "tag" => db.get_tag_id(&self.name),
             "company" => db.get_company_id(&self.name),
             "category" => db.get_category_id(&self.name),
             _ => {
                 log::error!("Invalid table");
-                std::process::exit(0);
+                return Err(CmdError(1));
             },
-        }.unwrap_or_else(|e| {
+        }.map_err(|e| {
             log::error!("Error processing command {:?}. Error: {}", self, e);
-            std::process::exit(0);
-        });
+            CmdError(1)
+        })?;
 
         log::info!("{} id for {} is {}", self.table, self.name, id);
+        
+        Ok(())
     }
 }
 
@@ -63,53 +68,52 @@ pub struct GetCompanies;
 pub struct GetCategories;
 
 impl SubCmd for GetTags {
-    fn execute(&self, db: &Db, _opts: &Opts) {
-        let tags = db.get_all_tags().unwrap_or_else(|e| {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let tags = db.get_all_tags().map_err(|e| {
             log::error!("Error getting tags list. Error: {}", e);
-            std::process::exit(0);
-        });
+            CmdError(1)
+        })?;
         
-        // TODO: Generate view model and view renderer
+        list_all("tags", &tags, opts);
         
-        list_all("tags", &tags);
+        Ok(())
     }
 }
 
 impl SubCmd for GetCompanies {
-    fn execute(&self, db: &Db, _opts: &Opts) {
-        let companies = db.get_all_companies().unwrap_or_else(|e| {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let companies = db.get_all_companies().map_err(|e| {
             log::error!("Error getting companies list. Error: {}", e);
-            std::process::exit(0);
-        });
-
-        // TODO: Generate view model and view renderer
+            CmdError(1)
+        })?;
 
-        list_all("companies", &companies);
+        list_all("companies", &companies, opts);
+        
+        Ok(())
     }
 }
 
 impl SubCmd for GetCategories {
-    fn execute(&self, db: &Db, _opts: &Opts) {
-        let categories = db.get_all_categories().unwrap_or_else(|e| {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let categories = db.get_all_categories().map_err(|e| {
             log::error!("Error getting categories list. Error: {}", e);
-            std::process::exit(0);
-        });
-
-        // TODO: Generate view model and view renderer
+            CmdError(1)
+        })?;
 
-        list_all("categories", &categories);
+        list_all("categories", &categories, opts);
+        
+        Ok(())
     }
 }
 
-fn list_all(table: &str, list: &Vec<Name>) {
-    print!("List of {}: \n", table);
-    for value in list {
-        let id = value.id;
-        let name = &value.name;
-        let desc = &value.description;
-
-        print!("[{}]: {} - {}\n", id, name, desc);
-    }
+fn list_all(table: &str, list: &[Name], opts: &Opts) {
+    let rows = list.iter().map(|value| vec![
+        Value::from(value.id),
+        Value::from(value.name.as_str()),
+        Value::from(value.description.as_str()),
+    ]).collect();
 
-    println!();
-}
\ No newline at end of file
+    let mut report = Report::new();
+    report.add_table(table, &format!("List of {}", table), vec!["Id", "Name", "Description"], rows);
+    opts.get_renderer().print(&report);
+}
@@ -0,0 +1,102 @@
+use clap::Parser;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::commons::{Opts, parse_day};
+use crate::models::Db;
+use crate::models::audit::{self, AuditEntry};
+use crate::renderer::{Report, Value};
+
+#[derive(Parser, Debug)]
+pub struct History {
+    // Only changes to transaction, payroll, payroll-item, account, tag, company, category or attachment
+    #[clap(short, long)]
+    entity: Option<String>,
+    // First day to show, YYYY-MM-DD
+    #[clap(long)]
+    from: Option<String>,
+    // Last day to show, YYYY-MM-DD
+    #[clap(long)]
+    to: Option<String>,
+    #[clap(short='n', long, default_value="20")]
+    limit: usize,
+    // Also show the changes already undone
+    #[clap(short, long)]
+    all: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct Undo {
+    // Commands to revert, newest first. A whole import counts as one
+    #[clap(default_value="1")]
+    count: usize,
+    // Show what would be reverted without changing anything
+    #[clap(long)]
+    dry_run: bool,
+}
+
+impl SubCmd for History {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let entity = match &self.entity {
+            Some(name) => Some(audit::entity_table(name).ok_or_else(|| {
+                log::error!("Invalid entity [{}]. Available: {}", name, audit::entity_names().join(", "));
+                CmdError(1)
+            })?),
+            None => None,
+        };
+        let from = parse_day(&self.from)?;
+        let to = parse_day(&self.to)?;
+
+        let entries = db.get_history(entity, from.as_deref(), to.as_deref(), self.all, self.limit).map_err(|e| {
+            log::error!("Error getting history: {}", e);
+            CmdError(1)
+        })?;
+
+        let mut report = Report::new();
+        report.add_table("history", "History", entries_header(), entries_rows(&entries));
+        opts.get_renderer().print(&report);
+
+        Ok(())
+    }
+}
+
+impl SubCmd for Undo {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let entries = match self.dry_run {
+            true => db.get_last_batches(self.count),
+            false => db.undo(self.count),
+        }.map_err(|e| {
+            log::error!("Error undoing, nothing was reverted: {}", e);
+            CmdError(1)
+        })?;
+
+        let title = match (entries.is_empty(), self.dry_run) {
+            (true, _) => "Nothing to undo",
+            (false, true) => "Would undo",
+            (false, false) => "Undone",
+        };
+        let mut report = Report::new();
+        report.add_table("undo", title, entries_header(), entries_rows(&entries));
+        opts.get_renderer().print(&report);
+
+        Ok(())
+    }
+}
+
+fn entries_header() -> Vec<&'static str> {
+    vec!["Batch", "Time", "Command", "Action", "Entity", "Id", "Changes"]
+}
+
+fn entries_rows(entries: &[AuditEntry]) -> Vec<Vec<Value>> {
+    entries.iter().map(|e| {
+        let action = if e.undone { format!("{} (undone)", e.action) } else { e.action.to_string() };
+        vec![
+            Value::Int(e.batch),
+            Value::from(e.time.clone()),
+            Value::from(e.command.clone()),
+            Value::from(action),
+            Value::from(e.entity.clone()),
+            Value::from(e.entity_id),
+            Value::from(e.changes()),
+        ]
+    }).collect()
+}
@@ -0,0 +1,71 @@
+use clap::Parser;
+
+use std::fs;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::models::Db;
+use crate::commons::{Opts, Period};
+use crate::renderer::{Report, html};
+use crate::charts::SvgPlotter;
+use crate::view_models::balance_summary_vm::BalanceSummaryVm;
+use crate::view_models::payroll_data_vm::PayrollDataVm;
+use crate::view_models::transaction_data_vm::TransactionDataVm;
+use crate::view_models::accounts_vm::AccountsVm;
+
+#[derive(Parser, Debug)]
+pub struct HtmlReport {
+    // YYYY or YYYY-MM
+    #[clap(short, long)]
+    period: Period,
+    // Write a self-contained html page instead of printing the tables
+    #[clap(long)]
+    html: Option<String>,
+}
+
+impl SubCmd for HtmlReport {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let payrolls = db.get_payroll_data(self.period.year(), self.period.month()).map_err(|e| {
+            log::error!("Error getting payrolls: {}", e);
+            CmdError(1)
+        })?;
+
+        let transactions = db.get_transaction_data(self.period.year(), self.period.month()).map_err(|e| {
+            log::error!("Error getting transactions: {}", e);
+            CmdError(1)
+        })?;
+
+        let accounts = db.get_all_accounts().map_err(|e| {
+            log::error!("Error getting all account data: {}", e);
+            CmdError(1)
+        })?;
+
+        let balance = BalanceSummaryVm::generate(&payrolls, &transactions);
+        let mut report = Report::new();
+        balance.render(db, &mut report);
+        AccountsVm::generate(&accounts).render(&mut report);
+
+        let filename = match &self.html {
+            Some(f) => f,
+            None => {
+                opts.get_renderer().print(&report);
+                return Ok(());
+            },
+        };
+
+        let charts = vec![
+            SvgPlotter::render_inline(&balance.charts()),
+            SvgPlotter::render_inline(&TransactionDataVm::generate(&transactions).charts(db)),
+            SvgPlotter::render_inline(&PayrollDataVm::generate(&payrolls).charts(db)),
+        ];
+
+        let title = format!("Financial report {}", self.period);
+        fs::write(filename, html::render_page(&title, &report, &charts)).map_err(|e| {
+            log::error!("Error writing report to [{}]: {}", filename, e);
+            CmdError(1)
+        })?;
+
+        log::info!("Report for {} written to {}", self.period, filename);
+        
+        Ok(())
+    }
+}
@@ -1,11 +1,12 @@
-pub use sub_cmd::SubCmd;
+pub use sub_cmd::{SubCmd, CmdError, CmdResult};
 
 use std::fmt::Formatter;
-use clap::Parser;
+use clap::{AppSettings, Parser};
 
 use crate::{Db, Opts};
 
 mod sub_cmd;
+mod prompt;
 mod add_transaction;
 mod add_payroll;
 mod add_names;
@@ -16,6 +17,23 @@ mod transaction_data;
 mod parse_transaction;
 mod parse_payroll;
 mod balance_summary;
+mod export_ledger;
+mod html_report;
+mod compare;
+mod pivot;
+mod tax_summary;
+mod simulate_salary;
+mod add_payroll_item;
+mod config_cmd;
+mod shell;
+mod dashboard;
+mod completions;
+mod serve;
+mod db_encryption;
+mod backup;
+mod history;
+mod attachments;
+mod search;
 
 use add_transaction::*;
 use add_payroll::*;
@@ -27,6 +45,23 @@ use transaction_data::*;
 use parse_transaction::*;
 use parse_payroll::*;
 use balance_summary::*;
+use export_ledger::*;
+use html_report::*;
+use compare::*;
+use pivot::*;
+use tax_summary::*;
+use simulate_salary::*;
+use add_payroll_item::*;
+use config_cmd::*;
+use shell::*;
+use dashboard::*;
+use completions::*;
+use serve::*;
+use db_encryption::*;
+use backup::*;
+use history::*;
+use attachments::*;
+use search::*;
 
 #[derive(Parser, Debug)]
 pub enum SubCommand {
@@ -72,6 +107,58 @@ pub enum SubCommand {
     ParsePayroll(ParsePayroll),
     #[clap(version="1.0", author="Josef212")]
     BalanceSummary(BalanceSummary),
+    #[clap(version="1.0", author="Josef212")]
+    ExportLedger(ExportLedger),
+    #[clap(version="1.0", author="Josef212")]
+    Report(HtmlReport),
+    #[clap(version="1.0", author="Josef212")]
+    Compare(Compare),
+    #[clap(version="1.0", author="Josef212")]
+    Pivot(Pivot),
+    #[clap(version="1.0", author="Josef212")]
+    TaxSummary(TaxSummary),
+    #[clap(version="1.0", author="Josef212")]
+    SimulateSalary(SimulateSalary),
+    #[clap(version="1.0", author="Josef212")]
+    AddPayrollItem(AddPayrollItem),
+    #[clap(version="1.0", author="Josef212")]
+    AddPayrollItemP(AddPayrollItemP),
+    #[clap(version="1.0", author="Josef212")]
+    Config(ConfigCmd),
+    #[clap(version="1.0", author="Josef212")]
+    Shell(Shell),
+    #[clap(version="1.0", author="Josef212")]
+    Tui(Tui),
+    #[clap(version="1.0", author="Josef212")]
+    Completions(Completions),
+    #[clap(version="1.0", author="Josef212")]
+    Serve(Serve),
+    #[clap(version="1.0", author="Josef212")]
+    EncryptDb(EncryptDb),
+    #[clap(version="1.0", author="Josef212")]
+    DecryptDb(DecryptDb),
+    #[clap(version="1.0", author="Josef212")]
+    Rekey(Rekey),
+    #[clap(version="1.0", author="Josef212")]
+    Backup(Backup),
+    #[clap(version="1.0", author="Josef212")]
+    Restore(Restore),
+    #[clap(version="1.0", author="Josef212")]
+    History(History),
+    #[clap(version="1.0", author="Josef212")]
+    Undo(Undo),
+    #[clap(version="1.0", author="Josef212")]
+    Attach(Attach),
+    #[clap(version="1.0", author="Josef212")]
+    Open(Open),
+    #[clap(version="1.0", author="Josef212")]
+    Extract(Extract),
+    #[clap(version="1.0", author="Josef212")]
+    CheckAttachments(CheckAttachments),
+    #[clap(version="1.0", author="Josef212")]
+    Search(Search),
+    #[clap(name="__complete", setting=AppSettings::Hidden)]
+    CompleteNames(CompleteNames),
 }
 
 impl std::fmt::Display for SubCommand {
@@ -98,6 +185,32 @@ impl std::fmt::Display for SubCommand {
             SubCommand::ParseTransaction(_) => write!(f, "{}", "ParseTransaction"),
             SubCommand::ParsePayroll(_) => write!(f, "{}", "ParsePayroll"),
             SubCommand::BalanceSummary(_) => write!(f, "{}", "BalanceSummary"),
+            SubCommand::ExportLedger(_) => f.write_str("ExportLedger"),
+            SubCommand::Report(_) => f.write_str("Report"),
+            SubCommand::Compare(_) => f.write_str("Compare"),
+            SubCommand::Pivot(_) => f.write_str("Pivot"),
+            SubCommand::TaxSummary(_) => f.write_str("TaxSummary"),
+            SubCommand::SimulateSalary(_) => f.write_str("SimulateSalary"),
+            SubCommand::AddPayrollItem(_) => f.write_str("AddPayrollItem"),
+            SubCommand::AddPayrollItemP(_) => f.write_str("AddPayrollItemP"),
+            SubCommand::Config(_) => f.write_str("Config"),
+            SubCommand::Shell(_) => f.write_str("Shell"),
+            SubCommand::Tui(_) => f.write_str("Tui"),
+            SubCommand::Completions(_) => f.write_str("Completions"),
+            SubCommand::Serve(_) => f.write_str("Serve"),
+            SubCommand::EncryptDb(_) => f.write_str("EncryptDb"),
+            SubCommand::DecryptDb(_) => f.write_str("DecryptDb"),
+            SubCommand::Rekey(_) => f.write_str("Rekey"),
+            SubCommand::Backup(_) => f.write_str("Backup"),
+            SubCommand::Restore(_) => f.write_str("Restore"),
+            SubCommand::History(_) => f.write_str("History"),
+            SubCommand::Undo(_) => f.write_str("Undo"),
+            SubCommand::Attach(_) => f.write_str("Attach"),
+            SubCommand::Open(_) => f.write_str("Open"),
+            SubCommand::Extract(_) => f.write_str("Extract"),
+            SubCommand::CheckAttachments(_) => f.write_str("CheckAttachments"),
+            SubCommand::Search(_) => f.write_str("Search"),
+            SubCommand::CompleteNames(_) => f.write_str("CompleteNames"),
             
             #[allow(unreachable_patterns)]
             _ => write!(f, "Not implemented enumerator display")
@@ -106,7 +219,7 @@ impl std::fmt::Display for SubCommand {
 }
 
 impl SubCommand {
-    pub fn execute(&self, db: &Db, opts: &Opts) {
+    pub fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
         log::debug!("Processing command: {:?}", self);
         match self {
             // TODO: Can this be done generic???
@@ -131,9 +244,38 @@ impl SubCommand {
             SubCommand::ParseTransaction(cmd) => cmd.execute(db, opts),
             SubCommand::ParsePayroll(cmd) => cmd.execute(db, opts),
             SubCommand::BalanceSummary(cmd) => cmd.execute(db, opts),
+            SubCommand::ExportLedger(cmd) => cmd.execute(db, opts),
+            SubCommand::Report(cmd) => cmd.execute(db, opts),
+            SubCommand::Compare(cmd) => cmd.execute(db, opts),
+            SubCommand::Pivot(cmd) => cmd.execute(db, opts),
+            SubCommand::TaxSummary(cmd) => cmd.execute(db, opts),
+            SubCommand::SimulateSalary(cmd) => cmd.execute(db, opts),
+            SubCommand::AddPayrollItem(cmd) => cmd.execute(db, opts),
+            SubCommand::AddPayrollItemP(cmd) => cmd.execute(db, opts),
+            SubCommand::Config(cmd) => cmd.execute(db, opts),
+            SubCommand::Shell(cmd) => cmd.execute(db, opts),
+            SubCommand::Tui(cmd) => cmd.execute(db, opts),
+            SubCommand::Completions(cmd) => cmd.execute(db, opts),
+            SubCommand::Serve(cmd) => cmd.execute(db, opts),
+            SubCommand::EncryptDb(cmd) => cmd.execute(db, opts),
+            SubCommand::DecryptDb(cmd) => cmd.execute(db, opts),
+            SubCommand::Rekey(cmd) => cmd.execute(db, opts),
+            SubCommand::Backup(cmd) => cmd.execute(db, opts),
+            SubCommand::Restore(cmd) => cmd.execute(db, opts),
+            SubCommand::History(cmd) => cmd.execute(db, opts),
+            SubCommand::Undo(cmd) => cmd.execute(db, opts),
+            SubCommand::Attach(cmd) => cmd.execute(db, opts),
+            SubCommand::Open(cmd) => cmd.execute(db, opts),
+            SubCommand::Extract(cmd) => cmd.execute(db, opts),
+            SubCommand::CheckAttachments(cmd) => cmd.execute(db, opts),
+            SubCommand::Search(cmd) => cmd.execute(db, opts),
+            SubCommand::CompleteNames(cmd) => cmd.execute(db, opts),
 
             #[allow(unreachable_patterns)]
-            _ => log::error!("SubCommand {} not implemented.", self),
+            _ => {
+                log::error!("SubCommand {} not implemented.", self);
+                Err(CmdError(1))
+            },
         }
     }
 }
\ No newline at end of file
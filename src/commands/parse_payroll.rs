@@ -1,18 +1,136 @@
 use clap::Parser;
 use log;
 
-use crate::commands::sub_cmd::SubCmd;
+use std::path::Path;
+use csv::StringRecord;
+use serde::Deserialize;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::commands::add_payroll::PayrollChecks;
 use crate::models::Db;
 use crate::commons::Opts;
+use crate::models::payroll::Payroll;
 
 #[derive(Parser, Debug)]
 pub struct ParsePayroll {
+    // Csv with date, gross, net, ss, irpf, company and category columns
     filename: String,
+    #[clap(flatten)]
+    checks: PayrollChecks,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PayrollRow {
+    date: String,
+    gross: String,
+    net: String,
+    ss: String,
+    irpf: String,
+    company: String,
+    category: String,
+}
+
+impl PayrollRow {
+    fn to_payroll(&self, db: &Db) -> Result<Payroll, Vec<String>> {
+        let mut errors = Vec::new();
+        
+        let mut amount = |name: &str, value: &str| {
+            value.replace(',', ".").trim().parse::<f32>().unwrap_or_else(|e| {
+                errors.push(format!("Error parsing {} [{}]. E: {}", name, value, e));
+                0.0
+            })
+        };
+        
+        let gross = amount("gross", &self.gross);
+        let net = amount("net", &self.net);
+        let ss = amount("ss", &self.ss);
+        let irpf = amount("irpf", &self.irpf);
+        
+        let company_id = db.get_company_id(&self.company).unwrap_or_else(|e| {
+            errors.push(format!("Error getting company id from [{}]. E: {}", self.company, e));
+            0
+        });
+        
+        // Categories can be given by name or by id.
+        let category_id = self.category.trim().parse::<i32>().or_else(|_| db.get_category_id(&self.category)).unwrap_or_else(|e| {
+            errors.push(format!("Error getting category id from [{}]. E: {}", self.category, e));
+            0
+        });
+        
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        
+        Ok(Payroll::new(&self.date, gross, net, ss, irpf, company_id, category_id))
+    }
 }
 
 impl SubCmd for ParsePayroll {
-    fn execute(&self, _db: &Db, _opts: &Opts) {
-        log::trace!("");
-        todo!()
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        if !Path::new(&self.filename).exists() {
+            log::error!("File [{}] does not exists", self.filename);
+            return Err(CmdError(1));
+        }
+        
+        log::info!("Parsing payrolls from file: {}", self.filename);
+        
+        let mut reader = csv::Reader::from_path(&self.filename).map_err(|e| {
+            log::error!("Error creating csv reader from file [{}]. Error: {}", self.filename, e);
+            CmdError(1)
+        })?;
+        
+        let validator = self.checks.validator(opts);
+        let mut payroll_rows = 0;
+        let mut error_rows = 0;
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        
+        for (i, result) in reader.records().enumerate() {
+            let record = result.unwrap_or_else(|e| {
+                errors.push((i, format!("Error getting string record. E: {}", e)));
+                StringRecord::new()
+            });
+            
+            let row: PayrollRow = record
+                .deserialize(None)
+                .unwrap_or_else(|e| {
+                    errors.push((i, format!("Error deserializing row. E: {}", e)));
+                    PayrollRow::default()
+                });
+            
+            let payroll = row.to_payroll(db).and_then(|mut p| validator.validate(db, &mut p).map(|w| (p, w)));
+            match payroll {
+                Err(er) => {
+                    error_rows += 1;
+                    for e in er {
+                        errors.push((i, e));
+                    }
+                },
+                Ok((p, w)) => {
+                    warnings.extend(w.into_iter().map(|w| (i, w)));
+                    match db.insert_payroll(&p) {
+                        Ok(_) => payroll_rows += 1,
+                        Err(e) => {
+                            error_rows += 1;
+                            errors.push((i, format!("Error inserting payroll. E: {}", e)));
+                        },
+                    }
+                },
+            }
+        }
+        
+        log::info!("Parse complete. Success: {} - Error: {}", payroll_rows, error_rows);
+        for (i, w) in warnings {
+            log::warn!("[L:{}] {}", i, w);
+        }
+        
+        if !errors.is_empty() {
+            log::info!("Errors:");
+            for (i, e) in errors {
+                log::info!("[L:{}] {}", i, e);
+            }
+        }
+        
+        Ok(())
     }
-}
\ No newline at end of file
+}
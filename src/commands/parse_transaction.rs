@@ -2,82 +2,131 @@ use clap::Parser;
 use log;
 
 use std::path::Path;
+use chrono::NaiveDate;
 use csv::StringRecord;
-use serde::Deserialize;
 
-use crate::commands::sub_cmd::SubCmd;
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
 use crate::models::Db;
-use crate::commons::Opts;
+use crate::commons::{Opts, ImportProfile};
 use crate::models::transaction::Transaction;
 
 #[derive(Parser, Debug)]
 pub struct ParseTransaction {
     filename: String,
+    // Import profile from the config file describing the csv layout
+    #[clap(short, long)]
+    profile: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug)]
 struct TransRow {
     amount: String,
     date: String,
     name: String,
     tag: String,
+    notes: String,
 }
 
 impl TransRow {
-    fn new() -> TransRow {
+    fn from_record(record: &StringRecord, columns: &[Option<usize>; 5]) -> TransRow {
+        let get = |i: Option<usize>| i.and_then(|i| record.get(i)).unwrap_or("").trim().to_string();
+        
         TransRow {
-            amount: String::new(),
-            date: String::new(),
-            name: String::new(),
-            tag: String::new(),
+            amount: get(columns[0]),
+            date: get(columns[1]),
+            name: get(columns[2]),
+            tag: get(columns[3]),
+            notes: get(columns[4]),
         }
     }
     
-    fn to_transaction(&self, db: &Db) -> Result<Transaction, Vec<String>> {
+    fn to_transaction(&self, db: &Db, profile: &ImportProfile) -> Result<Transaction, Vec<String>> {
         let mut errors = Vec::new();
         
-        let amount = self.amount.replace(',', ".");
+        let amount = if profile.decimal_comma { self.amount.replace('.', "").replace(',', ".") } else { self.amount.replace(',', ".") };
         let amount = amount.parse::<f32>().unwrap_or_else(|e| {
             let e = format!("Error parsing amount [{}]. E: {}", self.amount, e);
             errors.push(e);
             
             0.0
         });
+        let amount = if profile.negate { -amount } else { amount };
         
-        let tag_id = db.get_tag_id(&self.tag).unwrap_or_else(|e| {
-            let e = format!("Error getting tag id from [{}]. E: {}", self.tag, e);
+        let tag = match (self.tag.is_empty(), &profile.default_tag) {
+            (true, Some(t)) => t,
+            _ => &self.tag,
+        };
+        let tag_id = db.get_tag_id(tag).unwrap_or_else(|e| {
+            let e = format!("Error getting tag id from [{}]. E: {}", tag, e);
             errors.push(e);
             
             0
         });
         
-        // TODO: Verify date has a good format. (YYYY-MM-DD)
-        let date = self.date.replace('/', "-");
+        let date = NaiveDate::parse_from_str(&self.date, &profile.date_format)
+            .or_else(|e| NaiveDate::parse_from_str(&self.date.replace('/', "-"), "%Y-%m-%d").map_err(|_| e))
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|e| {
+                errors.push(format!("Error parsing date [{}] with format {}. E: {}", self.date, profile.date_format, e));
+                String::new()
+            });
         
         if errors.len() > 0 {
             return Err(errors);
         }
         
-        Ok(Transaction::new(&self.name, &date, amount, tag_id))
+        let mut transaction = Transaction::new(&self.name, &date, amount, tag_id);
+        transaction.notes = Some(self.notes.clone()).filter(|n| !n.is_empty());
+        
+        Ok(transaction)
     }
 }
 
 impl SubCmd for ParseTransaction {
-    fn execute(&self, db: &Db, _opts: &Opts) {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
         if !Path::new(&self.filename).exists() {
             log::error!("File [{}] does not exists", self.filename);
-            std::process::exit(0);
+            return Err(CmdError(1));
         }
         
         log::info!("Parsing transactions from file: {}", self.filename);
         
-        let mut reader = csv::Reader::from_path(&self.filename).unwrap_or_else(|e| {
-            log::error!("Error creating csv reader from file [{}]. Error: {}", self.filename, e);
-            std::process::exit(0);
-        });
+        let profile = match &self.profile {
+            Some(name) => opts.get_config().import.get(name).cloned().ok_or_else(|| {
+                log::error!("Import profile [{}] not found in the config file", name);
+                CmdError(1)
+            })?,
+            None => ImportProfile::default(),
+        };
+        
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(profile.delimiter as u8)
+            .from_path(&self.filename)
+            .map_err(|e| {
+                log::error!("Error creating csv reader from file [{}]. Error: {}", self.filename, e);
+                CmdError(1)
+            })?;
         
         log::trace!("Csv reader created successfully");
         
+        let headers = reader.headers().cloned().map_err(|e| {
+            log::error!("Error reading csv headers from [{}]. Error: {}", self.filename, e);
+            CmdError(1)
+        })?;
+        
+        let column = |name: &str| headers.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+        let columns = [column(&profile.amount), column(&profile.date), column(&profile.name), column(&profile.tag), column(&profile.notes)];
+        for (i, name) in [&profile.amount, &profile.date, &profile.name].iter().enumerate() {
+            if columns[i].is_none() {
+                log::error!("Column [{}] not found in [{}]", name, self.filename);
+                return Err(CmdError(1));
+            }
+        }
+        if columns[3].is_none() && profile.default_tag.is_none() {
+            log::error!("Column [{}] not found in [{}] and the profile has no default_tag", profile.tag, self.filename);
+            return Err(CmdError(1));
+        }
+        
         let mut i = 0;
         let mut transaction_rows = 0;
         let mut error_rows = 0;
@@ -89,14 +138,8 @@ impl SubCmd for ParseTransaction {
                 StringRecord::new()
             });
             
-            let row: TransRow = record
-                .deserialize(None)
-                .unwrap_or_else(|e| {
-                    errors.push((i, format!("Error deserializing row. E: {}", e)));
-                    TransRow::new()
-                });
-            
-            let transaction = row.to_transaction(db);
+            let row = TransRow::from_record(&record, &columns);
+            let transaction = row.to_transaction(db, &profile);
             match transaction {
                 Err(er) => { 
                     for e in er {
@@ -126,5 +169,7 @@ impl SubCmd for ParseTransaction {
                 log::info!("[L:{}] {}", i, e);
             }
         }
+        
+        Ok(())
     }
 }
@@ -1,10 +1,12 @@
 use clap::Parser;
 use log;
 
-use crate::commands::sub_cmd::SubCmd;
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError, check_plot, write_svg};
 use crate::models::Db;
 use crate::commons::Opts;
 use crate::view_models::payroll_data_vm::PayrollDataVm;
+use crate::renderer::Report;
+use crate::models::validation::PayrollValidator;
 
 #[derive(Parser, Debug)]
 pub struct PayrollData {
@@ -14,26 +16,51 @@ pub struct PayrollData {
     month: Option<u32>,
     #[clap(short, long)]
     list: bool,
+    // Breakdown per payroll item concept
+    #[clap(short, long)]
+    items: bool,
     #[clap(short, long)]
     plot: bool,
+    #[clap(long)]
+    plot_out: Option<String>,
 }
 
 impl SubCmd for PayrollData {
-    fn execute(&self, db: &Db, _opts: &Opts) {
-        let payrolls = db.get_payroll_data(self.year, self.month).unwrap_or_else(|e| {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        check_plot(self.plot, opts)?;
+        
+        let payrolls = db.get_payroll_data(self.year, self.month).map_err(|e| {
             log::error!("Error getting payrolls: {}", e);
-            std::process::exit(0);
-        });
+            CmdError(1)
+        })?;
         
         let vm = PayrollDataVm::generate(&payrolls);
-        vm.render(db);
+        let mut report = Report::new();
+        vm.render(db, &mut report);
         
         if self.list {
-            vm.full_list(db);
+            vm.full_list(db, &mut report);
+        }
+        
+        if self.items {
+            let items = db.get_payroll_items_data(self.year, self.month).map_err(|e| {
+                log::error!("Error getting payroll items: {}", e);
+                CmdError(1)
+            })?;
+            
+            vm.items(&items, &PayrollValidator { tolerance: opts.get_payroll_tolerance(), record_other: false }, &mut report);
         }
         
+        opts.get_renderer().print(&report);
+        
         if self.plot {
-            vm.plot(db);
+            vm.plot(db, &opts.get_plotter());
+        }
+        
+        if let Some(filename) = &self.plot_out {
+            write_svg(filename, &vm.charts(db))?;
         }
+        
+        Ok(())
     }
 }
\ No newline at end of file
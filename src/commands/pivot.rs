@@ -0,0 +1,58 @@
+use clap::Parser;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::models::Db;
+use crate::commons::Opts;
+use crate::charts::Charset;
+use crate::renderer::Report;
+use crate::view_models::pivot_vm::{PivotVm, PivotSource, PivotPeriod};
+
+#[derive(Parser, Debug)]
+pub struct Pivot {
+    #[clap(short, long)]
+    year: Option<u32>,
+    // tags, companies or categories
+    #[clap(short, long, default_value="tags")]
+    source: PivotSource,
+    // month or quarter. Defaults to report.pivot_by from the config, or month
+    #[clap(short, long)]
+    by: Option<PivotPeriod>,
+}
+
+impl SubCmd for Pivot {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let by = match (self.by, &opts.get_config().report.pivot_by) {
+            (Some(by), _) => by,
+            (None, Some(by)) => by.parse().map_err(|e| {
+                log::error!("Invalid report.pivot_by in config: {}", e);
+                CmdError(1)
+            })?,
+            (None, None) => PivotPeriod::Month,
+        };
+        
+        let vm = match self.source {
+            PivotSource::Tags => {
+                let transactions = db.get_transaction_data(self.year, None).map_err(|e| {
+                    log::error!("Error getting transactions: {}", e);
+                    CmdError(1)
+                })?;
+                
+                PivotVm::from_transactions(&transactions, by)
+            },
+            _ => {
+                let payrolls = db.get_payroll_data(self.year, None).map_err(|e| {
+                    log::error!("Error getting payrolls: {}", e);
+                    CmdError(1)
+                })?;
+                
+                PivotVm::from_payrolls(&payrolls, self.source, by)
+            },
+        };
+        
+        let mut report = Report::new();
+        vm.render(db, Charset::detect(), &mut report);
+        opts.get_renderer().print(&report);
+        
+        Ok(())
+    }
+}
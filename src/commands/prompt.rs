@@ -0,0 +1,234 @@
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use chrono::{Local, NaiveDate};
+
+use crate::commands::sub_cmd::CmdError;
+use crate::commons::Opts;
+use crate::models::Name;
+use crate::renderer::{OutputFormat, Renderer, Report, Value};
+
+const MAX_CHOICES: usize = 9;
+
+// What the user picked from a list of existing names.
+pub enum Selection {
+    Existing(Name),
+    New(String),
+}
+
+// Reads one answer. An empty answer takes the default, and a closed input cancels the command.
+fn read_answer(label: &str, default: Option<&str>) -> Result<String, CmdError> {
+    match default {
+        Some(d) if !d.is_empty() => print!("{} [{}]: ", label, d),
+        _ => print!("{}: ", label),
+    }
+    let _ = io::stdout().flush();
+
+    let mut buffer = String::new();
+    let read = io::stdin().read_line(&mut buffer).map_err(|e| {
+        log::error!("Error reading input: {}", e);
+        CmdError(1)
+    })?;
+    if read == 0 {
+        println!();
+        log::error!("Input closed, nothing inserted.");
+        return Err(CmdError(1));
+    }
+
+    let answer = buffer.trim();
+    match (answer.is_empty(), default) {
+        (true, Some(d)) => Ok(String::from(d)),
+        _ => Ok(String::from(answer)),
+    }
+}
+
+// Asks until the answer passes the check, which can also normalize it.
+pub fn ask_with<T>(label: &str, default: Option<&str>, check: impl Fn(&str) -> Result<T, String>) -> Result<T, CmdError> {
+    loop {
+        let answer = read_answer(label, default)?;
+        match check(&answer) {
+            Ok(value) => return Ok(value),
+            Err(e) => println!("  {}", e),
+        }
+    }
+}
+
+pub fn ask<T>(label: &str, default: Option<T>) -> Result<T, CmdError>
+    where T: FromStr + Display, T::Err: Display {
+    let default = default.map(|d| d.to_string());
+    ask_with(label, default.as_deref(), |answer| {
+        answer.parse::<T>().map_err(|e| format!("Invalid value [{}]: {}", answer, e))
+    })
+}
+
+pub fn ask_text(label: &str, default: Option<&str>) -> Result<String, CmdError> {
+    ask_with(label, default, |answer| match answer.is_empty() {
+        true => Err(String::from("A value is required")),
+        false => Ok(String::from(answer)),
+    })
+}
+
+// Amounts accept a decimal comma.
+pub fn ask_amount(label: &str, default: Option<f32>) -> Result<f32, CmdError> {
+    let default = default.map(|d| format!("{:.2}", d));
+    ask_with(label, default.as_deref(), |answer| {
+        answer.replace(',', ".").parse::<f32>()
+            .ok()
+            .filter(|a| a.is_finite())
+            .ok_or_else(|| format!("Invalid amount [{}]", answer))
+    })
+}
+
+// Dates are stored as YYYY-MM-DD and default to today.
+pub fn ask_date(label: &str, default: Option<&str>) -> Result<String, CmdError> {
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    ask_with(label, Some(default.unwrap_or(&today)), |answer| {
+        NaiveDate::parse_from_str(&answer.replace('/', "-"), "%Y-%m-%d")
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .map_err(|e| format!("Invalid date [{}], expected YYYY-MM-DD: {}", answer, e))
+    })
+}
+
+// Prints what is about to be inserted and asks for confirmation. Always a table since it is shown between prompts.
+pub fn confirm_summary(opts: &Opts, title: &str, values: Vec<(&str, Value)>) -> Result<bool, CmdError> {
+    let mut report = Report::new();
+    report.add_summary("summary", title, values);
+    Renderer::new(OutputFormat::Table, opts.get_no_color()).with_formats(opts.get_formats()).print(&report);
+
+    confirm("Insert?", true)
+}
+
+pub fn confirm(label: &str, default: bool) -> Result<bool, CmdError> {
+    let hint = if default { "Y/n" } else { "y/N" };
+    ask_with(&format!("{} ({})", label, hint), None, |answer| match answer.to_lowercase().as_str() {
+        "" => Ok(default),
+        "y" | "yes" => Ok(true),
+        "n" | "no" => Ok(false),
+        _ => Err(String::from("Answer y or n")),
+    })
+}
+
+// Asks twice without echoing, until both answers match.
+pub fn ask_new_passphrase() -> Result<String, CmdError> {
+    loop {
+        let first = read_passphrase("New passphrase")?;
+        if first.is_empty() {
+            println!("  The passphrase can not be empty");
+            continue;
+        }
+
+        if read_passphrase("Repeat passphrase")? == first {
+            return Ok(first);
+        }
+        println!("  Passphrases do not match");
+    }
+}
+
+fn read_passphrase(label: &str) -> Result<String, CmdError> {
+    rpassword::prompt_password(format!("{}: ", label)).map_err(|e| {
+        log::error!("Error reading passphrase: {}", e);
+        CmdError(1)
+    })
+}
+
+// Picks one of the names. The answer can be the name, part of it or a few of its letters in order,
+// and a number when several names match. Unknown names are returned as new when allowed.
+pub fn select(label: &str, names: &[Name], default: Option<&str>, allow_new: bool) -> Result<Selection, CmdError> {
+    loop {
+        let answer = ask_text(label, default)?;
+
+        if let Some(n) = names.iter().find(|n| n.name.eq_ignore_ascii_case(&answer)) {
+            return Ok(Selection::Existing(n.clone()));
+        }
+
+        let matches = fuzzy_matches(&answer, names);
+        match matches.len() {
+            0 if allow_new => return Ok(Selection::New(answer)),
+            0 => println!("  No match for [{}]", answer),
+            1 => {
+                if confirm(&format!("  Use [{}]?", matches[0].name), true)? {
+                    return Ok(Selection::Existing(matches[0].clone()));
+                }
+                if allow_new && confirm(&format!("  Use new [{}]?", answer), false)? {
+                    return Ok(Selection::New(answer));
+                }
+            },
+            _ => {
+                for (i, n) in matches.iter().take(MAX_CHOICES).enumerate() {
+                    println!("  {}) {}", i + 1, n.name);
+                }
+                let choice = ask_with("  Choice (empty to retype)", Some("0"), |a| {
+                    a.parse::<usize>().ok().filter(|c| *c <= matches.len().min(MAX_CHOICES)).ok_or_else(|| format!("Invalid choice [{}]", a))
+                })?;
+                if choice > 0 {
+                    return Ok(Selection::Existing(matches[choice - 1].clone()));
+                }
+            },
+        }
+    }
+}
+
+// Like select but only existing names are accepted.
+pub fn pick(label: &str, names: &[Name], default: Option<&str>) -> Result<Name, CmdError> {
+    match select(label, names, default, false)? {
+        Selection::Existing(name) => Ok(name),
+        Selection::New(_) => unreachable!("select only returns new names when allowed"),
+    }
+}
+
+fn fuzzy_matches<'n>(query: &str, names: &'n [Name]) -> Vec<&'n Name> {
+    let query = query.to_lowercase();
+    let mut scored: Vec<(u8, &Name)> = names.iter().filter_map(|n| {
+        let name = n.name.to_lowercase();
+        let score = if name.starts_with(&query) {
+            0
+        } else if name.contains(&query) {
+            1
+        } else if is_subsequence(&query, &name) {
+            2
+        } else {
+            return None;
+        };
+        Some((score, n))
+    }).collect();
+
+    scored.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.name.len().cmp(&b.1.name.len())));
+    scored.into_iter().map(|(_, n)| n).collect()
+}
+
+fn is_subsequence(query: &str, name: &str) -> bool {
+    let mut chars = name.chars();
+    query.chars().filter(|c| !c.is_whitespace()).all(|q| chars.any(|c| c == q))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<Name> {
+        list.iter().enumerate().map(|(i, n)| Name { id: i as i32 + 1, name: String::from(*n), description: String::new() }).collect()
+    }
+
+    fn matched(query: &str, list: &[Name]) -> Vec<String> {
+        fuzzy_matches(query, list).into_iter().map(|n| n.name.clone()).collect()
+    }
+
+    #[test]
+    fn prefixes_go_before_contains_and_subsequences() {
+        let list = names(&["Supermarket", "Market stall", "Mortgage", "Markets"]);
+        assert_eq!(matched("mark", &list), vec!["Markets", "Market stall", "Supermarket"]);
+        // Same kind of match, shorter names first.
+        assert_eq!(matched("mrt", &list), vec!["Markets", "Mortgage", "Supermarket", "Market stall"]);
+    }
+
+    #[test]
+    fn matching_ignores_case_and_spaces_in_the_query() {
+        let list = names(&["Eating out", "Electricity"]);
+        assert_eq!(matched("EAT", &list), vec!["Eating out"]);
+        assert_eq!(matched("e o", &list), vec!["Eating out"]);
+        assert!(matched("xyz", &list).is_empty());
+        assert!(matched("tuo", &list).is_empty());
+        assert_eq!(matched("", &list).len(), 2);
+    }
+}
@@ -0,0 +1,100 @@
+use clap::Parser;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::commons::{Opts, parse_day};
+use crate::models::Db;
+use crate::models::search::{self, SearchFilter, SearchHit};
+use crate::renderer::{OutputFormat, Report, Value};
+
+#[derive(Parser, Debug)]
+pub struct Search {
+    // Words to look for in names, notes, tags, companies and categories. All of them must match,
+    // each one as the start of a word
+    #[clap(required = true)]
+    query: Vec<String>,
+    // Only transaction or payroll
+    #[clap(short, long)]
+    entity: Option<String>,
+    #[clap(short, long)]
+    year: Option<u32>,
+    #[clap(short, long)]
+    month: Option<u32>,
+    // First day to search, YYYY-MM-DD
+    #[clap(long)]
+    from: Option<String>,
+    // Last day to search, YYYY-MM-DD
+    #[clap(long)]
+    to: Option<String>,
+    // Smallest amount, payrolls are compared by net
+    #[clap(long, allow_hyphen_values = true)]
+    min: Option<f32>,
+    // Largest amount, payrolls are compared by net
+    #[clap(long, allow_hyphen_values = true)]
+    max: Option<f32>,
+    #[clap(short = 'n', long, default_value = "50")]
+    limit: usize,
+    // Pass the query to sqlite as is, for fts5 syntax like OR, NOT, NEAR or name:word
+    #[clap(long)]
+    raw: bool,
+}
+
+impl SubCmd for Search {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        if self.year.is_none() && self.month.is_some() {
+            log::error!("A month needs a year to search in");
+            return Err(CmdError(1));
+        }
+
+        let kind = match &self.entity {
+            Some(name) => Some(search::kind(name).ok_or_else(|| {
+                log::error!("Invalid entity [{}]. Available: {}", name, search::KINDS.join(", "));
+                CmdError(1)
+            })?),
+            None => None,
+        };
+
+        let text = self.query.join(" ");
+        let query = if self.raw { text.clone() } else { search::match_query(&text) };
+        let filter = SearchFilter {
+            kind: kind.map(String::from),
+            year: self.year,
+            month: self.month,
+            from: parse_day(&self.from)?,
+            to: parse_day(&self.to)?,
+            min: self.min,
+            max: self.max,
+            limit: self.limit,
+        };
+
+        // Colors would throw the table widths off, so matches are marked with plain text.
+        let marks = match opts.get_output() {
+            OutputFormat::Table => ("[", "]"),
+            OutputFormat::Markdown => ("**", "**"),
+            OutputFormat::Json | OutputFormat::Csv => ("", ""),
+        };
+
+        let hits = db.search(&query, &filter, marks).map_err(|e| {
+            log::error!("Error searching [{}]: {}", text, e);
+            CmdError(1)
+        })?;
+
+        let mut report = Report::new();
+        report.add_table("results", &format!("Matches of \"{}\"", text), vec!["Type", "Id", "Date", "Amount", "Name", "Tag / Category", "Notes", "Score"], rows(&hits));
+        opts.get_renderer().print(&report);
+
+        Ok(())
+    }
+}
+
+fn rows(hits: &[SearchHit]) -> Vec<Vec<Value>> {
+    hits.iter().map(|h| vec![
+        Value::from(h.kind.as_str()),
+        Value::from(h.id),
+        Value::date(&h.date),
+        Value::from(h.amount),
+        Value::from(h.name.clone().unwrap_or_default()),
+        Value::from(h.names.clone().unwrap_or_default()),
+        Value::from(h.notes.clone().unwrap_or_default()),
+        Value::from(h.score as f32),
+    ]).collect()
+}
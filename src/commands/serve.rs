@@ -0,0 +1,36 @@
+use clap::Parser;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::commons::{Opts, DEFAULT_BIND};
+use crate::models::Db;
+use crate::server::Server;
+
+#[derive(Parser, Debug)]
+pub struct Serve {
+    // Address to listen on. Defaults to server.bind from the config or 127.0.0.1:8080
+    #[clap(short, long)]
+    bind: Option<String>,
+}
+
+impl SubCmd for Serve {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let config = &opts.get_config().server;
+        let bind = self.bind.clone().or_else(|| config.bind.clone()).unwrap_or_else(|| String::from(DEFAULT_BIND));
+
+        let token = config.token.as_deref().ok_or_else(|| {
+            log::error!("Set server.token in the config file, clients must send it as \"Authorization: Bearer <token>\"");
+            CmdError(1)
+        })?;
+
+        let server = Server::bind(&bind, token).map_err(|e| {
+            log::error!("Error listening on [{}]: {}", bind, e);
+            CmdError(1)
+        })?.with_payroll_tolerance(opts.get_payroll_tolerance());
+
+        let addr = server.addr().map(|a| a.to_string()).unwrap_or(bind);
+        println!("Serving {} on http://{}", opts.get_db_name(), addr);
+        server.run(db);
+
+        Ok(())
+    }
+}
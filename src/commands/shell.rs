@@ -0,0 +1,232 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use clap::{AppSettings, IntoApp, Parser};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::commands::SubCommand;
+use crate::models::Db;
+use crate::commons::{self, Opts};
+
+const PROMPT: &str = "greedy> ";
+const BUILTINS: [&str; 4] = ["exit", "quit", "help", "repeat"];
+
+#[derive(Parser, Debug)]
+pub struct Shell {
+    // History file. Defaults to $XDG_DATA_HOME/gitgud-greedy/shell_history
+    #[clap(long)]
+    history: Option<String>,
+}
+
+// A shell line is a subcommand without the binary name or global options.
+#[derive(Parser, Debug)]
+#[clap(setting = AppSettings::NoBinaryName)]
+struct ShellLine {
+    #[clap(subcommand)]
+    sub_cmd: SubCommand,
+}
+
+struct ShellHelper {
+    commands: Vec<String>,
+    names: Vec<String>,
+}
+
+impl ShellHelper {
+    fn new(db: &Db) -> Self {
+        let mut commands: Vec<String> = ShellLine::into_app().get_subcommands()
+            .filter(|c| !c.is_set(AppSettings::Hidden))
+            .map(|c| c.get_name().to_string())
+            .collect();
+        commands.extend(BUILTINS.iter().map(|b| b.to_string()));
+        commands.sort();
+        commands.dedup();
+
+        let mut helper = Self { commands, names: Vec::new() };
+        helper.load_names(db);
+        helper
+    }
+
+    // Names can change after every command so they are reloaded each time.
+    fn load_names(&mut self, db: &Db) {
+        self.names = vec![db.get_all_tags(), db.get_all_companies(), db.get_all_categories()]
+            .into_iter()
+            .flat_map(|names| names.unwrap_or_default())
+            .map(|n| n.name)
+            .collect();
+        self.names.sort();
+        self.names.dedup();
+    }
+}
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line.rfind(' ').map(|i| i + 1).unwrap_or(0);
+        let word = line[start..].trim_start_matches(['"', '\'']);
+        let previous: Vec<&str> = line[..start].split_whitespace().collect();
+        let first = previous.is_empty() || previous == ["repeat"];
+
+        let candidates = if first { &self.commands } else { &self.names };
+        let pairs = candidates.iter()
+            .filter(|c| c.to_lowercase().starts_with(&word.to_lowercase()))
+            .map(|c| Pair {
+                display: c.clone(),
+                replacement: if c.contains(' ') { format!("\"{}\"", c) } else { c.clone() },
+            })
+            .collect();
+
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+impl Validator for ShellHelper {}
+impl Helper for ShellHelper {}
+
+impl SubCmd for Shell {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let mut editor = Editor::<ShellHelper>::new();
+        editor.set_helper(Some(ShellHelper::new(db)));
+
+        let history = self.history.as_ref().map(PathBuf::from).or_else(history_path);
+        if let Some(path) = &history {
+            if editor.load_history(path).is_err() {
+                log::debug!("No shell history found at {}", path.display());
+            }
+        }
+
+        println!("Type a subcommand, 'repeat <subcommand>' to run it several times, 'help' or 'exit'.");
+
+        loop {
+            let line = match editor.readline(PROMPT) {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(e) => {
+                    log::error!("Error reading line: {}", e);
+                    break;
+                },
+            };
+
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            editor.add_history_entry(line);
+
+            let mut args = match split_line(line) {
+                Some(args) => args,
+                None => continue,
+            };
+
+            match args[0].as_str() {
+                "exit" | "quit" => break,
+                "help" if args.len() == 1 => args[0] = String::from("--help"),
+                _ => (),
+            }
+
+            let repeat = args[0] == "repeat";
+            if repeat {
+                args.remove(0);
+            }
+
+            run_line(&args, repeat, db, opts);
+
+            if let Some(helper) = editor.helper_mut() {
+                helper.load_names(db);
+            }
+        }
+
+        if let Some(path) = &history {
+            if let Some(dir) = path.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            editor.save_history(path).map_err(|e| {
+                log::error!("Error saving shell history to {}: {}", path.display(), e);
+                CmdError(1)
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+// Words of a line, None for comments like `# receipts from today` or lines that can not be split.
+fn split_line(line: &str) -> Option<Vec<String>> {
+    match shell_words::split(line) {
+        Ok(args) if args.is_empty() => None,
+        Ok(args) => Some(args),
+        Err(e) => {
+            log::error!("Error splitting line: {}", e);
+            None
+        },
+    }
+}
+
+fn run_line(args: &[String], repeat: bool, db: &Db, opts: &Opts) {
+    let cmd = match ShellLine::try_parse_from(args) {
+        Ok(line) => line.sub_cmd,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        },
+    };
+
+    if let SubCommand::Shell(_) = cmd {
+        log::error!("Already inside a shell");
+        return;
+    }
+
+    loop {
+        // Each line is undone on its own.
+        db.start_batch(&args.join(" "));
+        // Errors have been logged already and must not end the session.
+        let result = cmd.execute(db, opts);
+        // An encrypted db lives in memory, keep the file up to date in case the session dies.
+        if let Err(e) = db.save() {
+            log::error!("Error saving db [{}]: {}", db.get_name(), e);
+        }
+        if !repeat || result.is_err() || !ask_again() {
+            break;
+        }
+    }
+}
+
+fn ask_again() -> bool {
+    print!("Another? [Y/n] ");
+    let _ = io::stdout().flush();
+
+    let mut buffer = String::new();
+    match io::stdin().read_line(&mut buffer) {
+        Ok(0) | Err(_) => false,
+        Ok(_) => !buffer.trim().eq_ignore_ascii_case("n"),
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    commons::data_dir().map(|d| d.join("shell_history"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comment_lines_are_skipped() {
+        assert_eq!(split_line("# receipts from today"), None);
+        assert_eq!(split_line("add-tag Food # groceries"), Some(vec![String::from("add-tag"), String::from("Food")]));
+        assert_eq!(split_line("add-tag \"Food"), None);
+    }
+}
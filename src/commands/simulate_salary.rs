@@ -0,0 +1,78 @@
+use clap::Parser;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::commands::tax_summary::IrpfArgs;
+use crate::models::Db;
+use crate::commons::Opts;
+use crate::renderer::Report;
+use crate::tax::SsModel;
+use crate::view_models::salary_simulation_vm::SalarySimulationVm;
+
+#[derive(Parser, Debug)]
+pub struct SimulateSalary {
+    // Yearly gross salary
+    #[clap(short, long)]
+    gross: f32,
+    // Number of payments per year, 12 or 14 with two extra payments
+    #[clap(short, long, default_value="14")]
+    payments: u32,
+    // Employee SS rate in %. Defaults to the 2021 rate for a permanent contract
+    #[clap(long)]
+    ss_rate: Option<f32>,
+    // Monthly SS contribution base limits
+    #[clap(long)]
+    ss_min_base: Option<f32>,
+    #[clap(long)]
+    ss_max_base: Option<f32>,
+    #[clap(flatten)]
+    irpf: IrpfArgs,
+    // Compare the model against the stored payrolls, per year
+    #[clap(short, long)]
+    calibrate: bool,
+    // Only calibrate against this year
+    #[clap(short, long)]
+    year: Option<u32>,
+}
+
+impl SubCmd for SimulateSalary {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        if self.gross < 0.0 || self.payments < 12 {
+            log::error!("Gross must be non-negative and payments at least 12");
+            return Err(CmdError(1));
+        }
+        
+        let mut ss_model = SsModel::default();
+        if let Some(rate) = self.ss_rate {
+            ss_model.rate = rate;
+        }
+        if let Some(base) = self.ss_min_base {
+            ss_model.min_base = base;
+        }
+        if let Some(base) = self.ss_max_base {
+            ss_model.max_base = base;
+        }
+        
+        if ss_model.min_base > ss_model.max_base {
+            log::error!("SS min base {} is greater than the max base {}", ss_model.min_base, ss_model.max_base);
+            return Err(CmdError(1));
+        }
+        
+        let irpf_model = self.irpf.model();
+        let vm = SalarySimulationVm::generate(self.gross, self.payments, &ss_model, &irpf_model);
+        let mut report = Report::new();
+        vm.render(&mut report);
+        
+        if self.calibrate {
+            let payrolls = db.get_payroll_data(self.year, None).map_err(|e| {
+                log::error!("Error getting payrolls: {}", e);
+                CmdError(1)
+            })?;
+            
+            vm.calibrate(&payrolls, &mut report);
+        }
+        
+        opts.get_renderer().print(&report);
+        
+        Ok(())
+    }
+}
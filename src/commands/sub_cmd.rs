@@ -1,25 +1,35 @@
-use std::io;
-use std::str::FromStr;
-
 use crate::models::Db;
 use crate::commons::Opts;
+use crate::charts::{Chart, SvgPlotter};
+use crate::renderer::OutputFormat;
+
+/// Error returned by a command once it has been logged. Holds the exit code used when running
+/// as a one shot command.
+#[derive(Debug)]
+pub struct CmdError(pub i32);
+
+pub type CmdResult = Result<(), CmdError>;
 
 pub trait SubCmd {
-    fn execute(&self, db: &Db, _opts: &Opts);
+    fn execute(&self, db: &Db, _opts: &Opts) -> CmdResult;
 }
 
-pub fn ask_parameter<T: FromStr>(msg: &str) -> T {
-    let mut buffer = String::new();
-    println!("{}: ", msg);
-    io::stdin().read_line(&mut buffer).unwrap_or_else(|e| {
-        log::error!("Error reading input: {}", e);
-        std::process::exit(0);
-    });
-    
-    let value = buffer.trim().parse::<T>().unwrap_or_else(|_| {
-        log::error!("Error parsing input.");
-        std::process::exit(0);
-    });
-    
-    value
+// Terminal charts are plain text after the report, which would break json, csv or markdown output.
+pub fn check_plot(plot: bool, opts: &Opts) -> CmdResult {
+    if plot && !matches!(opts.get_output(), OutputFormat::Table) {
+        log::error!("--plot only works with table output, use --plot-out to write the charts to a file");
+        return Err(CmdError(1));
+    }
+
+    Ok(())
+}
+
+pub fn write_svg(filename: &str, charts: &[Chart]) -> CmdResult {
+    SvgPlotter::write(filename, charts).map_err(|e| {
+        log::error!("Error writing charts to [{}]: {}", filename, e);
+        CmdError(1)
+    })?;
+
+    log::info!("Charts written to {}", filename);
+    Ok(())
 }
@@ -0,0 +1,89 @@
+use clap::Parser;
+
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError};
+use crate::models::Db;
+use crate::commons::Opts;
+use crate::renderer::Report;
+use crate::tax::{IrpfModel, TaxBrackets};
+use crate::view_models::tax_summary_vm::TaxSummaryVm;
+
+#[derive(Parser, Debug)]
+pub struct TaxSummary {
+    #[clap(short, long)]
+    year: u32,
+    // Tags whose transactions reduce the taxable base, e.g. pension plan contributions. Can be repeated
+    #[clap(long)]
+    deductible: Vec<String>,
+    #[clap(flatten)]
+    irpf: IrpfArgs,
+}
+
+// IRPF model settings shared by the commands that estimate taxes.
+#[derive(Parser, Debug)]
+pub struct IrpfArgs {
+    // Scales as limit:rate pairs, e.g. "12450:9.5,20200:12,inf:24.5". Defaults to the 2021 state scale
+    #[clap(long)]
+    state_brackets: Option<TaxBrackets>,
+    #[clap(long)]
+    region_brackets: Option<TaxBrackets>,
+    #[clap(long)]
+    personal_minimum: Option<f32>,
+    #[clap(long, default_value="0")]
+    descendants: u32,
+    // How many of the descendants are under three years old
+    #[clap(long, default_value="0")]
+    under_three: u32,
+    // Extra minimum for ascendants or disability, added to the descendants one
+    #[clap(long, default_value="0")]
+    family_minimum: f32,
+}
+
+impl IrpfArgs {
+    pub fn model(&self) -> IrpfModel {
+        let mut model = IrpfModel::default();
+        if let Some(state) = &self.state_brackets {
+            model.state = state.clone();
+        }
+        if let Some(region) = &self.region_brackets {
+            model.region = region.clone();
+        }
+        if let Some(minimum) = self.personal_minimum {
+            model.personal_minimum = minimum;
+        }
+        
+        model.family_minimum = IrpfModel::descendants_minimum(self.descendants, self.under_three) + self.family_minimum;
+        model
+    }
+}
+
+impl SubCmd for TaxSummary {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        let payrolls = db.get_payroll_data(Some(self.year), None).map_err(|e| {
+            log::error!("Error getting payrolls: {}", e);
+            CmdError(1)
+        })?;
+        
+        let transactions = db.get_transaction_data(Some(self.year), None).map_err(|e| {
+            log::error!("Error getting transactions: {}", e);
+            CmdError(1)
+        })?;
+        
+        let mut deductions = Vec::new();
+        for tag in &self.deductible {
+            let tag_id = db.get_tag_id(tag).map_err(|e| {
+                log::error!("Error getting deductible tag [{}]: {}", tag, e);
+                CmdError(1)
+            })?;
+            
+            let amount = transactions.iter().filter(|t| t.tag_id == tag_id).map(|t| t.amount).sum();
+            deductions.push((tag.clone(), amount));
+        }
+        
+        let vm = TaxSummaryVm::generate(self.year, &payrolls, deductions, &self.irpf.model());
+        let mut report = Report::new();
+        vm.render(db, &mut report);
+        opts.get_renderer().print(&report);
+        
+        Ok(())
+    }
+}
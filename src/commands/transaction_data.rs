@@ -1,10 +1,11 @@
 use clap::Parser;
 use log;
 
-use crate::commands::sub_cmd::SubCmd;
+use crate::commands::sub_cmd::{SubCmd, CmdResult, CmdError, check_plot, write_svg};
 use crate::models::Db;
 use crate::commons::Opts;
 use crate::view_models::transaction_data_vm::TransactionDataVm;
+use crate::renderer::Report;
 
 #[derive(Parser, Debug)]
 pub struct TransactionData {
@@ -16,24 +17,37 @@ pub struct TransactionData {
     list: bool,
     #[clap(short, long)]
     plot: bool,
+    #[clap(long)]
+    plot_out: Option<String>,
 }
 
 impl SubCmd for TransactionData {
-    fn execute(&self, db: &Db, _opts: &Opts) {
-        let transactions = db.get_transaction_data(self.year, self.month).unwrap_or_else(|e| {
+    fn execute(&self, db: &Db, opts: &Opts) -> CmdResult {
+        check_plot(self.plot, opts)?;
+        
+        let transactions = db.get_transaction_data(self.year, self.month).map_err(|e| {
             log::error!("Error getting transactions: {}", e);
-            std::process::exit(0);
-        });
+            CmdError(1)
+        })?;
 
         let vm = TransactionDataVm::generate(&transactions);
-        vm.render(db);
+        let mut report = Report::new();
+        vm.render(db, &mut report);
         
         if self.list {
-            vm.full_list(db);
+            vm.full_list(db, &mut report);
         }
         
+        opts.get_renderer().print(&report);
+        
         if self.plot {
-            vm.plot(db);
+            vm.plot(db, &opts.get_plotter());
         }
+        
+        if let Some(filename) = &self.plot_out {
+            write_svg(filename, &vm.charts(db))?;
+        }
+        
+        Ok(())
     }
 }
@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+pub const CONFIG_ENV: &str = "GG_CONFIG";
+
+// Settings read from the TOML config file. Everything is optional, CLI flags win over them.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub db_name: Option<String>,
+    pub log: Option<String>,
+    pub output: Option<String>,
+    pub no_color: Option<bool>,
+    pub currency: Option<String>,
+    pub default_account: Option<String>,
+    // chrono format used to show dates, e.g. "%d/%m/%Y".
+    pub date_format: Option<String>,
+    pub decimal_separator: Option<char>,
+    pub thousands_separator: Option<char>,
+    // File holding the passphrase of an encrypted database. $GG_DB_KEY wins over it.
+    pub key_file: Option<String>,
+    pub report: ReportConfig,
+    // Named csv layouts for parse-transaction --profile.
+    pub import: BTreeMap<String, ImportProfile>,
+    pub server: ServerConfig,
+    pub backup: BackupConfig,
+    pub payroll: PayrollConfig,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    // Address for serve when --bind is not given.
+    pub bind: Option<String>,
+    // Clients send it as "Authorization: Bearer <token>".
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackupConfig {
+    // Where backup writes the copies. Defaults to $XDG_DATA_HOME/gitgud-greedy/backups.
+    pub dir: Option<String>,
+    // How many of the latest days, weeks and months keep their newest copy.
+    pub daily: Option<usize>,
+    pub weekly: Option<usize>,
+    pub monthly: Option<usize>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PayrollConfig {
+    // Allowed difference between a payroll's totals when validating it, from the cli or the api.
+    pub tolerance: Option<f32>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ReportConfig {
+    // Biggest movers listed by compare.
+    pub top: Option<usize>,
+    // month or quarter, for pivot.
+    pub pivot_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImportProfile {
+    pub delimiter: char,
+    pub date_format: String,
+    // Amounts like 1.234,56
+    pub decimal_comma: bool,
+    // Banks usually export expenses as negative amounts.
+    pub negate: bool,
+    // Header of each column in the csv.
+    pub name: String,
+    pub date: String,
+    pub amount: String,
+    pub tag: String,
+    // Optional, usually the long description the bank gives each movement. Kept as the notes.
+    pub notes: String,
+    // Used when the tag column is missing or empty.
+    pub default_tag: Option<String>,
+}
+
+impl Default for ImportProfile {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            date_format: String::from("%Y-%m-%d"),
+            decimal_comma: false,
+            negate: false,
+            name: String::from("name"),
+            date: String::from("date"),
+            amount: String::from("amount"),
+            tag: String::from("tag"),
+            notes: String::from("notes"),
+            default_tag: None,
+        }
+    }
+}
+
+impl Config {
+    // The file comes from --config, then GG_CONFIG, then $XDG_CONFIG_HOME/gitgud-greedy/config.toml.
+    // Only the last one may be missing.
+    pub fn load(flag: Option<&str>) -> Result<(Config, Option<PathBuf>), String> {
+        let env = std::env::var(CONFIG_ENV).ok();
+        
+        match Config::path(flag, env, Config::xdg_path()) {
+            Some(path) => Ok((Config::read(&path)?, Some(path))),
+            None => Ok((Config::default(), None)),
+        }
+    }
+    
+    fn path(flag: Option<&str>, env: Option<String>, xdg: Option<PathBuf>) -> Option<PathBuf> {
+        match flag.map(String::from).or_else(|| env.filter(|v| !v.is_empty())) {
+            Some(p) => Some(PathBuf::from(p)),
+            None => xdg.filter(|p| p.exists()),
+        }
+    }
+    
+    fn read(path: &Path) -> Result<Config, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Error reading config file [{}]: {}", path.display(), e))?;
+        toml::from_str(&content)
+            .map_err(|e| format!("Error parsing config file [{}]: {}", path.display(), e))
+    }
+    
+    fn xdg_path() -> Option<PathBuf> {
+        let base = std::env::var("XDG_CONFIG_HOME").ok().filter(|v| !v.is_empty()).map(PathBuf::from)
+            .or_else(|| std::env::var("HOME").ok().map(|h| PathBuf::from(h).join(".config")))?;
+        
+        Some(base.join("gitgud-greedy").join("config.toml"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_beats_env_which_beats_xdg() {
+        let dir = std::env::temp_dir().join(format!("gitgud-greedy-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let xdg = dir.join("config.toml");
+        std::fs::write(&xdg, "").unwrap();
+        let env = || Some(String::from("env.toml"));
+
+        assert_eq!(Config::path(Some("flag.toml"), env(), Some(xdg.clone())), Some(PathBuf::from("flag.toml")));
+        assert_eq!(Config::path(None, env(), Some(xdg.clone())), Some(PathBuf::from("env.toml")));
+        // An empty variable counts as unset.
+        assert_eq!(Config::path(None, Some(String::new()), Some(xdg.clone())), Some(xdg.clone()));
+        // Only the xdg file is optional.
+        assert_eq!(Config::path(None, None, Some(dir.join("missing.toml"))), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let config: Config = toml::from_str("db_name = \"money.db\"\n[payroll]\ntolerance = 0.5\n").unwrap();
+        assert_eq!(config.db_name.as_deref(), Some("money.db"));
+        assert_eq!(config.payroll.tolerance, Some(0.5));
+
+        let error = toml::from_str::<Config>("db_nme = \"money.db\"\n").unwrap_err().to_string();
+        assert!(error.contains("unknown field `db_nme`"), "{}", error);
+        assert!(toml::from_str::<Config>("[payroll]\ntolerence = 0.5\n").is_err());
+    }
+}
@@ -1,34 +1,284 @@
+pub use period::Period;
+pub use config::{Config, ImportProfile};
+
+use std::path::PathBuf;
+
 use clap::Parser;
 
-use crate::commands::SubCommand;
+use crate::commands::{SubCommand, CmdError};
+use crate::renderer::{OutputFormat, Renderer, Formats};
+use crate::charts::{Charset, TerminalPlotter};
+
+mod period;
+mod config;
+
+const DEFAULT_DB_NAME: &str = "gg_financials.db";
+const DEFAULT_LOG: &str = "Debug";
+const DEFAULT_CURRENCY: &str = "EUR";
+const DEFAULT_ACCOUNT: &str = "Assets:Bank";
+pub const DEFAULT_BIND: &str = "127.0.0.1:8080";
+pub const DB_KEY_ENV: &str = "GG_DB_KEY";
+const DEFAULT_DAILY_BACKUPS: usize = 7;
+const DEFAULT_WEEKLY_BACKUPS: usize = 4;
+const DEFAULT_MONTHLY_BACKUPS: usize = 12;
+pub const DEFAULT_PAYROLL_TOLERANCE: f32 = 0.01;
 
 #[derive(Parser, Debug)]
 #[clap(version="1.0", author="Josef212")]
 pub struct Opts {
-    #[clap(short, long, default_value="default.conf")]
-    config: String,
-    #[clap(short, long, default_value="gg_financials.db")]
-    db_name: String,
-    #[clap(short, long, default_value="Debug")]
-    log: String,
+    // TOML config file. Defaults to $GG_CONFIG or $XDG_CONFIG_HOME/gitgud-greedy/config.toml
+    #[clap(short, long)]
+    config: Option<String>,
+    #[clap(short, long)]
+    db_name: Option<String>,
+    #[clap(short, long)]
+    log: Option<String>,
+    #[clap(short, long)]
+    output: Option<OutputFormat>,
+    #[clap(long)]
+    no_color: bool,
+    // File with the passphrase of an encrypted database
+    #[clap(long)]
+    key_file: Option<String>,
     #[clap(subcommand)]
     sub_cmd: Option<SubCommand>,
+    #[clap(skip)]
+    settings: Config,
+    #[clap(skip)]
+    config_path: Option<PathBuf>,
 }
 
 impl Opts {
     pub fn new() -> Opts {
-        Opts::parse()
+        let mut opts = Opts::parse();
+        
+        // The logger is configured from these settings, so errors can only go to stderr here.
+        let (settings, path) = Config::load(opts.config.as_deref()).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        
+        if let Some(Err(e)) = settings.output.as_ref().map(|o| o.parse::<OutputFormat>()) {
+            eprintln!("Invalid output in config file: {}", e);
+            std::process::exit(1);
+        }
+        
+        opts.settings = settings;
+        opts.config_path = path;
+        opts
+    }
+    
+    pub fn get_config(&self) -> &Config {
+        &self.settings
+    }
+    
+    pub fn get_config_path(&self) -> Option<&PathBuf> {
+        self.config_path.as_ref()
+    }
+    
+    pub fn get_db_name(&self) -> String {
+        pick(self.db_name.clone(), self.settings.db_name.clone(), String::from(DEFAULT_DB_NAME)).0
+    }
+    
+    pub fn get_log(&self) -> String {
+        pick(self.log.clone(), self.settings.log.clone(), String::from(DEFAULT_LOG)).0
+    }
+
+    pub fn get_output(&self) -> OutputFormat {
+        let file = self.settings.output.as_ref().and_then(|o| o.parse().ok());
+        pick(self.output, file, OutputFormat::Table).0
+    }
+
+    pub fn get_no_color(&self) -> bool {
+        self.no_color || self.settings.no_color.unwrap_or(false)
     }
     
-    pub fn get_db_name(&self) -> &String {
-        &self.db_name
+    pub fn get_currency(&self) -> String {
+        self.settings.currency.clone().unwrap_or_else(|| String::from(DEFAULT_CURRENCY))
     }
     
-    pub fn get_log(&self) -> &String {
-        &self.log
+    pub fn get_default_account(&self) -> String {
+        self.settings.default_account.clone().unwrap_or_else(|| String::from(DEFAULT_ACCOUNT))
     }
 
+    pub fn get_formats(&self) -> Formats {
+        Formats {
+            decimal_separator: self.settings.decimal_separator,
+            thousands_separator: self.settings.thousands_separator,
+            date_format: self.settings.date_format.clone(),
+        }
+    }
+
+    pub fn get_renderer(&self) -> Renderer {
+        Renderer::new(self.get_output(), self.get_no_color()).with_formats(self.get_formats())
+    }
+
+    pub fn get_plotter(&self) -> TerminalPlotter {
+        TerminalPlotter::new(self.get_renderer().width(), Charset::detect())
+    }
+
+    pub fn get_backup_dir(&self) -> Option<PathBuf> {
+        self.settings.backup.dir.as_ref().map(PathBuf::from).or_else(|| data_dir().map(|d| d.join("backups")))
+    }
+    
+    // Copies kept per day, week and month when rotating backups.
+    pub fn get_backup_retention(&self) -> (usize, usize, usize) {
+        let b = &self.settings.backup;
+        (
+            b.daily.unwrap_or(DEFAULT_DAILY_BACKUPS),
+            b.weekly.unwrap_or(DEFAULT_WEEKLY_BACKUPS),
+            b.monthly.unwrap_or(DEFAULT_MONTHLY_BACKUPS),
+        )
+    }
+    
+    // Allowed difference between the totals of a payroll, for the commands without --tolerance.
+    pub fn get_payroll_tolerance(&self) -> f32 {
+        self.settings.payroll.tolerance.unwrap_or(DEFAULT_PAYROLL_TOLERANCE)
+    }
+    
+    // Passphrase from $GG_DB_KEY or the key file, None when neither is set.
+    pub fn get_db_key(&self) -> Result<Option<String>, String> {
+        if let Some(key) = std::env::var(DB_KEY_ENV).ok().filter(|k| !k.is_empty()) {
+            return Ok(Some(key));
+        }
+        
+        match self.key_file.as_ref().or(self.settings.key_file.as_ref()) {
+            Some(path) => read_key_file(path).map(Some),
+            None => Ok(None),
+        }
+    }
+    
     pub fn get_sub_cmd(&self) -> &Option<SubCommand> {
         &self.sub_cmd
     }
-}
\ No newline at end of file
+    
+    // Effective value and origin (cli, config or default) of every setting, for config show.
+    pub fn get_settings(&self) -> Vec<(&'static str, String, &'static str)> {
+        let s = &self.settings;
+        let text = |v: &Option<String>| v.clone();
+        let output_file = s.output.as_ref().and_then(|o| o.parse::<OutputFormat>().ok());
+        let no_color = if self.no_color { Some(true) } else { None };
+        
+        let mut ret = vec![
+            with_name("db_name", pick(self.db_name.clone(), text(&s.db_name), String::from(DEFAULT_DB_NAME))),
+            with_name("log", pick(self.log.clone(), text(&s.log), String::from(DEFAULT_LOG))),
+            with_name("output", pick(self.output, output_file, OutputFormat::Table)),
+            with_name("no_color", pick(no_color, s.no_color, false)),
+            with_name("currency", pick(None, text(&s.currency), String::from(DEFAULT_CURRENCY))),
+            with_name("default_account", pick(None, text(&s.default_account), String::from(DEFAULT_ACCOUNT))),
+            with_name("date_format", pick(None, text(&s.date_format), String::from("%Y-%m-%d"))),
+            with_name("decimal_separator", pick(None, s.decimal_separator, '.')),
+            with_name("thousands_separator", pick(None, s.thousands_separator.map(String::from), String::new())),
+            with_name("payroll.tolerance", pick(None, s.payroll.tolerance, DEFAULT_PAYROLL_TOLERANCE)),
+            with_name("report.top", pick(None, s.report.top, 5)),
+            with_name("report.pivot_by", pick(None, text(&s.report.pivot_by), String::from("month"))),
+            with_name("key_file", pick(self.key_file.clone(), text(&s.key_file), String::from("none"))),
+            with_name("backup.dir", pick(None, text(&s.backup.dir), self.get_backup_dir().map(|d| d.display().to_string()).unwrap_or_default())),
+            with_name("backup.daily", pick(None, s.backup.daily, DEFAULT_DAILY_BACKUPS)),
+            with_name("backup.weekly", pick(None, s.backup.weekly, DEFAULT_WEEKLY_BACKUPS)),
+            with_name("backup.monthly", pick(None, s.backup.monthly, DEFAULT_MONTHLY_BACKUPS)),
+            with_name("server.bind", pick(None, text(&s.server.bind), String::from(DEFAULT_BIND))),
+            with_name("server.token", pick(None, s.server.token.as_ref().map(|_| "***"), "none")),
+        ];
+        
+        for name in s.import.keys() {
+            ret.push(("import", name.clone(), "config"));
+        }
+        
+        ret
+    }
+}
+
+// $XDG_DATA_HOME/gitgud-greedy, for files the tool keeps besides the db.
+pub fn data_dir() -> Option<PathBuf> {
+    let base = std::env::var("XDG_DATA_HOME").ok().filter(|v| !v.is_empty()).map(PathBuf::from)
+        .or_else(|| std::env::var("HOME").ok().map(|h| PathBuf::from(h).join(".local").join("share")))?;
+    Some(base.join("gitgud-greedy"))
+}
+
+// Optional YYYY-MM-DD argument, also taking YYYY/MM/DD, normalized for comparing with the dates in the db.
+pub fn parse_day(day: &Option<String>) -> Result<Option<String>, CmdError> {
+    match day {
+        None => Ok(None),
+        Some(d) => chrono::NaiveDate::parse_from_str(&d.replace('/', "-"), "%Y-%m-%d")
+            .map(|d| Some(d.format("%Y-%m-%d").to_string()))
+            .map_err(|e| {
+                log::error!("Invalid date [{}], expected YYYY-MM-DD. E: {}", d, e);
+                CmdError(1)
+            }),
+    }
+}
+
+// Characters that can not go as is in html or svg text and attributes.
+pub const XML_ESCAPES: [(char, &str); 4] = [('&', "&amp;"), ('<', "&lt;"), ('>', "&gt;"), ('"', "&quot;")];
+// Inside the double quoted strings of a beancount journal.
+pub const QUOTE_ESCAPES: [(char, &str); 2] = [('\\', "\\\\"), ('"', "\\\"")];
+
+pub fn escape(value: &str, escapes: &[(char, &str)]) -> String {
+    let mut ret = String::with_capacity(value.len());
+    for c in value.chars() {
+        match escapes.iter().find(|(e, _)| *e == c) {
+            Some((_, replacement)) => ret.push_str(replacement),
+            None => ret.push(c),
+        }
+    }
+    ret
+}
+
+// The whole first line is the passphrase, trailing spaces included.
+pub fn read_key_file(path: &str) -> Result<String, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("Error reading key file [{}]: {}", path, e))?;
+    let key = content.lines().next().unwrap_or("");
+    
+    match key.is_empty() {
+        true => Err(format!("Key file [{}] is empty", path)),
+        false => Ok(String::from(key)),
+    }
+}
+
+fn pick<T>(cli: Option<T>, file: Option<T>, default: T) -> (T, &'static str) {
+    match (cli, file) {
+        (Some(v), _) => (v, "cli"),
+        (None, Some(v)) => (v, "config"),
+        (None, None) => (default, "default"),
+    }
+}
+
+fn with_name<T: ToString>(name: &'static str, (value, source): (T, &'static str)) -> (&'static str, String, &'static str) {
+    (name, value.to_string(), source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts(args: &[&str], config: &str) -> Opts {
+        let mut opts = Opts::parse_from(std::iter::once("gitgud-greedy").chain(args.iter().copied()));
+        opts.settings = toml::from_str(config).unwrap();
+        opts
+    }
+
+    fn setting(opts: &Opts, name: &str) -> (String, &'static str) {
+        opts.get_settings().into_iter().find(|(n, _, _)| *n == name).map(|(_, v, s)| (v, s)).unwrap()
+    }
+
+    #[test]
+    fn cli_beats_config_which_beats_defaults() {
+        let config = "db_name = \"config.db\"\noutput = \"csv\"\ncurrency = \"USD\"\n";
+
+        let cli = opts(&["-d", "cli.db", "-o", "json"], config);
+        assert_eq!(cli.get_db_name(), "cli.db");
+        assert_eq!(cli.get_output(), OutputFormat::Json);
+        assert_eq!(setting(&cli, "db_name"), (String::from("cli.db"), "cli"));
+
+        let file = opts(&[], config);
+        assert_eq!(file.get_db_name(), "config.db");
+        assert_eq!(file.get_output(), OutputFormat::Csv);
+        assert_eq!(setting(&file, "currency"), (String::from("USD"), "config"));
+
+        let default = opts(&[], "");
+        assert_eq!(default.get_db_name(), DEFAULT_DB_NAME);
+        assert_eq!(default.get_output(), OutputFormat::Table);
+        assert_eq!(setting(&default, "log"), (String::from(DEFAULT_LOG), "default"));
+    }
+}
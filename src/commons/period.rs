@@ -0,0 +1,67 @@
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+// A whole year (YYYY) or a single month (YYYY-MM), matching the year/month filters the db understands.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Period {
+    pub year: u32,
+    pub month: Option<u32>,
+}
+
+impl Period {
+    pub fn year(&self) -> Option<u32> {
+        Some(self.year)
+    }
+
+    pub fn month(&self) -> Option<u32> {
+        self.month
+    }
+
+    // The period of the same length right before this one.
+    pub fn previous(&self) -> Period {
+        match self.month {
+            Some(1) => Period { year: self.year - 1, month: Some(12) },
+            Some(m) => Period { year: self.year, month: Some(m - 1) },
+            None => Period { year: self.year - 1, month: None },
+        }
+    }
+
+    // The period of the same length right after this one.
+    pub fn next(&self) -> Period {
+        match self.month {
+            Some(12) => Period { year: self.year + 1, month: Some(1) },
+            Some(m) => Period { year: self.year, month: Some(m + 1) },
+            None => Period { year: self.year + 1, month: None },
+        }
+    }
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid period [{}]. Expected YYYY or YYYY-MM", s);
+        let mut parts = s.trim().split(['-', '/']);
+
+        let year = parts.next().and_then(|y| y.parse::<u32>().ok()).ok_or_else(error)?;
+        let month = match parts.next() {
+            Some(m) => Some(m.parse::<u32>().ok().filter(|m| (1..=12).contains(m)).ok_or_else(error)?),
+            None => None,
+        };
+
+        if parts.next().is_some() || !(1000..=9999).contains(&year) {
+            return Err(error());
+        }
+
+        Ok(Period { year, month })
+    }
+}
+
+impl std::fmt::Display for Period {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.month {
+            Some(m) => write!(f, "{:04}-{:02}", self.year, m),
+            None => write!(f, "{:04}", self.year),
+        }
+    }
+}
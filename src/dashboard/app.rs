@@ -0,0 +1,367 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui::widgets::TableState;
+
+use crate::commons::Period;
+use crate::models::Db;
+use crate::models::transaction::Transaction;
+use crate::renderer::{Content, Formats, Report, Section, Value};
+use crate::view_models::accounts_vm::AccountsVm;
+use crate::view_models::balance_summary_vm::BalanceSummaryVm;
+use crate::view_models::transaction_data_vm::TransactionDataVm;
+
+const PAGE: usize = 10;
+
+pub enum Mode {
+    Browse,
+    Filter,
+    EditTag(String),
+    EditAmount(String),
+}
+
+// Dashboard state. Every pane is a report built by the same view models the cli commands use.
+pub struct App<'a> {
+    db: &'a Db,
+    pub formats: Formats,
+    pub color: bool,
+    pub period: Period,
+    pub accounts: Report,
+    pub summary: Report,
+    pub list: Report,
+    transactions: Vec<Transaction>,
+    pub visible: Vec<usize>,
+    pub state: TableState,
+    pub filter: String,
+    pub mode: Mode,
+    pub status: String,
+    pub quit: bool,
+}
+
+impl<'a> App<'a> {
+    pub fn new(db: &'a Db, period: Period, formats: Formats, color: bool) -> Self {
+        let mut app = Self {
+            db,
+            formats,
+            color,
+            period,
+            accounts: Report::new(),
+            summary: Report::new(),
+            list: Report::new(),
+            transactions: Vec::new(),
+            visible: Vec::new(),
+            state: TableState::default(),
+            filter: String::new(),
+            mode: Mode::Browse,
+            status: String::new(),
+            quit: false,
+        };
+        app.load();
+        app
+    }
+
+    pub fn load(&mut self) {
+        let selected = self.selected().map(|t| t._id);
+        let year = self.period.year();
+        let month = self.period.month();
+
+        let accounts = self.db.get_all_accounts().unwrap_or_else(|e| {
+            self.status = format!("Error getting accounts: {}", e);
+            Vec::new()
+        });
+        let payrolls = self.db.get_payroll_data(year, month).unwrap_or_else(|e| {
+            self.status = format!("Error getting payrolls: {}", e);
+            Vec::new()
+        });
+        self.transactions = self.db.get_transaction_data(year, month).unwrap_or_else(|e| {
+            self.status = format!("Error getting transactions: {}", e);
+            Vec::new()
+        });
+
+        self.accounts = Report::new();
+        AccountsVm::generate(&accounts).render(&mut self.accounts);
+
+        self.summary = Report::new();
+        BalanceSummaryVm::generate(&payrolls, &self.transactions).render(self.db, &mut self.summary);
+
+        self.list = Report::new();
+        TransactionDataVm::generate(&self.transactions).full_list(self.db, &mut self.list);
+
+        self.apply_filter();
+        if let Some(id) = selected {
+            if let Some(row) = self.visible.iter().position(|i| self.transactions[*i]._id == id) {
+                self.state.select(Some(row));
+            }
+        }
+    }
+
+    pub fn section<'r>(report: &'r Report, key: &str) -> Option<&'r Section> {
+        report.sections().iter().find(|s| s.key == key)
+    }
+
+    pub fn rows(&self) -> &[Vec<Value>] {
+        match Self::section(&self.list, "transactions").map(|s| &s.content) {
+            Some(Content::Table { rows, .. }) => rows,
+            _ => &[],
+        }
+    }
+
+    pub fn selected(&self) -> Option<&Transaction> {
+        self.state.selected().and_then(|r| self.visible.get(r)).map(|i| &self.transactions[*i])
+    }
+
+    fn apply_filter(&mut self) {
+        let filter = self.filter.to_lowercase();
+        let previous = self.state.selected().and_then(|r| self.visible.get(r)).copied();
+        self.visible = self.rows().iter().enumerate()
+            .filter(|(_, row)| filter.is_empty() || row.iter().any(|v| self.formats.apply(v).to_lowercase().contains(&filter)))
+            .map(|(i, _)| i)
+            .collect();
+
+        // Keep the same transaction selected while it is still visible.
+        let selected = match self.state.selected() {
+            _ if self.visible.is_empty() => None,
+            _ if previous.is_some() && self.visible.contains(&previous.unwrap()) => self.visible.iter().position(|i| Some(*i) == previous),
+            Some(r) => Some(r.min(self.visible.len() - 1)),
+            None => Some(0),
+        };
+        self.state.select(selected);
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+
+        let last = self.visible.len() as isize - 1;
+        let current = self.state.selected().unwrap_or(0) as isize;
+        self.state.select(Some((current + delta).max(0).min(last) as usize));
+    }
+
+    fn change_period(&mut self, period: Period) {
+        self.period = period;
+        self.state.select(None);
+        self.status.clear();
+        self.load();
+    }
+
+    pub fn on_key(&mut self, key: KeyEvent) {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.quit = true;
+            return;
+        }
+
+        match &mut self.mode {
+            Mode::Browse => self.on_browse_key(key.code),
+            Mode::Filter => match key.code {
+                KeyCode::Enter => self.mode = Mode::Browse,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.mode = Mode::Browse;
+                    self.apply_filter();
+                },
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.apply_filter();
+                },
+                KeyCode::Char(c) => {
+                    self.filter.push(c);
+                    self.apply_filter();
+                },
+                _ => (),
+            },
+            Mode::EditTag(buffer) | Mode::EditAmount(buffer) => match key.code {
+                KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Backspace => { buffer.pop(); },
+                KeyCode::Char(c) => buffer.push(c),
+                KeyCode::Enter => {
+                    let mode = std::mem::replace(&mut self.mode, Mode::Browse);
+                    self.save(mode);
+                },
+                _ => (),
+            },
+        }
+    }
+
+    fn on_browse_key(&mut self, code: KeyCode) {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::PageDown => self.move_selection(PAGE as isize),
+            KeyCode::PageUp => self.move_selection(-(PAGE as isize)),
+            KeyCode::Home => self.move_selection(isize::MIN / 2),
+            KeyCode::End => self.move_selection(isize::MAX / 2),
+            KeyCode::Left | KeyCode::Char('h') => self.change_period(self.period.previous()),
+            KeyCode::Right | KeyCode::Char('l') => self.change_period(self.period.next()),
+            KeyCode::Char('r') => {
+                self.status.clear();
+                self.load();
+            },
+            KeyCode::Char('/') => self.mode = Mode::Filter,
+            KeyCode::Char('t') => {
+                if let Some(t) = self.selected() {
+                    let tag = self.db.get_tag_str(t.tag_id).unwrap_or_default();
+                    self.mode = Mode::EditTag(tag);
+                }
+            },
+            KeyCode::Char('a') => {
+                if let Some(t) = self.selected() {
+                    let amount = format!("{:.2}", t.amount);
+                    self.mode = Mode::EditAmount(amount);
+                }
+            },
+            _ => (),
+        }
+    }
+
+    fn save(&mut self, mode: Mode) {
+        // A copy of the whole row, so fields the dashboard does not edit (notes) are kept.
+        let mut transaction = match self.selected() {
+            Some(t) => t.clone(),
+            None => return,
+        };
+
+        match mode {
+            Mode::EditTag(tag) => match self.db.get_tag_id(tag.trim()) {
+                Ok(id) => transaction.tag_id = id,
+                Err(_) => {
+                    self.status = format!("Unknown tag [{}]", tag.trim());
+                    return;
+                },
+            },
+            Mode::EditAmount(amount) => match amount.trim().replace(',', ".").parse::<f32>() {
+                Ok(a) => transaction.amount = a,
+                Err(e) => {
+                    self.status = format!("Invalid amount [{}]: {}", amount.trim(), e);
+                    return;
+                },
+            },
+            _ => return,
+        }
+
+        self.db.start_batch("dashboard");
+        self.status = match self.db.update_transaction(&transaction).map_err(|e| e.to_string())
+            .and_then(|_| self.db.save().map_err(|e| e.to_string())) {
+            Ok(_) => format!("Transaction {} updated", transaction._id),
+            Err(e) => format!("Error updating transaction {}: {}", transaction._id, e),
+        };
+        self.load();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db() -> Db {
+        let db = Db::load(":memory:").unwrap();
+        db.insert_tag("Food", "").unwrap();
+        db.insert_tag("Rent", "").unwrap();
+        for (name, date, amount, tag) in [("Market", "2022-03-01", 40.0, 1), ("Flat", "2022-03-02", 700.0, 2), ("Bakery", "2022-03-03", 10.0, 1)] {
+            let mut t = Transaction::new(&String::from(name), &String::from(date), amount, tag);
+            t.notes = Some(format!("{} notes", name));
+            db.insert_transaction(&t).unwrap();
+        }
+        db
+    }
+
+    fn app(db: &Db) -> App<'_> {
+        App::new(db, Period { year: 2022, month: Some(3) }, Formats::default(), false)
+    }
+
+    fn press(app: &mut App, keys: &str) {
+        for c in keys.chars() {
+            app.on_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
+    fn key(app: &mut App, code: KeyCode) {
+        app.on_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn selected_name(app: &App) -> Option<String> {
+        app.selected().map(|t| t.name.clone())
+    }
+
+    #[test]
+    fn filter_matches_any_column_ignoring_case() {
+        let db = db();
+        let mut app = app(&db);
+        assert_eq!(app.visible, vec![0, 1, 2]);
+
+        press(&mut app, "/food");
+        assert_eq!(app.visible, vec![0, 2]);
+        press(&mut app, "x");
+        assert!(app.visible.is_empty());
+        assert_eq!(app.selected().map(|t| t._id), None);
+
+        key(&mut app, KeyCode::Esc);
+        assert!(app.filter.is_empty());
+        assert_eq!(app.visible, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn selection_follows_the_transaction_through_filters() {
+        let db = db();
+        let mut app = app(&db);
+        press(&mut app, "jj");
+        assert_eq!(selected_name(&app).as_deref(), Some("Bakery"));
+
+        // Still visible, now on the second row.
+        press(&mut app, "/food");
+        assert_eq!(selected_name(&app).as_deref(), Some("Bakery"));
+        assert_eq!(app.state.selected(), Some(1));
+
+        // Gone, the selection stays within the rows left.
+        key(&mut app, KeyCode::Esc);
+        press(&mut app, "/flat");
+        assert_eq!(selected_name(&app).as_deref(), Some("Flat"));
+
+        key(&mut app, KeyCode::Enter);
+        press(&mut app, "r");
+        assert_eq!(selected_name(&app).as_deref(), Some("Flat"));
+    }
+
+    #[test]
+    fn save_updates_the_selected_transaction_and_keeps_its_notes() {
+        let db = db();
+        let mut app = app(&db);
+        press(&mut app, "j");
+
+        press(&mut app, "a");
+        for _ in 0..6 {
+            key(&mut app, KeyCode::Backspace);
+        }
+        press(&mut app, "650,5");
+        key(&mut app, KeyCode::Enter);
+        assert_eq!(app.status, "Transaction 2 updated");
+
+        press(&mut app, "t");
+        for _ in 0..4 {
+            key(&mut app, KeyCode::Backspace);
+        }
+        press(&mut app, "Food");
+        key(&mut app, KeyCode::Enter);
+
+        let stored = db.get_transaction(2).unwrap();
+        assert_eq!((stored.amount, stored.tag_id), (650.5, 1));
+        assert_eq!(stored.notes.as_deref(), Some("Flat notes"));
+        assert_eq!(selected_name(&app).as_deref(), Some("Flat"));
+    }
+
+    #[test]
+    fn invalid_edits_are_reported_and_not_saved() {
+        let db = db();
+        let mut app = app(&db);
+
+        press(&mut app, "tx");
+        key(&mut app, KeyCode::Enter);
+        assert_eq!(app.status, "Unknown tag [Foodx]");
+
+        press(&mut app, "ax");
+        key(&mut app, KeyCode::Enter);
+        assert!(app.status.starts_with("Invalid amount [40.00x]"));
+
+        let stored = db.get_transaction(1).unwrap();
+        assert_eq!((stored.amount, stored.tag_id), (40.0, 1));
+    }
+}
@@ -0,0 +1,82 @@
+pub use app::App;
+
+use std::io;
+use std::sync::Arc;
+
+use crossterm::event::{self, Event};
+use crossterm::cursor::Show;
+use crossterm::execute;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use tui::backend::{Backend, CrosstermBackend};
+use tui::Terminal;
+
+mod app;
+mod ui;
+
+type PanicHook = Box<dyn Fn(&std::panic::PanicHookInfo<'_>) + Send + Sync + 'static>;
+
+// Takes over the terminal until the user quits. The log output would draw over the panes, so it is
+// muted meanwhile and errors are shown in the status line instead.
+pub fn run(app: &mut App) -> io::Result<()> {
+    let _guard = TerminalGuard::new();
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    event_loop(&mut terminal, app)
+}
+
+// Gives the terminal and the log level back however the dashboard ends. The panic hook runs before
+// unwinding, so it restores them too or the panic message would be muted and drawn in raw mode.
+struct TerminalGuard {
+    level: log::LevelFilter,
+    previous: Arc<PanicHook>,
+}
+
+impl TerminalGuard {
+    fn new() -> Self {
+        let level = log::max_level();
+        let previous: Arc<PanicHook> = Arc::new(std::panic::take_hook());
+
+        let hook = previous.clone();
+        std::panic::set_hook(Box::new(move |info| {
+            restore(level);
+            hook(info);
+        }));
+        log::set_max_level(log::LevelFilter::Off);
+
+        Self { level, previous }
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore(self.level);
+
+        // The hook can not be changed while panicking, the one set stays until the process ends.
+        if !std::thread::panicking() {
+            let previous = self.previous.clone();
+            std::panic::set_hook(Box::new(move |info| previous(info)));
+        }
+    }
+}
+
+fn restore(level: log::LevelFilter) {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+    log::set_max_level(level);
+}
+
+fn event_loop<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
+    while !app.quit {
+        terminal.draw(|f| ui::draw(f, app))?;
+
+        if let Event::Key(key) = event::read()? {
+            app.on_key(key);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Cell, Paragraph, Row, Table};
+use tui::Frame;
+
+use crate::dashboard::app::{App, Mode};
+use crate::renderer::{Content, Formats, Report, Section, Value};
+
+const HELP: &str = "q quit | ↑↓ move | ←→ month | / filter | t tag | a amount | r reload";
+
+pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(3)])
+        .split(f.size());
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(rows[0]);
+
+    let accounts_height = report_height(&app.accounts, &["accounts", "total"]);
+    let balance_height = report_height(&app.summary, &["balance"]);
+    let panes = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(accounts_height), Constraint::Length(balance_height), Constraint::Min(3)])
+        .split(columns[0]);
+
+    draw_report(f, panes[0], "Accounts", &app.accounts, &["accounts", "total"], app);
+    draw_report(f, panes[1], &format!("Balance {}", app.period), &app.summary, &["balance"], app);
+    draw_report(f, panes[2], "Per tag spending", &app.summary, &["tags"], app);
+    draw_transactions(f, columns[1], app);
+    draw_footer(f, rows[1], app);
+}
+
+fn report_height(report: &Report, keys: &[&str]) -> u16 {
+    let lines: usize = sections(report, keys).iter().map(|s| section_lines(s)).sum();
+    lines as u16 + 2
+}
+
+fn sections<'r>(report: &'r Report, keys: &[&str]) -> Vec<&'r Section> {
+    keys.iter().filter_map(|k| App::section(report, k)).collect()
+}
+
+fn section_lines(section: &Section) -> usize {
+    match &section.content {
+        Content::Table { rows, .. } => rows.len() + 1,
+        Content::Summary(values) => values.len(),
+    }
+}
+
+fn draw_report<B: Backend>(f: &mut Frame<B>, area: Rect, title: &str, report: &Report, keys: &[&str], app: &App) {
+    let block = Block::default().borders(Borders::ALL).title(title.to_string());
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let sections = sections(report, keys);
+    let constraints: Vec<Constraint> = sections.iter().map(|s| Constraint::Length(section_lines(s) as u16)).collect();
+    let areas = Layout::default().direction(Direction::Vertical).constraints(constraints).split(inner);
+
+    for (section, area) in sections.iter().zip(areas) {
+        let (header, rows) = match &section.content {
+            Content::Table { header, rows } => (Some(header.clone()), rows.clone()),
+            Content::Summary(values) => (None, values.iter().map(|(k, v)| vec![Value::text(k), v.clone()]).collect()),
+        };
+        let (table, widths) = table(header, rows.iter(), &app.formats, app.color);
+        f.render_widget(table.widths(&widths), area);
+    }
+}
+
+fn draw_transactions<B: Backend>(f: &mut Frame<B>, area: Rect, app: &mut App) {
+    let title = match app.filter.is_empty() {
+        true => format!("Transactions {} ({})", app.period, app.visible.len()),
+        false => format!("Transactions {} ({}) filter: {}", app.period, app.visible.len(), app.filter),
+    };
+
+    let header = App::section(&app.list, "transactions").and_then(|s| match &s.content {
+        Content::Table { header, .. } => Some(header.clone()),
+        _ => None,
+    });
+    let rows = app.visible.iter().map(|i| &app.rows()[*i]);
+    let (table, widths) = table(header, rows, &app.formats, app.color);
+
+    let table = table
+        .widths(&widths)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+
+    f.render_stateful_widget(table, area, &mut app.state);
+}
+
+fn draw_footer<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App) {
+    let line = match &app.mode {
+        Mode::Browse if app.status.is_empty() => Spans::from(HELP),
+        Mode::Browse => Spans::from(app.status.as_str()),
+        Mode::Filter => prompt("Filter", &app.filter),
+        Mode::EditTag(buffer) => prompt("Tag", buffer),
+        Mode::EditAmount(buffer) => prompt("Amount", buffer),
+    };
+
+    f.render_widget(Paragraph::new(line).block(Block::default().borders(Borders::ALL)), area);
+}
+
+fn prompt<'a>(label: &'a str, buffer: &'a str) -> Spans<'a> {
+    Spans::from(vec![
+        Span::styled(format!("{}: ", label), Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(buffer),
+        Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+    ])
+}
+
+// Builds a table with fixed column widths so numbers can be right aligned like in the cli tables.
+fn table<'a, 'r>(header: Option<Vec<String>>, rows: impl Iterator<Item = &'r Vec<Value>>, formats: &Formats, color: bool) -> (Table<'a>, Vec<Constraint>) {
+    let rows: Vec<&Vec<Value>> = rows.collect();
+    let cells: Vec<Vec<String>> = rows.iter().map(|r| r.iter().map(|v| formats.apply(v)).collect()).collect();
+
+    let columns = header.as_ref().map(|h| h.len()).or_else(|| cells.first().map(|c| c.len())).unwrap_or(0);
+    let mut widths = vec![0; columns];
+    for row in header.iter().chain(cells.iter()) {
+        for (i, cell) in row.iter().enumerate().take(columns) {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+
+    let body: Vec<Row> = rows.iter().zip(cells).map(|(values, texts)| {
+        Row::new(values.iter().zip(texts).enumerate().map(|(i, (value, text))| {
+            let text = if value.is_numeric() { format!("{:>w$}", text, w = widths[i]) } else { text };
+            let style = if color && value.is_negative() { Style::default().fg(Color::Red) } else { Style::default() };
+            Cell::from(text).style(style)
+        }).collect::<Vec<Cell>>())
+    }).collect();
+
+    let mut table = Table::new(body).column_spacing(2);
+    if let Some(header) = header {
+        table = table.header(Row::new(header).style(Style::default().add_modifier(Modifier::BOLD)));
+    }
+
+    let constraints = widths.iter().map(|w| Constraint::Length(*w as u16)).collect();
+    (table, constraints)
+}
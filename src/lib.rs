@@ -1,3 +1,7 @@
+//! Household finance tracker: transactions, payrolls and accounts in a sqlite database.
+//!
+//! The binary is a command line tool. To use a database from Rust, open it with [`GreedyClient`].
+
 // https://github.com/clap-rs/clap
 // https://docs.rs/clap/latest/clap/
 // https://rust-lang-nursery.github.io/rust-cookbook/database/sqlite.html
@@ -6,16 +10,34 @@ mod commons;
 mod commands;
 mod models;
 mod view_models;
+mod renderer;
+mod charts;
+mod tax;
+mod dashboard;
+mod server;
+mod client;
 
 use std::error::Error;
+use std::io::IsTerminal;
 use std::io::Write;
 use std::str::FromStr;
 use chrono::Local;
 use env_logger::fmt::Color;
+use env_logger::WriteStyle;
 use log::LevelFilter;
 
-use crate::commons::Opts;
-use crate::models::Db;
+use crate::commands::SubCommand;
+use crate::commons::{Opts, DB_KEY_ENV};
+
+pub use crate::client::{GreedyClient, ClientError, ClientResult, GroupTotal, TransactionSummary, PayrollSummary, BalanceSummary};
+pub use crate::models::{Db, LoadError, Name, SCHEMA_VERSION};
+pub use crate::models::account::Account;
+pub use crate::models::attachment::{self, Attachment};
+pub use crate::models::search::{self, SearchFilter, SearchHit};
+pub use crate::models::payroll::Payroll;
+pub use crate::models::payroll_item::{PayrollItem, ItemKind};
+pub use crate::models::transaction::Transaction;
+pub use crate::server::Server;
 
 pub struct Cli {
     pub opts: Opts,
@@ -27,12 +49,23 @@ impl Cli {
         let opts = &self.opts;
         
         log::info!("Database name: {}", opts.get_db_name());
+        if let Some(path) = opts.get_config_path() {
+            log::info!("Config file: {}", path.display());
+        }
         log::info!("LogLevel: {}", log::max_level());
     }
 
     pub fn match_subcommand(&self) {
         match &self.opts.get_sub_cmd() {
-            Some(sub_cmd) => sub_cmd.execute(&self.db, &self.opts),
+            Some(sub_cmd) => {
+                if let Err(e) = sub_cmd.execute(&self.db, &self.opts) {
+                    // Exiting skips dropping the db, which is what writes an encrypted one back.
+                    if let Err(e) = self.db.save() {
+                        log::error!("Error saving db [{}]: {}", self.db.get_name(), e);
+                    }
+                    std::process::exit(e.0);
+                }
+            },
             None => log::error!("No matching subcommand found. Use -h or --help to see the list."),
         }
     }
@@ -40,8 +73,8 @@ impl Cli {
 
 pub fn init() -> Cli {
     let opts: Opts = Opts::new();
-    init_logger(&opts.get_log());
-    let db = load_db(&opts.get_db_name());
+    init_logger(&opts.get_log(), opts.get_no_color());
+    let db = load_db(&opts);
     
     Cli { 
         opts,
@@ -49,7 +82,7 @@ pub fn init() -> Cli {
     }
 }
 
-fn init_logger(log_level: &String) {
+fn init_logger(log_level: &str, no_color: bool) {
     env_logger::Builder::new()
         .format(|buf, record| {
             let level = record.level();
@@ -70,19 +103,52 @@ fn init_logger(log_level: &String) {
                      record.args()
             )
         })
-        .filter_level(LevelFilter::from_str(log_level.as_str()).unwrap_or(LevelFilter::Error))
+        .filter_level(LevelFilter::from_str(log_level).unwrap_or(LevelFilter::Error))
+        .write_style(if no_color { WriteStyle::Never } else { WriteStyle::Auto })
         .init();
 
     std::panic::set_hook(Box::new(|err| {log::error!("{}", err)}));
 }
 
-fn load_db(db_name: &str) -> Db {
-    match Db::load(db_name) {
+fn load_db(opts: &Opts) -> Db {
+    // Printing a completion script needs no data, an empty db stands in for the real one so
+    // nothing is created nor asked for.
+    if matches!(opts.get_sub_cmd(), Some(SubCommand::Completions(_))) {
+        return Db::load(":memory:").unwrap_or_else(|e| {
+            log::error!("Error loading db [:memory:]: {}", e);
+            std::process::exit(1);
+        });
+    }
+    
+    let db_name = opts.get_db_name();
+    // Completion scripts run in the background of the shell, they can not stop to ask nor leave a
+    // new db behind in whatever folder TAB was pressed.
+    let completing = matches!(opts.get_sub_cmd(), Some(SubCommand::CompleteNames(_)));
+    if completing && db_name != ":memory:" && !std::path::Path::new(&db_name).exists() {
+        std::process::exit(0);
+    }
+    let can_ask = std::io::stdin().is_terminal() && !completing;
+    
+    let passphrase = || match opts.get_db_key() {
+        Ok(Some(key)) => Some(key),
+        Ok(None) if can_ask => rpassword::prompt_password(format!("Passphrase for {}: ", db_name)).ok(),
+        Ok(None) => None,
+        Err(e) => {
+            log::error!("{}", e);
+            std::process::exit(1);
+        },
+    };
+    
+    match Db::open(&db_name, passphrase) {
+        Err(LoadError::KeyRequired) => {
+            log::error!("Database [{}] is encrypted. Set ${} or key_file in the config, use --key-file or run from a terminal to type it", db_name, DB_KEY_ENV);
+            std::process::exit(1);
+        },
         Err(e) => {
             log::error!("Error loading db [{}]: {}", db_name, e);
             std::process::exit(1);
         },
-        Ok(db) => return db
+        Ok(db) => db
     }
 }
 
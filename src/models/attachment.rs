@@ -0,0 +1,142 @@
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use rusqlite::Row;
+use sha2::{Digest, Sha256};
+
+use crate::models::{TRANSACTIONS_KEY, PAYROLLS_KEY};
+
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    pub _id: i32,
+    pub entity: String,
+    pub entity_id: i32,
+    // Name of the file when it was attached, given back on extract.
+    pub name: String,
+    // Sha256 of the contents, which is also its name in the store.
+    pub hash: String,
+    pub size: i64,
+    pub added: String,
+}
+
+impl Attachment {
+    pub fn new(entity: &str, entity_id: i32, name: &str, hash: &str, size: i64) -> Self {
+        Self {
+            _id: 0,
+            entity: String::from(entity),
+            entity_id,
+            name: String::from(name),
+            hash: String::from(hash),
+            size,
+            added: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+
+    pub fn from_row(r: &Row) -> Self {
+        Self {
+            _id: r.get_unwrap(0),
+            entity: r.get_unwrap(1),
+            entity_id: r.get_unwrap(2),
+            name: r.get_unwrap(3),
+            hash: r.get_unwrap(4),
+            size: r.get_unwrap(5),
+            added: r.get_unwrap(6),
+        }
+    }
+
+    // How --list shows it, the id being what open and extract take.
+    pub fn label(&self) -> String {
+        format!("#{} {}", self._id, self.name)
+    }
+}
+
+// Labels of all the attachments of a row, for a single table cell.
+pub fn labels(attachments: &[Attachment]) -> String {
+    attachments.iter().map(|a| a.label()).collect::<Vec<String>>().join(", ")
+}
+
+// Rows that can have attachments, by the name the commands take.
+pub fn entity_table(name: &str) -> Option<&'static str> {
+    match name.to_lowercase().trim_end_matches('s') {
+        "transaction" => Some(TRANSACTIONS_KEY),
+        "payroll" => Some(PAYROLLS_KEY),
+        _ => None,
+    }
+}
+
+// Files live next to the db in `<name>-attachments`, named by their hash so a receipt attached
+// twice is stored once. In-memory dbs have nowhere to keep them.
+pub fn store_dir(db_name: &str) -> Option<PathBuf> {
+    if db_name == ":memory:" {
+        return None;
+    }
+
+    let path = Path::new(db_name);
+    let stem = path.file_stem()?.to_string_lossy();
+    Some(path.parent().unwrap_or_else(|| Path::new("")).join(format!("{}-attachments", stem)))
+}
+
+pub fn stored_path(dir: &Path, hash: &str) -> PathBuf {
+    dir.join(&hash[..2.min(hash.len())]).join(hash)
+}
+
+pub fn hash_file(path: &Path) -> io::Result<(String, u64)> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut size = 0;
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
+// Copies the file into the store unless the same contents are there already. Returns hash and size.
+pub fn store(dir: &Path, file: &Path) -> io::Result<(String, u64)> {
+    let (hash, size) = hash_file(file)?;
+    let target = stored_path(dir, &hash);
+
+    if !target.exists() {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = target.with_extension("tmp");
+        let _ = fs::remove_file(&tmp);
+        fs::copy(file, &tmp)?;
+        let mut permissions = fs::metadata(&tmp)?.permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&tmp, permissions)?;
+        fs::rename(&tmp, &target)?;
+    }
+
+    Ok((hash, size))
+}
+
+// What is wrong with the stored copy of the attachment, if anything.
+pub fn verify(dir: &Path, attachment: &Attachment) -> Option<String> {
+    let path = stored_path(dir, &attachment.hash);
+    match hash_file(&path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Some(format!("Missing {}", path.display())),
+        Err(e) => Some(format!("Can not read {}: {}", path.display(), e)),
+        Ok((hash, _)) if hash != attachment.hash => Some(format!("Contents changed, the hash is now {}", hash)),
+        Ok((_, size)) if size as i64 != attachment.size => Some(format!("Size is {} instead of {}", size, attachment.size)),
+        Ok(_) => None,
+    }
+}
+
+// Files in the store no attachment uses, left behind by undo or deleted rows.
+pub fn unreferenced(dir: &Path, hashes: &[String]) -> Vec<PathBuf> {
+    fs::read_dir(dir).into_iter().flatten().flatten()
+        .flat_map(|prefix| fs::read_dir(prefix.path()).into_iter().flatten().flatten())
+        .map(|entry| entry.path())
+        .filter(|path| path.file_name().is_some_and(|name| !hashes.iter().any(|h| name == h.as_str())))
+        .collect()
+}
@@ -0,0 +1,162 @@
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+use rusqlite::Row;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::models::{TAGS_KEY, COMPANIES_KEY, CATEGORIES_KEY, TRANSACTIONS_KEY, PAYROLLS_KEY, PAYROLL_ITEMS_KEY, ACCOUNTS_KEY, ATTACHMENTS_KEY};
+
+// Tables whose changes are recorded, with the singular name the commands also accept.
+const ENTITIES: [(&str, &str); 8] = [
+    ("transaction", TRANSACTIONS_KEY),
+    ("payroll", PAYROLLS_KEY),
+    ("payroll-item", PAYROLL_ITEMS_KEY),
+    ("account", ACCOUNTS_KEY),
+    ("tag", TAGS_KEY),
+    ("company", COMPANIES_KEY),
+    ("category", CATEGORIES_KEY),
+    ("attachment", ATTACHMENTS_KEY),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Create,
+    Update,
+    Delete,
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "create" => Ok(Action::Create),
+            "update" => Ok(Action::Update),
+            "delete" => Ok(Action::Delete),
+            _ => Err(format!("Invalid audit action [{}]", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::Create => write!(f, "create"),
+            Action::Update => write!(f, "update"),
+            Action::Delete => write!(f, "delete"),
+        }
+    }
+}
+
+// One changed row. Values are the whole row as json, before and after the change.
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub _id: i32,
+    // Every change made by the same command line, shell line or api request.
+    pub batch: i64,
+    pub time: String,
+    pub command: String,
+    pub entity: String,
+    pub entity_id: i32,
+    pub action: Action,
+    pub old_values: Option<String>,
+    pub new_values: Option<String>,
+    pub undone: bool,
+}
+
+impl AuditEntry {
+    pub fn from_row(r: &Row) -> Self {
+        let action: String = r.get_unwrap(6);
+
+        Self {
+            _id: r.get_unwrap(0),
+            batch: r.get_unwrap(1),
+            time: r.get_unwrap(2),
+            command: r.get_unwrap(3),
+            entity: r.get_unwrap(4),
+            entity_id: r.get_unwrap(5),
+            action: action.parse().unwrap_or(Action::Update),
+            old_values: r.get_unwrap(7),
+            new_values: r.get_unwrap(8),
+            undone: r.get_unwrap(9),
+        }
+    }
+
+    // Fields that changed, or the whole row for creates and deletes, as `field: value` pairs.
+    pub fn changes(&self) -> String {
+        let old = parse_object(&self.old_values);
+        let new = parse_object(&self.new_values);
+
+        let fields = match self.action {
+            Action::Create => new.iter().filter(|(k, _)| *k != "id").map(|(k, v)| format!("{}: {}", k, show(v))).collect(),
+            Action::Delete => old.iter().filter(|(k, _)| *k != "id").map(|(k, v)| format!("{}: {}", k, show(v))).collect(),
+            Action::Update => new.iter()
+                .filter(|(k, v)| old.get(*k) != Some(*v))
+                .map(|(k, v)| format!("{}: {} -> {}", k, old.get(k).map(show).unwrap_or_default(), show(v)))
+                .collect::<Vec<String>>(),
+        };
+
+        fields.join(", ")
+    }
+}
+
+// Table name for `transaction`, `transactions`, `payroll-item`...
+pub fn entity_table(name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase().replace('_', "-");
+    ENTITIES.iter()
+        .find(|(singular, table)| name == *singular || name.replace('-', "_") == *table)
+        .map(|(_, table)| *table)
+}
+
+pub fn entity_names() -> Vec<&'static str> {
+    ENTITIES.iter().map(|(singular, _)| *singular).collect()
+}
+
+pub fn is_audited(table: &str) -> bool {
+    ENTITIES.iter().any(|(_, t)| *t == table)
+}
+
+pub fn row_json(r: &Row, columns: &[String]) -> JsonValue {
+    let mut values = Map::new();
+    for (i, column) in columns.iter().enumerate() {
+        let value = match r.get_ref_unwrap(i) {
+            ValueRef::Null => JsonValue::Null,
+            ValueRef::Integer(v) => JsonValue::from(v),
+            ValueRef::Real(v) => JsonValue::from(v),
+            ValueRef::Text(v) => JsonValue::from(String::from_utf8_lossy(v).into_owned()),
+            ValueRef::Blob(v) => JsonValue::from(v.to_vec()),
+        };
+        values.insert(column.clone(), value);
+    }
+    JsonValue::Object(values)
+}
+
+pub fn sql_value(value: &JsonValue) -> SqlValue {
+    match value {
+        JsonValue::Null => SqlValue::Null,
+        JsonValue::Bool(v) => SqlValue::Integer(*v as i64),
+        JsonValue::Number(n) => match n.as_i64() {
+            Some(v) => SqlValue::Integer(v),
+            None => SqlValue::Real(n.as_f64().unwrap_or(0.0)),
+        },
+        JsonValue::String(v) => SqlValue::Text(v.clone()),
+        JsonValue::Array(v) => SqlValue::Blob(v.iter().filter_map(|b| b.as_u64()).map(|b| b as u8).collect()),
+        JsonValue::Object(_) => SqlValue::Text(value.to_string()),
+    }
+}
+
+pub fn parse_object(values: &Option<String>) -> Map<String, JsonValue> {
+    match values.as_ref().map(|v| serde_json::from_str(v)) {
+        Some(Ok(JsonValue::Object(map))) => map,
+        _ => Map::new(),
+    }
+}
+
+fn show(value: &JsonValue) -> String {
+    match value {
+        JsonValue::String(s) => s.clone(),
+        JsonValue::Null => String::from("-"),
+        v => v.to_string(),
+    }
+}
@@ -0,0 +1,228 @@
+use std::fmt::Formatter;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::raw::{c_char, c_int, c_uchar, c_uint};
+use std::path::Path;
+
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use rusqlite::{ffi, Connection};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+// Encrypted files are MAGIC | salt | nonce | sealed sqlite image. The header is authenticated too.
+const MAGIC: &[u8; 8] = b"GGCRYPT1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + SALT_LEN;
+
+const SQLITE_DESERIALIZE_FREEONCLOSE: c_uint = 1;
+const SQLITE_DESERIALIZE_RESIZEABLE: c_uint = 2;
+
+// Not in the bundled bindings, but exported by every sqlite since 3.36.
+extern "C" {
+    fn sqlite3_serialize(db: *mut ffi::sqlite3, schema: *const c_char, size: *mut i64, flags: c_uint) -> *mut c_uchar;
+    fn sqlite3_deserialize(db: *mut ffi::sqlite3, schema: *const c_char, data: *mut c_uchar, size: i64, capacity: i64, flags: c_uint) -> c_int;
+    fn sqlite3_malloc64(size: u64) -> *mut std::os::raw::c_void;
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Sqlite(rusqlite::Error),
+    Io(io::Error),
+    // The file is encrypted and nobody gave a passphrase.
+    KeyRequired,
+    // The passphrase does not open the file, which is also what a tampered file looks like.
+    WrongKey,
+    Corrupt(String),
+    // A valid sqlite file this version can not use.
+    Incompatible(String),
+    // Someone else wrote the file since it was read, saving would throw their changes away.
+    Changed,
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Sqlite(e) => write!(f, "{}", e),
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::KeyRequired => write!(f, "The database is encrypted and no passphrase was given"),
+            LoadError::WrongKey => write!(f, "Wrong passphrase for the encrypted database (or the file is damaged)"),
+            LoadError::Corrupt(e) => write!(f, "Invalid database file: {}", e),
+            LoadError::Incompatible(e) => write!(f, "Incompatible database: {}", e),
+            LoadError::Changed => write!(f, "The file was changed by another process since it was opened, not overwriting it"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<rusqlite::Error> for LoadError {
+    fn from(e: rusqlite::Error) -> Self {
+        LoadError::Sqlite(e)
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+// Key derived from the passphrase. Kept so saving does not run the key derivation again.
+pub struct Key {
+    key: Zeroizing<[u8; 32]>,
+    salt: [u8; SALT_LEN],
+}
+
+impl Key {
+    // A new salt, so rekeying with the same passphrase still changes the key.
+    pub fn new(passphrase: &str) -> Result<Key, LoadError> {
+        let mut salt = [0u8; SALT_LEN];
+        getrandom::getrandom(&mut salt).map_err(|e| LoadError::Io(io::Error::other(e.to_string())))?;
+        Self::derive(passphrase, salt)
+    }
+
+    fn derive(passphrase: &str, salt: [u8; SALT_LEN]) -> Result<Key, LoadError> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::default().hash_password_into(passphrase.as_bytes(), &salt, key.as_mut())
+            .map_err(|e| LoadError::Corrupt(e.to_string()))?;
+        Ok(Key { key, salt })
+    }
+
+    fn header(&self) -> Vec<u8> {
+        let mut header = MAGIC.to_vec();
+        header.extend_from_slice(&self.salt);
+        header
+    }
+}
+
+pub fn is_encrypted(path: &str) -> io::Result<bool> {
+    let mut magic = [0u8; 8];
+    match File::open(path).and_then(|mut f| f.read_exact(&mut magic)) {
+        Ok(()) => Ok(&magic == MAGIC),
+        Err(e) if matches!(e.kind(), io::ErrorKind::NotFound | io::ErrorKind::UnexpectedEof) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+// Reads an encrypted file and returns its key and the plain sqlite image. A known key with the
+// same salt (backups of this db) opens it without asking for the passphrase.
+pub fn open(path: &str, known: Option<&Key>, passphrase: impl FnOnce() -> Option<String>) -> Result<(Key, Zeroizing<Vec<u8>>), LoadError> {
+    unseal(&fs::read(path)?, known, passphrase)
+}
+
+// Same as open, for a file already read.
+pub fn unseal(data: &[u8], known: Option<&Key>, passphrase: impl FnOnce() -> Option<String>) -> Result<(Key, Zeroizing<Vec<u8>>), LoadError> {
+    if data.len() < HEADER_LEN + NONCE_LEN || &data[..MAGIC.len()] != MAGIC {
+        return Err(LoadError::Corrupt(String::from("truncated header")));
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    salt.copy_from_slice(&data[MAGIC.len()..HEADER_LEN]);
+    let key = match known.filter(|k| k.salt == salt) {
+        Some(k) => Key { key: k.key.clone(), salt },
+        None => Key::derive(&passphrase().ok_or(LoadError::KeyRequired)?, salt)?,
+    };
+
+    let nonce = Nonce::from_slice(&data[HEADER_LEN..HEADER_LEN + NONCE_LEN]);
+    let payload = Payload { msg: &data[HEADER_LEN + NONCE_LEN..], aad: &data[..HEADER_LEN] };
+    let plain = cipher(&key).decrypt(nonce, payload).map_err(|_| LoadError::WrongKey)?;
+
+    Ok((key, Zeroizing::new(plain)))
+}
+
+pub fn seal(key: &Key, plain: &[u8]) -> Result<Vec<u8>, LoadError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    getrandom::getrandom(&mut nonce).map_err(|e| LoadError::Io(io::Error::other(e.to_string())))?;
+
+    let header = key.header();
+    let sealed = cipher(key).encrypt(Nonce::from_slice(&nonce), Payload { msg: plain, aad: &header })
+        .map_err(|_| LoadError::Corrupt(String::from("encryption failed")))?;
+
+    let mut out = header;
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&sealed);
+    Ok(out)
+}
+
+fn cipher(key: &Key) -> ChaCha20Poly1305 {
+    ChaCha20Poly1305::new(key.key.as_ref().into())
+}
+
+pub fn hash(data: &[u8]) -> Vec<u8> {
+    Sha256::digest(data).to_vec()
+}
+
+// Hash of the file contents, None when there is no file. Tells whether someone else wrote it.
+pub fn fingerprint(path: &str) -> io::Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(data) => Ok(Some(hash(&data))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// Replaces the file in one step so a failed write never leaves half a database behind.
+pub fn write_file(path: &str, data: &[u8]) -> io::Result<()> {
+    let tmp = format!("{}.tmp", path);
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(&tmp)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    fs::rename(&tmp, Path::new(path))
+}
+
+// Copy of the main database of the connection, as it would be on disk.
+pub fn serialize(conn: &Connection) -> Result<Zeroizing<Vec<u8>>, LoadError> {
+    let mut size: i64 = 0;
+    unsafe {
+        let data = sqlite3_serialize(conn.handle(), b"main\0".as_ptr() as *const c_char, &mut size, 0);
+        if data.is_null() {
+            return Err(LoadError::Corrupt(String::from("could not serialize the database")));
+        }
+        let bytes = std::slice::from_raw_parts(data, size as usize).to_vec();
+        ffi::sqlite3_free(data as *mut _);
+        Ok(Zeroizing::new(bytes))
+    }
+}
+
+// Makes the image the main database of the connection. It lives in memory from then on.
+pub fn deserialize(conn: &Connection, image: &[u8]) -> Result<(), LoadError> {
+    // Cached statements still point to the pages of the replaced database.
+    conn.flush_prepared_statement_cache();
+    unsafe {
+        let data = sqlite3_malloc64(image.len() as u64) as *mut c_uchar;
+        if data.is_null() {
+            return Err(LoadError::Sqlite(rusqlite::Error::SqliteFailure(ffi::Error::new(ffi::SQLITE_NOMEM), None)));
+        }
+        std::ptr::copy_nonoverlapping(image.as_ptr(), data, image.len());
+
+        let size = image.len() as i64;
+        let rc = sqlite3_deserialize(conn.handle(), b"main\0".as_ptr() as *const c_char, data, size, size,
+                                     SQLITE_DESERIALIZE_FREEONCLOSE | SQLITE_DESERIALIZE_RESIZEABLE);
+        if rc != ffi::SQLITE_OK {
+            return Err(LoadError::Sqlite(rusqlite::Error::SqliteFailure(ffi::Error::new(rc), None)));
+        }
+    }
+
+    // A wrong image only shows up on the first read.
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |r| r.get::<_, i64>(0))
+        .map_err(|_| LoadError::Corrupt(String::from("not a sqlite database")))?;
+    Ok(())
+}
+
+// Row changes, schema changes and the user version, which none of the other two count.
+pub fn changes(conn: &Connection) -> (i32, i64, i64) {
+    let total = unsafe { ffi::sqlite3_total_changes(conn.handle()) };
+    let pragma = |name: &str| conn.query_row(&format!("PRAGMA {}", name), [], |r| r.get(0)).unwrap_or(0);
+    (total, pragma("schema_version"), pragma("user_version"))
+}
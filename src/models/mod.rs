@@ -1,13 +1,36 @@
-use rusqlite::{Connection, Error, params, Params, Row};
+pub use encryption::LoadError;
+
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+
+use rusqlite::{ffi, Connection, Error, params, params_from_iter, Params, Row};
+use rusqlite::backup::Backup;
+use rusqlite::types::Value as SqlValue;
 use log;
+use serde_json::Value as JsonValue;
+
+use crate::models::attachment::Attachment;
+use crate::models::audit::{Action, AuditEntry};
+use crate::models::encryption::Key;
+use crate::models::search::{SearchFilter, SearchHit};
 
 pub mod transaction;
 pub mod payroll;
 pub mod account;
+pub mod payroll_item;
+pub mod validation;
+pub mod audit;
+pub mod attachment;
+pub mod search;
+mod encryption;
 
 use crate::models::transaction::Transaction;
 use crate::models::payroll::Payroll;
 use crate::models::account::Account;
+use crate::models::payroll_item::PayrollItem;
+
+// Stored as PRAGMA user_version. Bump it with every change to the tables.
+pub const SCHEMA_VERSION: i64 = 4;
 
 const TAGS_KEY: &str = "tags";
 const TAGS_TABLE: &str = "
@@ -48,7 +71,19 @@ net REAL NOT NULL,
 ss REAL NOT NULL, 
 irpf REAL NOT NULL,
 company_id INTEGER REFERENCES companies(id), 
-category_id INTEGER REFERENCES categories(id)
+category_id INTEGER REFERENCES categories(id),
+other REAL NOT NULL DEFAULT 0
+";
+
+const PAYROLL_ITEMS_KEY: &str = "payroll_items";
+const PAYROLL_ITEMS_TABLE: &str = "
+id INTEGER PRIMARY KEY,
+payroll_id INTEGER NOT NULL REFERENCES payrolls(id),
+concept TEXT NOT NULL,
+kind TEXT NOT NULL CHECK (kind IN ('earning', 'deduction')),
+amount REAL NOT NULL,
+irpf_taxable INTEGER NOT NULL DEFAULT 1,
+ss_taxable INTEGER NOT NULL DEFAULT 1
 ";
 
 const ACCOUNTS_KEY: &str = "accounts";
@@ -59,6 +94,79 @@ amount REAL NOT NULL,
 description TEXT
 ";
 
+// Full text index of transactions and payrolls. The triggers keep it up to date whatever changes
+// the rows: commands, the api, undo or a restore of an older backup.
+const SEARCH_KEY: &str = "search";
+const SEARCH_TABLE: &str = "kind UNINDEXED, row_id UNINDEXED, name, notes, names, tokenize = 'unicode61 remove_diacritics 2'";
+const SEARCH_FILL: &str = "
+INSERT INTO search (kind, row_id, name, notes, names)
+    SELECT 'transaction', t.id, t.name, t.notes, tags.name FROM transactions t LEFT JOIN tags ON tags.id = t.tag_id;
+INSERT INTO search (kind, row_id, name, notes, names)
+    SELECT 'payroll', p.id, companies.name, NULL, categories.name FROM payrolls p
+    LEFT JOIN companies ON companies.id = p.company_id LEFT JOIN categories ON categories.id = p.category_id;
+";
+const SEARCH_TRIGGERS: &str = "
+CREATE TRIGGER IF NOT EXISTS search_transaction_insert AFTER INSERT ON transactions BEGIN
+    INSERT INTO search (kind, row_id, name, notes, names)
+    VALUES ('transaction', new.id, new.name, new.notes, (SELECT name FROM tags WHERE id = new.tag_id));
+END;
+CREATE TRIGGER IF NOT EXISTS search_transaction_update AFTER UPDATE ON transactions BEGIN
+    DELETE FROM search WHERE kind = 'transaction' AND row_id = old.id;
+    INSERT INTO search (kind, row_id, name, notes, names)
+    VALUES ('transaction', new.id, new.name, new.notes, (SELECT name FROM tags WHERE id = new.tag_id));
+END;
+CREATE TRIGGER IF NOT EXISTS search_transaction_delete AFTER DELETE ON transactions BEGIN
+    DELETE FROM search WHERE kind = 'transaction' AND row_id = old.id;
+END;
+CREATE TRIGGER IF NOT EXISTS search_payroll_insert AFTER INSERT ON payrolls BEGIN
+    INSERT INTO search (kind, row_id, name, notes, names)
+    VALUES ('payroll', new.id, (SELECT name FROM companies WHERE id = new.company_id), NULL, (SELECT name FROM categories WHERE id = new.category_id));
+END;
+CREATE TRIGGER IF NOT EXISTS search_payroll_update AFTER UPDATE ON payrolls BEGIN
+    DELETE FROM search WHERE kind = 'payroll' AND row_id = old.id;
+    INSERT INTO search (kind, row_id, name, notes, names)
+    VALUES ('payroll', new.id, (SELECT name FROM companies WHERE id = new.company_id), NULL, (SELECT name FROM categories WHERE id = new.category_id));
+END;
+CREATE TRIGGER IF NOT EXISTS search_payroll_delete AFTER DELETE ON payrolls BEGIN
+    DELETE FROM search WHERE kind = 'payroll' AND row_id = old.id;
+END;
+CREATE TRIGGER IF NOT EXISTS search_tag_update AFTER UPDATE OF name ON tags BEGIN
+    UPDATE search SET names = new.name WHERE kind = 'transaction' AND row_id IN (SELECT id FROM transactions WHERE tag_id = new.id);
+END;
+CREATE TRIGGER IF NOT EXISTS search_company_update AFTER UPDATE OF name ON companies BEGIN
+    UPDATE search SET name = new.name WHERE kind = 'payroll' AND row_id IN (SELECT id FROM payrolls WHERE company_id = new.id);
+END;
+CREATE TRIGGER IF NOT EXISTS search_category_update AFTER UPDATE OF name ON categories BEGIN
+    UPDATE search SET names = new.name WHERE kind = 'payroll' AND row_id IN (SELECT id FROM payrolls WHERE category_id = new.id);
+END;
+";
+
+const ATTACHMENTS_KEY: &str = "attachments";
+const ATTACHMENTS_TABLE: &str = "
+id INTEGER PRIMARY KEY,
+entity TEXT NOT NULL CHECK (entity IN ('transactions', 'payrolls')),
+entity_id INTEGER NOT NULL,
+name TEXT NOT NULL,
+hash TEXT NOT NULL,
+size INTEGER NOT NULL,
+added TEXT NOT NULL
+";
+
+const AUDIT_KEY: &str = "audit";
+const AUDIT_TABLE: &str = "
+id INTEGER PRIMARY KEY,
+batch INTEGER NOT NULL,
+time TEXT NOT NULL,
+command TEXT NOT NULL,
+entity TEXT NOT NULL,
+entity_id INTEGER NOT NULL,
+action TEXT NOT NULL CHECK (action IN ('create', 'update', 'delete')),
+old_values TEXT,
+new_values TEXT,
+undone INTEGER NOT NULL DEFAULT 0
+";
+
+#[derive(Debug, Clone)]
 pub struct Name {
     pub id: i32,
     pub name: String,
@@ -74,23 +182,125 @@ impl Name {
 pub struct Db {
     name: String,
     connection: Connection,
+    // Set while the file on disk is encrypted. Changes are then written back sealed on save.
+    key: RefCell<Option<Key>>,
+    // The connection works on an in-memory copy that save writes back to the file.
+    in_memory: Cell<bool>,
+    saved: Cell<(i32, i64, i64)>,
+    // Hash of the file as last read or written, checked before writing the in-memory copy back.
+    on_disk: RefCell<Option<Vec<u8>>>,
+    // What the audit records as the cause of the changes, and the batch they go to once one is made.
+    command: RefCell<String>,
+    batch: Cell<Option<i64>>,
+    // Inserting into the audit moves the connection last rowid, so the one of the row itself is kept.
+    last_insert: Cell<i32>,
 }
 
 impl Db {
-    pub fn load(db_name: &str) -> Result<Db, Error> {
+    pub fn load(db_name: &str) -> Result<Db, LoadError> {
+        Self::open(db_name, || None)
+    }
+    
+    // The passphrase is only asked for when the file turns out to be encrypted.
+    pub fn open(db_name: &str, passphrase: impl FnOnce() -> Option<String>) -> Result<Db, LoadError> {
         log::trace!("Loading db from {}", db_name);
         
-        let conn = rusqlite::Connection::open(db_name)?;
-        let db = Db {
-            name: String::from(db_name),
-            connection: conn,
+        let db = if encryption::is_encrypted(db_name)? {
+            let data = std::fs::read(db_name)?;
+            let (key, image) = encryption::unseal(&data, None, passphrase)?;
+            
+            let conn = Connection::open_in_memory()?;
+            encryption::deserialize(&conn, &image)?;
+            let db = Db::with_connection(db_name, conn, Some(key));
+            db.on_disk.replace(Some(encryption::hash(&data)));
+            db
+        } else {
+            Db::with_connection(db_name, Connection::open(db_name)?, None)
         };
         
+        db.saved.set(encryption::changes(&db.connection));
         db.init_tables()?;
         
         Ok(db)
     }
     
+    fn with_connection(db_name: &str, connection: Connection, key: Option<Key>) -> Db {
+        let in_memory = key.is_some();
+        Db {
+            name: String::from(db_name),
+            connection,
+            key: RefCell::new(key),
+            in_memory: Cell::new(in_memory),
+            saved: Cell::new((0, 0, 0)),
+            on_disk: RefCell::new(None),
+            command: RefCell::new(std::env::args().skip(1).collect::<Vec<String>>().join(" ")),
+            batch: Cell::new(None),
+            last_insert: Cell::new(0),
+        }
+    }
+    
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    
+    pub fn is_encrypted(&self) -> bool {
+        self.key.borrow().is_some()
+    }
+    
+    // Writes the in-memory copy back to the file when something changed. Plain files are written by sqlite itself.
+    pub fn save(&self) -> Result<(), LoadError> {
+        if !self.in_memory.get() || encryption::changes(&self.connection) == self.saved.get() {
+            return Ok(());
+        }
+        self.write_back()
+    }
+    
+    // Encrypts the file with a new passphrase, also used to rekey an encrypted one.
+    pub fn encrypt(&self, passphrase: &str) -> Result<(), LoadError> {
+        log::trace!("Encrypting {}", self.name);
+        
+        if !self.in_memory.get() {
+            let image = encryption::serialize(&self.connection)?;
+            encryption::deserialize(&self.connection, &image)?;
+            self.in_memory.set(true);
+        }
+        
+        self.key.replace(Some(Key::new(passphrase)?));
+        self.write_back()
+    }
+    
+    // Writes the file back as a plain sqlite database.
+    pub fn decrypt(&self) -> Result<(), LoadError> {
+        log::trace!("Decrypting {}", self.name);
+        
+        self.key.replace(None);
+        self.write_back()
+    }
+    
+    fn write_back(&self) -> Result<(), LoadError> {
+        if self.name == ":memory:" {
+            return Ok(());
+        }
+        
+        // A plain file turned into an in-memory copy by encrypt has no hash yet, it was ours until now.
+        if let Some(hash) = self.on_disk.borrow().as_ref() {
+            if encryption::fingerprint(&self.name)?.as_ref() != Some(hash) {
+                return Err(LoadError::Changed);
+            }
+        }
+        
+        let image = encryption::serialize(&self.connection)?;
+        let data = match self.key.borrow().as_ref() {
+            Some(key) => encryption::seal(key, &image)?,
+            None => image.to_vec(),
+        };
+        encryption::write_file(&self.name, &data)?;
+        
+        self.on_disk.replace(Some(encryption::hash(&data)));
+        self.saved.set(encryption::changes(&self.connection));
+        Ok(())
+    }
+    
     pub fn init_tables(&self) -> Result<(), Error> {
         log::trace!("Init tables if not created...");
 
@@ -100,6 +310,94 @@ impl Db {
         self.create_table_if_not_exists(TRANSACTIONS_KEY, TRANSACTIONS_TABLE)?;
         self.create_table_if_not_exists(PAYROLLS_KEY, PAYROLLS_TABLE)?;
         self.create_table_if_not_exists(ACCOUNTS_KEY, ACCOUNTS_TABLE)?;
+        self.create_table_if_not_exists(PAYROLL_ITEMS_KEY, PAYROLL_ITEMS_TABLE)?;
+        self.create_table_if_not_exists(ATTACHMENTS_KEY, ATTACHMENTS_TABLE)?;
+        self.create_table_if_not_exists(AUDIT_KEY, AUDIT_TABLE)?;
+        
+        // Columns added after the first release, for databases created before them.
+        self.add_column_if_not_exists(PAYROLLS_KEY, "other", "REAL NOT NULL DEFAULT 0")?;
+        self.add_column_if_not_exists(TRANSACTIONS_KEY, "notes", "TEXT")?;
+        
+        let indexed: i64 = self.connection.query_row("SELECT COUNT(*) FROM sqlite_master WHERE name = ?1", [SEARCH_KEY], |r| r.get(0))?;
+        if indexed == 0 {
+            log::info!("Creating the search index of {}", self.name);
+            self.connection.execute_batch(&format!("CREATE VIRTUAL TABLE {} USING fts5({});{}", SEARCH_KEY, SEARCH_TABLE, SEARCH_FILL))?;
+        }
+        self.connection.execute_batch(SEARCH_TRIGGERS)?;
+        
+        if self.schema_version()? < SCHEMA_VERSION {
+            self.connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        }
+        
+        Ok(())
+    }
+    
+    pub fn schema_version(&self) -> Result<i64, Error> {
+        self.connection.query_row("PRAGMA user_version", [], |r| r.get(0))
+    }
+    
+    // Copies the db with the online backup api and writes it like the db itself is stored,
+    // encrypted with the same key when it is. The written file is read back and checked.
+    pub fn backup_to(&self, path: &str) -> Result<(), LoadError> {
+        log::trace!("Backing up {} to {}", self.name, path);
+        
+        let mut copy = Connection::open_in_memory()?;
+        Backup::new(&self.connection, &mut copy)?.run_to_completion(256, std::time::Duration::ZERO, None)?;
+        
+        let image = encryption::serialize(&copy)?;
+        let data = match self.key.borrow().as_ref() {
+            Some(key) => encryption::seal(key, &image)?,
+            None => image.to_vec(),
+        };
+        encryption::write_file(path, &data)?;
+        
+        self.check_backup(path, || None).map(|_| ())
+    }
+    
+    // Opens a backup without touching it and checks it can be restored here. Returns its schema version.
+    pub fn check_backup(&self, path: &str, passphrase: impl FnOnce() -> Option<String>) -> Result<i64, LoadError> {
+        let copy = self.open_backup(path, passphrase)?;
+        check_copy(&copy)
+    }
+    
+    // Replaces every table with the backup contents, keeping this db encryption. Older backups are
+    // brought up to date like any older db.
+    pub fn restore_from(&self, path: &str, passphrase: impl FnOnce() -> Option<String>) -> Result<i64, LoadError> {
+        log::trace!("Restoring {} from {}", self.name, path);
+        
+        let copy = self.open_backup(path, passphrase)?;
+        let version = check_copy(&copy)?;
+        
+        copy_into(&copy, &self.connection)?;
+        self.init_tables()?;
+        // A plain file has been updated by the copy already, replacing it would leave the
+        // connection pointing at a removed file that can no longer be written.
+        if self.in_memory.get() {
+            self.write_back()?;
+        }
+        
+        Ok(version)
+    }
+    
+    fn open_backup(&self, path: &str, passphrase: impl FnOnce() -> Option<String>) -> Result<Connection, LoadError> {
+        let image = match encryption::is_encrypted(path)? {
+            true => encryption::open(path, self.key.borrow().as_ref(), passphrase)?.1,
+            false => zeroize::Zeroizing::new(std::fs::read(path)?),
+        };
+        
+        let copy = Connection::open_in_memory()?;
+        encryption::deserialize(&copy, &image)?;
+        Ok(copy)
+    }
+    
+    fn add_column_if_not_exists(&self, table_name: &str, column: &str, format: &str) -> Result<(), Error> {
+        let sql = format!("SELECT name FROM pragma_table_info('{}')", table_name);
+        let columns: Vec<String> = self.query(&sql, [], |r| r.get(0).ok())?;
+        
+        if !columns.iter().any(|c| c == column) {
+            log::info!("Adding column {} to {} table on {}", column, table_name.to_uppercase(), self.name);
+            self.connection.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table_name, column, format), [])?;
+        }
         
         Ok(())
     }
@@ -126,19 +424,131 @@ impl Db {
     pub fn insert_transaction(&self, transaction: &Transaction) -> Result<usize, Error> {
         log::trace!("Inserting new transaction: {:?} to {}", transaction, self.name);
         
-        let sql = format!("INSERT INTO {} (name, date, amount, tag_id) VALUES (?1, ?2, ?3, ?4)", TRANSACTIONS_KEY);
-        let params = params![&transaction.name, &transaction.date, &transaction.amount, &transaction.tag_id];
+        let sql = format!("INSERT INTO {} (name, date, amount, tag_id, notes) VALUES (?1, ?2, ?3, ?4, ?5)", TRANSACTIONS_KEY);
+        let params = params![&transaction.name, &transaction.date, &transaction.amount, &transaction.tag_id, &transaction.notes];
+        
+        self.audited(TRANSACTIONS_KEY, None, || self.connection.execute(&sql, params))
+    }
+    
+    pub fn update_transaction(&self, transaction: &Transaction) -> Result<usize, Error> {
+        log::trace!("Updating transaction: {:?} in {}", transaction, self.name);
+        
+        let sql = format!("UPDATE {} SET name = ?1, date = ?2, amount = ?3, tag_id = ?4, notes = ?5 WHERE id = ?6", TRANSACTIONS_KEY);
+        let params = params![&transaction.name, &transaction.date, &transaction.amount, &transaction.tag_id, &transaction.notes, &transaction._id];
+        
+        self.audited(TRANSACTIONS_KEY, Some(transaction._id), || self.connection.execute(&sql, params))
+    }
+    
+    // Id of the row added by the last insert on this connection.
+    pub fn last_insert_id(&self) -> i32 {
+        self.last_insert.get()
+    }
+    
+    pub fn get_transaction(&self, id: i32) -> Result<Transaction, Error> {
+        let sql = format!("SELECT * FROM {} WHERE id = ?1", TRANSACTIONS_KEY);
+        let mut data = self.query(&sql, [id], |r| Some(Transaction::from_row(r)))?;
         
-        self.connection.execute(&sql, params)
+        data.pop().ok_or(Error::QueryReturnedNoRows)
+    }
+    
+    pub fn delete_transaction(&self, id: i32) -> Result<usize, Error> {
+        log::trace!("Deleting transaction {} from {}", id, self.name);
+        self.delete_attachments_of(TRANSACTIONS_KEY, id)?;
+        self.delete_by_id(TRANSACTIONS_KEY, id)
     }
     
     pub fn insert_payroll(&self, payroll: &Payroll) -> Result<usize, Error> {
         log::trace!("Inserting new payroll: {:?} to {}", payroll, self.name);
 
-        let sql = format!("INSERT INTO {} (date, gross, net, ss, irpf, company_id, category_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)", PAYROLLS_KEY);
-        let params = params![&payroll.date, &payroll.gross, &payroll.net, &payroll.ss, &payroll.irpf, &payroll.company_id, &payroll.category_id];
+        let sql = format!("INSERT INTO {} (date, gross, net, ss, irpf, company_id, category_id, other) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)", PAYROLLS_KEY);
+        let params = params![&payroll.date, &payroll.gross, &payroll.net, &payroll.ss, &payroll.irpf, &payroll.company_id, &payroll.category_id, &payroll.other];
+        
+        self.audited(PAYROLLS_KEY, None, || self.connection.execute(&sql, params))
+    }
+    
+    pub fn update_payroll(&self, payroll: &Payroll) -> Result<usize, Error> {
+        log::trace!("Updating payroll: {:?} in {}", payroll, self.name);
+        
+        let sql = format!("UPDATE {} SET date = ?1, gross = ?2, net = ?3, ss = ?4, irpf = ?5, company_id = ?6, category_id = ?7, other = ?8 WHERE id = ?9", PAYROLLS_KEY);
+        let params = params![&payroll.date, &payroll.gross, &payroll.net, &payroll.ss, &payroll.irpf, &payroll.company_id, &payroll.category_id, &payroll.other, &payroll._id];
         
-        self.connection.execute(&sql, params)
+        self.audited(PAYROLLS_KEY, Some(payroll._id), || self.connection.execute(&sql, params))
+    }
+    
+    // Items and attachments belong to the payroll so they go with it.
+    pub fn delete_payroll(&self, id: i32) -> Result<usize, Error> {
+        log::trace!("Deleting payroll {} from {}", id, self.name);
+        
+        for item in self.get_payroll_items(id)? {
+            self.delete_by_id(PAYROLL_ITEMS_KEY, item._id)?;
+        }
+        self.delete_attachments_of(PAYROLLS_KEY, id)?;
+        self.delete_by_id(PAYROLLS_KEY, id)
+    }
+    
+    pub fn insert_payroll_item(&self, item: &PayrollItem) -> Result<usize, Error> {
+        log::trace!("Inserting new payroll item: {:?} to {}", item, self.name);
+        
+        let sql = format!("INSERT INTO {} (payroll_id, concept, kind, amount, irpf_taxable, ss_taxable) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", PAYROLL_ITEMS_KEY);
+        let params = params![&item.payroll_id, &item.concept, &item.kind.to_string(), &item.amount, &item.irpf_taxable, &item.ss_taxable];
+        
+        self.audited(PAYROLL_ITEMS_KEY, None, || self.connection.execute(&sql, params))
+    }
+    
+    // Where the attached files are kept, None for in-memory dbs.
+    pub fn attachments_dir(&self) -> Option<PathBuf> {
+        attachment::store_dir(&self.name)
+    }
+    
+    // The file must already be in the store. The row it belongs to must exist.
+    pub fn insert_attachment(&self, attachment: &Attachment) -> Result<usize, Error> {
+        log::trace!("Inserting new attachment: {:?} to {}", attachment, self.name);
+        
+        if !self.has_row(&attachment.entity, attachment.entity_id)? {
+            return Err(Error::QueryReturnedNoRows);
+        }
+        
+        let sql = format!("INSERT INTO {} (entity, entity_id, name, hash, size, added) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", ATTACHMENTS_KEY);
+        let params = params![&attachment.entity, &attachment.entity_id, &attachment.name, &attachment.hash, &attachment.size, &attachment.added];
+        
+        self.audited(ATTACHMENTS_KEY, None, || self.connection.execute(&sql, params))
+    }
+    
+    pub fn get_attachment(&self, id: i32) -> Result<Attachment, Error> {
+        let sql = format!("SELECT * FROM {} WHERE id = ?1", ATTACHMENTS_KEY);
+        let mut data = self.query(&sql, [id], |r| Some(Attachment::from_row(r)))?;
+        
+        data.pop().ok_or(Error::QueryReturnedNoRows)
+    }
+    
+    pub fn get_transaction_attachments(&self, transaction_id: i32) -> Result<Vec<Attachment>, Error> {
+        self.get_attachments_of(TRANSACTIONS_KEY, transaction_id)
+    }
+    
+    pub fn get_payroll_attachments(&self, payroll_id: i32) -> Result<Vec<Attachment>, Error> {
+        self.get_attachments_of(PAYROLLS_KEY, payroll_id)
+    }
+    
+    pub fn get_all_attachments(&self) -> Result<Vec<Attachment>, Error> {
+        let sql = format!("SELECT * FROM {} ORDER BY id", ATTACHMENTS_KEY);
+        self.query(&sql, [], |r| Some(Attachment::from_row(r)))
+    }
+    
+    fn get_attachments_of(&self, table: &str, id: i32) -> Result<Vec<Attachment>, Error> {
+        let sql = format!("SELECT * FROM {} WHERE entity = ?1 AND entity_id = ?2 ORDER BY id", ATTACHMENTS_KEY);
+        self.query(&sql, params![table, id], |r| Some(Attachment::from_row(r)))
+    }
+    
+    // Only the rows go, the files stay in the store in case the delete is undone.
+    fn delete_attachments_of(&self, table: &str, id: i32) -> Result<(), Error> {
+        for attachment in self.get_attachments_of(table, id)? {
+            self.delete_by_id(ATTACHMENTS_KEY, attachment._id)?;
+        }
+        Ok(())
+    }
+    
+    pub fn has_row(&self, table: &str, id: i32) -> Result<bool, Error> {
+        Ok(self.row_values(table, id)?.is_some())
     }
     
     pub fn insert_tag(&self, tag: &str, description: &str) -> Result<usize, Error> {
@@ -160,7 +570,71 @@ impl Db {
         let sql = format!("INSERT INTO {} (name, amount, description) VALUES (?1, ?2, ?3)", ACCOUNTS_KEY);
         let params = params![&account.name, &account.amount, &account.description];
         
-        self.connection.execute(&sql, params)
+        self.audited(ACCOUNTS_KEY, None, || self.connection.execute(&sql, params))
+    }
+
+    pub fn update_account(&self, account: &Account) -> Result<usize, Error> {
+        log::trace!("Updating account: {:?} in {}", account, self.name);
+        
+        let sql = format!("UPDATE {} SET name = ?1, amount = ?2, description = ?3 WHERE id = ?4", ACCOUNTS_KEY);
+        let params = params![&account.name, &account.amount, &account.description, &account._id];
+        
+        self.audited(ACCOUNTS_KEY, Some(account._id), || self.connection.execute(&sql, params))
+    }
+    
+    pub fn delete_account(&self, id: i32) -> Result<usize, Error> {
+        log::trace!("Deleting account {} from {}", id, self.name);
+        self.delete_by_id(ACCOUNTS_KEY, id)
+    }
+    
+    pub fn update_tag(&self, name: &Name) -> Result<usize, Error> {
+        self.update_name(TAGS_KEY, name)
+    }
+    
+    pub fn update_company(&self, name: &Name) -> Result<usize, Error> {
+        self.update_name(COMPANIES_KEY, name)
+    }
+    
+    pub fn update_category(&self, name: &Name) -> Result<usize, Error> {
+        self.update_name(CATEGORIES_KEY, name)
+    }
+    
+    // Names still referenced by transactions or payrolls are kept, returning how many rows use them.
+    pub fn delete_tag(&self, id: i32) -> Result<usize, Error> {
+        self.delete_name(TAGS_KEY, id, &[(TRANSACTIONS_KEY, "tag_id")])
+    }
+    
+    pub fn delete_company(&self, id: i32) -> Result<usize, Error> {
+        self.delete_name(COMPANIES_KEY, id, &[(PAYROLLS_KEY, "company_id")])
+    }
+    
+    pub fn delete_category(&self, id: i32) -> Result<usize, Error> {
+        self.delete_name(CATEGORIES_KEY, id, &[(PAYROLLS_KEY, "category_id")])
+    }
+    
+    fn update_name(&self, table: &str, name: &Name) -> Result<usize, Error> {
+        log::trace!("Updating 'name' {} in {}::{}. Value: {} - Description: {}", name.id, self.name, table, name.name, name.description);
+        
+        let sql = format!("UPDATE {} SET name = ?1, description = ?2 WHERE id = ?3", table);
+        self.audited(table, Some(name.id), || self.connection.execute(&sql, params![&name.name, &name.description, &name.id]))
+    }
+    
+    fn delete_name(&self, table: &str, id: i32, references: &[(&str, &str)]) -> Result<usize, Error> {
+        for (other, column) in references {
+            let sql = format!("SELECT COUNT(*) FROM {} WHERE {} = ?1", other, column);
+            let uses: i64 = self.connection.query_row(&sql, [id], |r| r.get(0))?;
+            if uses > 0 {
+                return Err(constraint_error(format!("{} {} is used by {} rows of {}", table, id, uses, other)));
+            }
+        }
+        
+        log::trace!("Deleting 'name' {} from {}::{}", id, self.name, table);
+        self.delete_by_id(table, id)
+    }
+    
+    fn delete_by_id(&self, table: &str, id: i32) -> Result<usize, Error> {
+        let sql = format!("DELETE FROM {} WHERE id = ?1", table);
+        self.audited(table, Some(id), || self.connection.execute(&sql, [id]))
     }
 
     // Using 'name' to describe tag, company, category generically. (something with only [id, name, description])
@@ -170,7 +644,192 @@ impl Db {
         let sql = format!("INSERT INTO {} (name, description) VALUES (?1, ?2)", table);
         let params = params![&value, &description];
         
-        self.connection.execute(&sql, params)
+        self.audited(table, None, || self.connection.execute(&sql, params))
+    }
+    
+    // Changes made from now on are one batch for undo, recorded as caused by the command.
+    pub fn start_batch(&self, command: &str) {
+        self.command.replace(String::from(command));
+        self.batch.set(None);
+    }
+    
+    // Runs a change on one row and records it in the audit. Inserts pass no id, the new row's is used.
+    fn audited(&self, table: &str, id: Option<i32>, change: impl FnOnce() -> Result<usize, Error>) -> Result<usize, Error> {
+        let old = match id {
+            Some(id) => self.row_values(table, id)?,
+            None => None,
+        };
+        
+        let changed = change()?;
+        if changed == 0 {
+            return Ok(0);
+        }
+        
+        let id = id.unwrap_or_else(|| {
+            let id = self.connection.last_insert_rowid() as i32;
+            self.last_insert.set(id);
+            id
+        });
+        let new = self.row_values(table, id)?;
+        
+        let action = match (&old, &new) {
+            (None, _) => Action::Create,
+            (_, None) => Action::Delete,
+            _ if old == new => return Ok(changed),
+            _ => Action::Update,
+        };
+        self.record(table, id, action, old, new)?;
+        
+        Ok(changed)
+    }
+    
+    fn row_values(&self, table: &str, id: i32) -> Result<Option<JsonValue>, Error> {
+        let mut stmt = self.connection.prepare(&format!("SELECT * FROM {} WHERE id = ?1", table))?;
+        let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+        let mut rows = stmt.query([id])?;
+        
+        Ok(rows.next()?.map(|r| audit::row_json(r, &columns)))
+    }
+    
+    fn record(&self, table: &str, id: i32, action: Action, old: Option<JsonValue>, new: Option<JsonValue>) -> Result<(), Error> {
+        let batch = match self.batch.get() {
+            Some(batch) => batch,
+            None => {
+                let sql = format!("SELECT COALESCE(MAX(batch), 0) + 1 FROM {}", AUDIT_KEY);
+                let batch: i64 = self.connection.query_row(&sql, [], |r| r.get(0))?;
+                self.batch.set(Some(batch));
+                batch
+            },
+        };
+        
+        let sql = format!("INSERT INTO {} (batch, time, command, entity, entity_id, action, old_values, new_values) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)", AUDIT_KEY);
+        let time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let params = params![batch, time, &*self.command.borrow(), table, id, action.to_string(), old.map(|v| v.to_string()), new.map(|v| v.to_string())];
+        
+        self.connection.execute(&sql, params)?;
+        Ok(())
+    }
+    
+    // Newest first. Dates are YYYY-MM-DD and both ends are included.
+    pub fn get_history(&self, entity: Option<&str>, from: Option<&str>, to: Option<&str>, include_undone: bool, limit: usize) -> Result<Vec<AuditEntry>, Error> {
+        log::trace!("Getting history of {}", self.name);
+        
+        let mut sql = format!("SELECT * FROM {} WHERE 1 = 1", AUDIT_KEY);
+        let mut values: Vec<String> = Vec::new();
+        for (condition, value) in [("entity = ?", entity), ("date(time) >= ?", from), ("date(time) <= ?", to)] {
+            if let Some(value) = value {
+                sql += &format!(" AND {}", condition);
+                values.push(String::from(value));
+            }
+        }
+        if !include_undone {
+            sql += " AND undone = 0";
+        }
+        sql += &format!(" ORDER BY id DESC LIMIT {}", limit);
+        
+        self.query(&sql, params_from_iter(values), |r| Some(AuditEntry::from_row(r)))
+    }
+    
+    // Best matches first. The query is fts5 syntax, see search::match_query for plain words. Matched
+    // words in the text fields are wrapped in the markers.
+    pub fn search(&self, query: &str, filter: &SearchFilter, marks: (&str, &str)) -> Result<Vec<SearchHit>, Error> {
+        log::trace!("Searching [{}] in {}", query, self.name);
+        
+        let mut sql = format!("SELECT {0}.kind, {0}.row_id, COALESCE(t.date, p.date), COALESCE(t.amount, p.net), \
+            highlight({0}, 2, ?2, ?3), highlight({0}, 3, ?2, ?3), highlight({0}, 4, ?2, ?3), bm25({0}, 0, 0, 4, 2, 1) \
+            FROM {0} \
+            LEFT JOIN {1} t ON {0}.kind = 'transaction' AND t.id = {0}.row_id \
+            LEFT JOIN {2} p ON {0}.kind = 'payroll' AND p.id = {0}.row_id \
+            WHERE {0} MATCH ?1", SEARCH_KEY, TRANSACTIONS_KEY, PAYROLLS_KEY);
+        let mut values = vec![SqlValue::from(String::from(query)), SqlValue::from(String::from(marks.0)), SqlValue::from(String::from(marks.1))];
+        
+        let mut condition = |sql_condition: &str, value: SqlValue| {
+            sql += &format!(" AND {}", sql_condition);
+            values.push(value);
+        };
+        if let Some(kind) = &filter.kind {
+            condition("kind = ?", SqlValue::from(kind.clone()));
+        }
+        if let Some(year) = filter.year {
+            condition("strftime('%Y', COALESCE(t.date, p.date)) = ?", SqlValue::from(format!("{:04}", year)));
+        }
+        if let Some(month) = filter.month {
+            condition("strftime('%m', COALESCE(t.date, p.date)) = ?", SqlValue::from(format!("{:02}", month)));
+        }
+        if let Some(from) = &filter.from {
+            condition("COALESCE(t.date, p.date) >= ?", SqlValue::from(from.clone()));
+        }
+        if let Some(to) = &filter.to {
+            condition("COALESCE(t.date, p.date) <= ?", SqlValue::from(to.clone()));
+        }
+        if let Some(min) = filter.min {
+            condition("COALESCE(t.amount, p.net) >= ?", SqlValue::from(min as f64));
+        }
+        if let Some(max) = filter.max {
+            condition("COALESCE(t.amount, p.net) <= ?", SqlValue::from(max as f64));
+        }
+        sql += &format!(" ORDER BY bm25({}, 0, 0, 4, 2, 1), row_id DESC LIMIT {}", SEARCH_KEY, filter.limit);
+        
+        self.query(&sql, params_from_iter(values), |r| Some(SearchHit::from_row(r)))
+    }
+    
+    // Changes of the last batches not undone yet, newest first. What undo would revert.
+    pub fn get_last_batches(&self, count: usize) -> Result<Vec<AuditEntry>, Error> {
+        let sql = format!("SELECT * FROM {0} WHERE undone = 0 AND batch IN \
+            (SELECT DISTINCT batch FROM {0} WHERE undone = 0 ORDER BY batch DESC LIMIT ?1) ORDER BY id DESC", AUDIT_KEY);
+        self.query(&sql, [count as i64], |r| Some(AuditEntry::from_row(r)))
+    }
+    
+    // Reverts the last batches all or nothing. Rows changed outside the audit since then stop it.
+    pub fn undo(&self, count: usize) -> Result<Vec<AuditEntry>, Error> {
+        log::trace!("Undoing the last {} batches of {}", count, self.name);
+        
+        let entries = self.get_last_batches(count)?;
+        let transaction = self.connection.unchecked_transaction()?;
+        
+        let sql = format!("UPDATE {} SET undone = 1 WHERE id = ?1", AUDIT_KEY);
+        for entry in &entries {
+            self.revert(entry)?;
+            self.connection.execute(&sql, [entry._id])?;
+        }
+        
+        transaction.commit()?;
+        Ok(entries)
+    }
+    
+    fn revert(&self, entry: &AuditEntry) -> Result<(), Error> {
+        if !audit::is_audited(&entry.entity) {
+            return Err(constraint_error(format!("Unknown entity [{}] in the audit", entry.entity)));
+        }
+        
+        let current = self.row_values(&entry.entity, entry.entity_id)?.map(|v| v.to_string());
+        if current != entry.new_values {
+            return Err(constraint_error(format!("{} {} changed after batch {}, it can not be undone", entry.entity, entry.entity_id, entry.batch)));
+        }
+        
+        let old = audit::parse_object(&entry.old_values);
+        let columns: Vec<String> = old.keys().map(|k| format!("\"{}\"", k.replace('"', "\"\""))).collect();
+        let mut values: Vec<_> = old.values().map(audit::sql_value).collect();
+        
+        let sql = match entry.action {
+            Action::Create => {
+                values = vec![rusqlite::types::Value::Integer(entry.entity_id as i64)];
+                format!("DELETE FROM {} WHERE id = ?1", entry.entity)
+            },
+            Action::Update => {
+                let set: Vec<String> = columns.iter().enumerate().map(|(i, c)| format!("{} = ?{}", c, i + 1)).collect();
+                values.push(rusqlite::types::Value::Integer(entry.entity_id as i64));
+                format!("UPDATE {} SET {} WHERE id = ?{}", entry.entity, set.join(", "), values.len())
+            },
+            Action::Delete => {
+                let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("?{}", i)).collect();
+                format!("INSERT INTO {} ({}) VALUES ({})", entry.entity, columns.join(", "), placeholders.join(", "))
+            },
+        };
+        
+        log::trace!("Reverting {} {} {}", entry.action, entry.entity, entry.entity_id);
+        self.connection.execute(&sql, params_from_iter(values))?;
+        Ok(())
     }
     
     pub fn get_tag_str(&self, tag_id: i32) -> Result<String, Error> {
@@ -200,10 +859,10 @@ impl Db {
     pub fn get_account(&self, name: &str) -> Result<Account, Error> {
         log::trace!("Get account for: {}", name);
         
-        let sql = format!("SELECT * FROM {} WHERE name = '{}'", ACCOUNTS_KEY, name);
+        let sql = format!("SELECT * FROM {} WHERE name = ?1", ACCOUNTS_KEY);
         log::trace!("Executing sql: {}", sql);
         
-        let data: Vec<Account> = self.query(&sql, [], |r| Some(Account::from_row(r)))?;
+        let data: Vec<Account> = self.query(&sql, [name], |r| Some(Account::from_row(r)))?;
         
         if data.len() == 0 {
             return Err(Error::QueryReturnedNoRows);
@@ -240,10 +899,10 @@ impl Db {
     fn get_name_id(&self, table: &str, name: &str) -> Result<i32, Error> {
         log::trace!("Get id for name: {}", name);
 
-        let sql = format!("SELECT * FROM {} WHERE name = '{}'", table, name);
+        let sql = format!("SELECT * FROM {} WHERE name = ?1", table);
         log::trace!("Executing sql: {}", sql);
 
-        let ids: Vec<i32> = self.query(&sql, [], |r| {
+        let ids: Vec<i32> = self.query(&sql, [name], |r| {
             if let Ok(v) = r.get(0) {
                 return Some(v);
             }
@@ -279,8 +938,11 @@ impl Db {
         Ok(ret)
     }
     
-    pub fn edit_account(&self, _name: &str, _amount: f32) -> Result<usize, Error> {
-        todo!("Edit account amount is still not implemented")
+    pub fn get_account_by_id(&self, id: i32) -> Result<Account, Error> {
+        let sql = format!("SELECT * FROM {} WHERE id = ?1", ACCOUNTS_KEY);
+        let mut data = self.query(&sql, [id], |r| Some(Account::from_row(r)))?;
+        
+        data.pop().ok_or(Error::QueryReturnedNoRows)
     }
     
     pub fn get_all_accounts(&self) -> Result<Vec<Account>, Error> {
@@ -328,7 +990,7 @@ impl Db {
         
         if year == None && month != None {
             log::error!("Error getting payrolls data. Year is None but month is not. Available combinations are (all none), (year and none month) or (year and month)");
-            std::process::exit(0);
+            return Err(Error::InvalidParameterName(String::from("month")));
         }
 
         let mut sql = format!("SELECT * FROM {}", PAYROLLS_KEY);
@@ -342,7 +1004,7 @@ impl Db {
             None => (),
         }
 
-        sql += " ORDER BY date ASC";
+        sql += " ORDER BY date ASC, id ASC";
 
         log::trace!("Executing sql: {}", sql);
         
@@ -351,12 +1013,51 @@ impl Db {
         Ok(ret)
     }
     
+    pub fn get_payroll(&self, id: i32) -> Result<Payroll, Error> {
+        let sql = format!("SELECT * FROM {} WHERE id = ?1", PAYROLLS_KEY);
+        let mut data = self.query(&sql, [id], |r| Some(Payroll::from_row(r)))?;
+        
+        data.pop().ok_or(Error::QueryReturnedNoRows)
+    }
+    
+    pub fn get_payroll_items(&self, payroll_id: i32) -> Result<Vec<PayrollItem>, Error> {
+        let sql = format!("SELECT * FROM {} WHERE payroll_id = ?1 ORDER BY id ASC", PAYROLL_ITEMS_KEY);
+        self.query(&sql, [payroll_id], |r| Some(PayrollItem::from_row(r)))
+    }
+    
+    // Items of the payrolls get_payroll_data would return for the same filters.
+    pub fn get_payroll_items_data(&self, year: Option<u32>, month: Option<u32>) -> Result<Vec<PayrollItem>, Error> {
+        log::trace!("Getting payroll items data");
+        
+        let mut sql = format!("SELECT i.* FROM {} i JOIN {} p ON p.id = i.payroll_id", PAYROLL_ITEMS_KEY, PAYROLLS_KEY);
+        if let Some(y) = year {
+            sql += &format!(" WHERE strftime('%Y', p.date) = '{:04}'", y);
+            
+            if let Some(m) = month {
+                sql += &format!(" AND strftime('%m', p.date) = '{:02}'", m);
+            }
+        }
+        
+        sql += " ORDER BY p.date ASC, i.id ASC";
+        
+        self.query(&sql, [], |r| Some(PayrollItem::from_row(r)))
+    }
+    
+    pub fn get_company_payrolls(&self, company_id: i32) -> Result<Vec<Payroll>, Error> {
+        log::trace!("Getting payrolls for company {}", company_id);
+        
+        let sql = format!("SELECT * FROM {} WHERE company_id = ?1 ORDER BY date ASC, id ASC", PAYROLLS_KEY);
+        let ret = self.query(&sql, [company_id], |r| Some(Payroll::from_row(r)))?;
+        
+        Ok(ret)
+    }
+    
     pub fn get_transaction_data(&self, year: Option<u32>, month: Option<u32>) -> Result<Vec<Transaction>, Error> {
         log::trace!("Getting transactions data");
 
         if year == None && month != None {
             log::error!("Error getting transactions data. Year is None but month is not. Available combinations are (all none), (year and none month) or (year and month)");
-            std::process::exit(0);
+            return Err(Error::InvalidParameterName(String::from("month")));
         }
 
         let mut sql = format!("SELECT * FROM {}", TRANSACTIONS_KEY);
@@ -370,7 +1071,7 @@ impl Db {
             None => (),
         }
         
-        sql += " ORDER BY date ASC";
+        sql += " ORDER BY date ASC, id ASC";
 
         log::trace!("Executing sql: {}", sql);
 
@@ -379,13 +1080,64 @@ impl Db {
         Ok(ret)
     }
 
-    pub fn get_payroll_data_range(&self, _start_year: u32, _start_month: u32, _end_year: u32, _end_month: u32) -> Result<Vec<Payroll>, Error> {
-        // TODO: Maybe can use a struct to wrap date ranges. Will probably be handy for other fetch
-        todo!()
+    pub fn get_last_transaction(&self) -> Result<Transaction, Error> {
+        let sql = format!("SELECT * FROM {} ORDER BY id DESC LIMIT 1", TRANSACTIONS_KEY);
+        let mut data = self.query(&sql, [], |r| Some(Transaction::from_row(r)))?;
+        
+        data.pop().ok_or(Error::QueryReturnedNoRows)
+    }
+}
+
+fn constraint_error(message: String) -> Error {
+    Error::SqliteFailure(ffi::Error::new(ffi::SQLITE_CONSTRAINT), Some(message))
+}
+
+fn check_copy(copy: &Connection) -> Result<i64, LoadError> {
+    let problems: Vec<String> = copy.prepare("PRAGMA integrity_check")?
+        .query_map([], |r| r.get(0))?
+        .collect::<Result<_, _>>()?;
+    if problems.iter().any(|p| p != "ok") {
+        return Err(LoadError::Corrupt(format!("integrity check failed: {}", problems.join("; "))));
+    }
+    
+    let version: i64 = copy.query_row("PRAGMA user_version", [], |r| r.get(0))?;
+    if version > SCHEMA_VERSION {
+        return Err(LoadError::Incompatible(format!("schema version {} is newer than the supported {}", version, SCHEMA_VERSION)));
     }
     
-    pub fn get_transaction_data_range(&self, _start_year: u32, _start_month: u32, _end_year: u32, _end_month: u32) -> Result<Vec<Transaction>, Error> {
-        // TODO: Maybe can use a struct to wrap date ranges. Will probably be handy for other fetch
-        todo!()
+    for table in [TAGS_KEY, COMPANIES_KEY, CATEGORIES_KEY, TRANSACTIONS_KEY, PAYROLLS_KEY, ACCOUNTS_KEY] {
+        let found: i64 = copy.query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1", [table], |r| r.get(0))?;
+        if found == 0 {
+            return Err(LoadError::Incompatible(format!("missing table {}, not a GitGud-Greedy database", table)));
+        }
     }
-}
\ No newline at end of file
+    
+    Ok(version)
+}
+
+// Backup api the other way around. Backup::new wants the destination as &mut, which the Db never has.
+fn copy_into(from: &Connection, to: &Connection) -> Result<(), LoadError> {
+    to.flush_prepared_statement_cache();
+    let main = b"main\0".as_ptr() as *const std::os::raw::c_char;
+    unsafe {
+        let backup = ffi::sqlite3_backup_init(to.handle(), main, from.handle(), main);
+        if backup.is_null() {
+            return Err(LoadError::Sqlite(Error::SqliteFailure(ffi::Error::new(ffi::sqlite3_errcode(to.handle())), None)));
+        }
+        let step = ffi::sqlite3_backup_step(backup, -1);
+        let finish = ffi::sqlite3_backup_finish(backup);
+        if step != ffi::SQLITE_DONE || finish != ffi::SQLITE_OK {
+            let rc = if step != ffi::SQLITE_DONE { step } else { finish };
+            return Err(LoadError::Sqlite(Error::SqliteFailure(ffi::Error::new(rc), None)));
+        }
+    }
+    Ok(())
+}
+
+impl Drop for Db {
+    fn drop(&mut self) {
+        if let Err(e) = self.save() {
+            log::error!("Error saving db [{}]: {}", self.name, e);
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use rusqlite::Row;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Payroll {
     pub _id: i32,
     pub date: String,
@@ -10,6 +10,8 @@ pub struct Payroll {
     pub irpf: f32,
     pub company_id: i32,
     pub category_id: i32,
+    // Deductions that are neither SS nor IRPF (advances, in-kind benefits...), so gross - ss - irpf - other = net.
+    pub other: f32,
 }
 
 impl Payroll {
@@ -23,6 +25,7 @@ impl Payroll {
             irpf,
             company_id,
             category_id,
+            other: 0.0,
         }
     }
     
@@ -35,7 +38,8 @@ impl Payroll {
             ss: r.get_unwrap(4),
             irpf: r.get_unwrap(5),
             company_id: r.get_unwrap(6),
-            category_id: r.get_unwrap(7)
+            category_id: r.get_unwrap(7),
+            other: r.get(8).unwrap_or(0.0),
         }
     }
 }
\ No newline at end of file
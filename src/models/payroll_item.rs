@@ -0,0 +1,72 @@
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+use rusqlite::Row;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ItemKind {
+    // Part of the gross: base salary, complements, overtime, paga extra...
+    Earning,
+    // Subtracted from the gross besides SS and IRPF: advances, pension contributions, in-kind benefits...
+    Deduction,
+}
+
+impl FromStr for ItemKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "earning" | "e" => Ok(ItemKind::Earning),
+            "deduction" | "d" => Ok(ItemKind::Deduction),
+            _ => Err(format!("Invalid item type [{}]. Available: earning, deduction", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ItemKind::Earning => write!(f, "earning"),
+            ItemKind::Deduction => write!(f, "deduction"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PayrollItem {
+    pub _id: i32,
+    pub payroll_id: i32,
+    pub concept: String,
+    pub kind: ItemKind,
+    pub amount: f32,
+    pub irpf_taxable: bool,
+    pub ss_taxable: bool,
+}
+
+impl PayrollItem {
+    pub fn new(payroll_id: i32, concept: &str, kind: ItemKind, amount: f32, irpf_taxable: bool, ss_taxable: bool) -> Self {
+        Self {
+            _id: 0,
+            payroll_id,
+            concept: String::from(concept),
+            kind,
+            amount,
+            irpf_taxable,
+            ss_taxable,
+        }
+    }
+    
+    pub fn from_row(r: &Row) -> Self {
+        let kind: String = r.get_unwrap(3);
+        
+        Self {
+            _id: r.get_unwrap(0),
+            payroll_id: r.get_unwrap(1),
+            concept: r.get_unwrap(2),
+            kind: kind.parse().unwrap_or(ItemKind::Earning),
+            amount: r.get_unwrap(4),
+            irpf_taxable: r.get_unwrap(5),
+            ss_taxable: r.get_unwrap(6),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use rusqlite::Row;
+
+// Rows the index holds, by the name the commands take.
+pub const KINDS: [&str; 2] = ["transaction", "payroll"];
+
+// Everything a search can be narrowed by besides the words. Dates are YYYY-MM-DD and both ends of
+// every range are included. Amounts are the transaction amount or the payroll net.
+#[derive(Debug, Clone)]
+pub struct SearchFilter {
+    pub kind: Option<String>,
+    pub year: Option<u32>,
+    pub month: Option<u32>,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+    pub limit: usize,
+}
+
+impl Default for SearchFilter {
+    fn default() -> Self {
+        Self {
+            kind: None,
+            year: None,
+            month: None,
+            from: None,
+            to: None,
+            min: None,
+            max: None,
+            limit: 50,
+        }
+    }
+}
+
+// A matching transaction or payroll. Text fields come with the matched words between the markers
+// given to the search. For payrolls the name is the company and the names the category.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub kind: String,
+    pub id: i32,
+    pub date: String,
+    pub amount: f32,
+    pub name: Option<String>,
+    pub notes: Option<String>,
+    // Tag of a transaction, category of a payroll.
+    pub names: Option<String>,
+    // Higher is a better match.
+    pub score: f64,
+}
+
+impl SearchHit {
+    pub fn from_row(r: &Row) -> Self {
+        let bm25: f64 = r.get_unwrap(7);
+
+        Self {
+            kind: r.get_unwrap(0),
+            id: r.get_unwrap(1),
+            date: r.get(2).unwrap_or_default(),
+            amount: r.get(3).unwrap_or_default(),
+            name: r.get_unwrap(4),
+            notes: r.get_unwrap(5),
+            names: r.get_unwrap(6),
+            score: -bm25,
+        }
+    }
+}
+
+// Turns what the user typed into an fts5 query: every word must appear, as a prefix so `amaz`
+// finds Amazon, and quoted so `-`, `:` or `*` in it are not fts5 syntax.
+pub fn match_query(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Kind for `transaction`, `transactions`, `Payroll`...
+pub fn kind(name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase();
+    KINDS.iter().find(|k| name.trim_end_matches('s') == **k).copied()
+}
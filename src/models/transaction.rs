@@ -1,12 +1,14 @@
 use rusqlite::Row;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Transaction {
     pub _id: i32,
     pub name: String,
     pub date: String,
     pub amount: f32,
     pub tag_id: i32,
+    // Free text, also where imports keep the bank description.
+    pub notes: Option<String>,
 }
 
 impl Transaction {
@@ -16,7 +18,8 @@ impl Transaction {
             name: name.clone(), 
             date: date.clone(), 
             amount, 
-            tag_id
+            tag_id,
+            notes: None,
         }
     }
 
@@ -27,6 +30,7 @@ impl Transaction {
             date: r.get_unwrap(2),
             amount: r.get_unwrap(3),
             tag_id: r.get_unwrap(4),
+            notes: r.get(5).unwrap_or(None),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,215 @@
+use chrono::NaiveDate;
+
+use crate::models::Db;
+use crate::models::payroll::Payroll;
+use crate::models::payroll_item::{PayrollItem, ItemKind};
+
+// Rates further than this from the company average (in percentage points) are reported.
+const IRPF_RATE_MARGIN: f32 = 5.0;
+const SS_RATE_MARGIN: f32 = 2.0;
+// Rates above these are very unlikely for a Spanish payslip.
+const MAX_IRPF_RATE: f32 = 47.0;
+const MAX_SS_RATE: f32 = 10.0;
+
+pub struct PayrollValidator {
+    // Allowed difference between gross - ss - irpf - other and net.
+    pub tolerance: f32,
+    // Store a positive difference as other deductions instead of rejecting the payroll.
+    pub record_other: bool,
+}
+
+impl PayrollValidator {
+    // Normalizes the payroll (date format, other deductions) and returns the warnings, or the errors if it must be rejected.
+    pub fn validate(&self, db: &Db, payroll: &mut Payroll) -> Result<Vec<String>, Vec<String>> {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        
+        let date = payroll.date.trim().replace('/', "-");
+        match NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+            Ok(d) => payroll.date = d.format("%Y-%m-%d").to_string(),
+            Err(e) => errors.push(format!("Invalid date [{}], expected YYYY-MM-DD. E: {}", payroll.date, e)),
+        }
+        
+        for (name, value) in [("gross", payroll.gross), ("net", payroll.net), ("ss", payroll.ss), ("irpf", payroll.irpf), ("other", payroll.other)] {
+            if value < 0.0 || !value.is_finite() {
+                errors.push(format!("Amount {} must be a non-negative number, got {}", name, value));
+            }
+        }
+        
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        
+        let gap = payroll.gross - payroll.ss - payroll.irpf - payroll.other - payroll.net;
+        if gap.abs() > self.tolerance {
+            if self.record_other && gap > 0.0 {
+                payroll.other += gap;
+                warnings.push(format!("Recorded {:.2} as other deductions (gross - ss - irpf does not match net)", gap));
+            } else {
+                errors.push(format!("Gross - ss - irpf - other ({:.2}) does not match net ({:.2}), difference {:.2}",
+                                    payroll.gross - payroll.ss - payroll.irpf - payroll.other, payroll.net, gap));
+            }
+        }
+        
+        if payroll.gross > 0.0 {
+            let (irpf_rate, ss_rate) = rates(payroll);
+            
+            if irpf_rate > MAX_IRPF_RATE {
+                warnings.push(format!("IRPF rate {:.2}% is above {}%", irpf_rate, MAX_IRPF_RATE));
+            }
+            
+            if ss_rate > MAX_SS_RATE {
+                warnings.push(format!("SS rate {:.2}% is above {}%", ss_rate, MAX_SS_RATE));
+            }
+            
+            warnings.extend(self.compare_history(db, payroll, irpf_rate, ss_rate));
+        }
+        
+        if errors.is_empty() { Ok(warnings) } else { Err(errors) }
+    }
+    
+    // Earning items must add up to the gross and deduction items to the other deductions, once there are any of each.
+    pub fn validate_items(&self, payroll: &Payroll, items: &[PayrollItem]) -> Vec<String> {
+        let mut issues = Vec::new();
+        let sum = |kind: ItemKind| items.iter().filter(|i| i.kind == kind).map(|i| i.amount).sum::<f32>();
+        let any = |kind: ItemKind| items.iter().any(|i| i.kind == kind);
+        
+        let earnings = sum(ItemKind::Earning);
+        if any(ItemKind::Earning) && (earnings - payroll.gross).abs() > self.tolerance {
+            issues.push(format!("Earning items add up to {:.2} but gross is {:.2} (difference {:.2})", earnings, payroll.gross, payroll.gross - earnings));
+        }
+        
+        let deductions = sum(ItemKind::Deduction);
+        if any(ItemKind::Deduction) && (deductions - payroll.other).abs() > self.tolerance {
+            issues.push(format!("Deduction items add up to {:.2} but other deductions are {:.2} (difference {:.2})", deductions, payroll.other, payroll.other - deductions));
+        }
+        
+        issues
+    }
+    
+    fn compare_history(&self, db: &Db, payroll: &Payroll, irpf_rate: f32, ss_rate: f32) -> Vec<String> {
+        let history = db.get_company_payrolls(payroll.company_id).unwrap_or_else(|e| {
+            log::warn!("Could not get previous payrolls for company {}: {}", payroll.company_id, e);
+            Vec::new()
+        });
+        
+        let previous: Vec<(f32, f32)> = history.iter()
+            .filter(|p| p.date < payroll.date && p.gross > 0.0)
+            .map(rates)
+            .collect();
+        
+        if previous.is_empty() {
+            return Vec::new();
+        }
+        
+        let count = previous.len() as f32;
+        let irpf_avg = previous.iter().map(|(i, _)| i).sum::<f32>() / count;
+        let ss_avg = previous.iter().map(|(_, s)| s).sum::<f32>() / count;
+        
+        let mut warnings = Vec::new();
+        if (irpf_rate - irpf_avg).abs() > IRPF_RATE_MARGIN {
+            warnings.push(format!("IRPF rate {:.2}% differs from this company's previous payslips ({:.2}%)", irpf_rate, irpf_avg));
+        }
+        
+        if (ss_rate - ss_avg).abs() > SS_RATE_MARGIN {
+            warnings.push(format!("SS rate {:.2}% differs from this company's previous payslips ({:.2}%)", ss_rate, ss_avg));
+        }
+        
+        warnings
+    }
+}
+
+fn rates(payroll: &Payroll) -> (f32, f32) {
+    (payroll.irpf / payroll.gross * 100.0, payroll.ss / payroll.gross * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db() -> Db {
+        let db = Db::load(":memory:").unwrap();
+        db.insert_company("ACME", "").unwrap();
+        db.insert_category("Salary", "").unwrap();
+        db
+    }
+
+    fn payroll(date: &str, net: f32, irpf: f32) -> Payroll {
+        Payroll::new(&String::from(date), 2000.0, net, 100.0, irpf, 1, 1)
+    }
+
+    #[test]
+    fn gaps_up_to_the_tolerance_are_accepted() {
+        let db = db();
+        let strict = PayrollValidator { tolerance: 0.5, record_other: false };
+
+        assert_eq!(strict.validate(&db, &mut payroll("2022-01-31", 1499.5, 400.0)), Ok(vec![]));
+        assert!(strict.validate(&db, &mut payroll("2022-01-31", 1499.0, 400.0)).is_err());
+        // A net above what the amounts allow is never recorded as other deductions.
+        let lenient = PayrollValidator { tolerance: 0.5, record_other: true };
+        assert!(lenient.validate(&db, &mut payroll("2022-01-31", 1501.0, 400.0)).is_err());
+
+        let mut short = payroll("2022-01-31", 1499.0, 400.0);
+        assert_eq!(lenient.validate(&db, &mut short).unwrap().len(), 1);
+        assert_eq!(short.other, 1.0);
+    }
+
+    #[test]
+    fn dates_and_amounts_are_checked() {
+        let db = db();
+        let validator = PayrollValidator { tolerance: 0.01, record_other: false };
+
+        let mut slashes = payroll("2022/1/31", 1500.0, 400.0);
+        assert!(validator.validate(&db, &mut slashes).is_ok());
+        assert_eq!(slashes.date, "2022-01-31");
+
+        assert!(validator.validate(&db, &mut payroll("31-01-2022", 1500.0, 400.0)).is_err());
+        let errors = validator.validate(&db, &mut payroll("2022-13-01", 1500.0, -400.0)).unwrap_err();
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn unusual_rates_are_warned_about() {
+        let db = db();
+        let validator = PayrollValidator { tolerance: 0.01, record_other: false };
+
+        let warnings = validator.validate(&db, &mut payroll("2022-01-31", 900.0, 1000.0)).unwrap();
+        assert!(warnings[0].starts_with("IRPF rate 50.00% is above"));
+
+        // Compared with earlier payslips of the same company only.
+        db.insert_payroll(&payroll("2022-01-31", 1500.0, 400.0)).unwrap();
+        let warnings = validator.validate(&db, &mut payroll("2022-02-28", 1300.0, 600.0)).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("previous payslips (20.00%)"));
+        assert!(validator.validate(&db, &mut payroll("2021-12-31", 1300.0, 600.0)).unwrap().is_empty());
+    }
+
+    fn item(kind: ItemKind, amount: f32) -> PayrollItem {
+        PayrollItem::new(1, "Concept", kind, amount, true, true)
+    }
+
+    #[test]
+    fn earnings_must_add_up_to_the_gross() {
+        let validator = PayrollValidator { tolerance: 0.5, record_other: false };
+        let payroll = payroll("2022-01-31", 1500.0, 400.0);
+
+        assert!(validator.validate_items(&payroll, &[]).is_empty());
+        assert!(validator.validate_items(&payroll, &[item(ItemKind::Earning, 1800.0), item(ItemKind::Earning, 199.6)]).is_empty());
+
+        let issues = validator.validate_items(&payroll, &[item(ItemKind::Earning, 1800.0)]);
+        assert_eq!(issues, vec!["Earning items add up to 1800.00 but gross is 2000.00 (difference 200.00)"]);
+    }
+
+    #[test]
+    fn deductions_must_add_up_to_the_other_deductions() {
+        let validator = PayrollValidator { tolerance: 0.01, record_other: false };
+        let mut payroll = payroll("2022-01-31", 1450.0, 400.0);
+        payroll.other = 50.0;
+
+        // Only the kinds that have items are checked.
+        assert!(validator.validate_items(&payroll, &[item(ItemKind::Deduction, 50.0)]).is_empty());
+
+        let issues = validator.validate_items(&payroll, &[item(ItemKind::Earning, 2000.0), item(ItemKind::Deduction, 30.0)]);
+        assert_eq!(issues, vec!["Deduction items add up to 30.00 but other deductions are 50.00 (difference 20.00)"]);
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt::Write;
+
+use crate::renderer::{Report, Value, Content};
+use crate::commons::{escape, XML_ESCAPES};
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em auto; max-width: 900px; color: #222; }
+h1 { border-bottom: 2px solid #4e79a7; padding-bottom: 0.2em; }
+h2 { margin-top: 1.6em; color: #4e79a7; }
+table { border-collapse: collapse; margin: 0.5em 0; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.7em; }
+th { background: #eef2f7; text-align: left; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+td.neg { color: #c0392b; }
+svg { max-width: 100%; height: auto; }
+";
+
+// A single offline page: styles are inline and charts are embedded svg elements.
+pub fn render_page(title: &str, report: &Report, charts: &[String]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>", escape(title, &XML_ESCAPES), STYLE);
+    let _ = writeln!(out, "<h1>{}</h1>", escape(title, &XML_ESCAPES));
+
+    for s in report.sections() {
+        let _ = writeln!(out, "<h2>{}</h2>", escape(&s.title, &XML_ESCAPES));
+
+        match &s.content {
+            Content::Table { header, rows } => {
+                let _ = writeln!(out, "<table>\n<tr>{}</tr>", header.iter().map(|h| format!("<th>{}</th>", escape(h, &XML_ESCAPES))).collect::<String>());
+                for r in rows {
+                    let _ = writeln!(out, "<tr>{}</tr>", r.iter().map(cell).collect::<String>());
+                }
+                let _ = writeln!(out, "</table>");
+            },
+            Content::Summary(values) => {
+                let _ = writeln!(out, "<table>");
+                for (k, v) in values {
+                    let _ = writeln!(out, "<tr><th>{}</th>{}</tr>", escape(k, &XML_ESCAPES), cell(v));
+                }
+                let _ = writeln!(out, "</table>");
+            },
+        }
+    }
+
+    if !charts.is_empty() {
+        let _ = writeln!(out, "<h2>Charts</h2>");
+        for c in charts {
+            let _ = writeln!(out, "<div>\n{}</div>", c);
+        }
+    }
+
+    let _ = writeln!(out, "</body>\n</html>");
+    out
+}
+
+fn cell(value: &Value) -> String {
+    let mut class = Vec::new();
+    if value.is_numeric() {
+        class.push("num");
+    }
+    if value.is_negative() {
+        class.push("neg");
+    }
+
+    if class.is_empty() {
+        format!("<td>{}</td>", escape(&value.to_plain(), &XML_ESCAPES))
+    } else {
+        format!("<td class=\"{}\">{}</td>", class.join(" "), escape(&value.to_plain(), &XML_ESCAPES))
+    }
+}
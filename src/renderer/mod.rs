@@ -0,0 +1,381 @@
+pub use report::{Report, Value, Content, Section};
+
+use std::str::FromStr;
+use std::fmt::Write;
+use std::io::IsTerminal;
+
+use comfy_table::{Table, Row, ContentArrangement, Cell, Attribute, Color, CellAlignment};
+use comfy_table::presets::UTF8_FULL;
+use comfy_table::modifiers::UTF8_ROUND_CORNERS;
+use serde_json::{Map, Value as JsonValue};
+
+mod report;
+pub mod html;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            _ => Err(format!("Invalid output format [{}]. Available: table, json, csv, markdown", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Table => write!(f, "table"),
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Csv => write!(f, "csv"),
+            OutputFormat::Markdown => write!(f, "markdown"),
+        }
+    }
+}
+
+// How numbers and dates are shown to people. Json and csv keep the plain values so they can be parsed back.
+#[derive(Debug, Clone, Default)]
+pub struct Formats {
+    pub decimal_separator: Option<char>,
+    pub thousands_separator: Option<char>,
+    pub date_format: Option<String>,
+}
+
+impl Formats {
+    pub fn apply(&self, value: &Value) -> String {
+        match value {
+            Value::Date(d) => match &self.date_format {
+                Some(f) => chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").map(|d| d.format(f).to_string()).unwrap_or_else(|_| d.clone()),
+                None => d.clone(),
+            },
+            // Ints are ids and counts, grouping them would turn id 1234 into 1.234.
+            Value::Int(_) => value.to_plain(),
+            Value::Number(_) | Value::Percent(_) => self.number(&value.to_plain()),
+            Value::Text(t) => t.clone(),
+        }
+    }
+    
+    fn number(&self, plain: &str) -> String {
+        if self.decimal_separator.is_none() && self.thousands_separator.is_none() {
+            return String::from(plain);
+        }
+        
+        let (sign, digits) = match plain.strip_prefix('-') {
+            Some(d) => ("-", d),
+            None => ("", plain),
+        };
+        let (int, rest) = match digits.find(|c: char| !c.is_ascii_digit()) {
+            Some(i) => digits.split_at(i),
+            None => (digits, ""),
+        };
+        
+        let mut grouped = String::new();
+        for (i, c) in int.chars().enumerate() {
+            if i > 0 && (int.len() - i) % 3 == 0 {
+                if let Some(t) = self.thousands_separator {
+                    grouped.push(t);
+                }
+            }
+            grouped.push(c);
+        }
+        
+        let rest = match self.decimal_separator {
+            Some(d) => rest.replacen('.', &d.to_string(), 1),
+            None => String::from(rest),
+        };
+        
+        format!("{}{}{}", sign, grouped, rest)
+    }
+}
+
+pub struct Renderer {
+    format: OutputFormat,
+    color: bool,
+    width: Option<u16>,
+    formats: Formats,
+}
+
+impl Renderer {
+    pub fn new(format: OutputFormat, no_color: bool) -> Self {
+        let tty = std::io::stdout().is_terminal();
+
+        Self {
+            format,
+            color: tty && !no_color && !no_color_env(),
+            width: terminal_width(tty),
+            formats: Formats::default(),
+        }
+    }
+    
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    pub fn color(&self) -> bool {
+        self.color
+    }
+
+    pub fn width(&self) -> Option<u16> {
+        self.width
+    }
+
+    pub fn print(&self, report: &Report) {
+        print!("{}", self.render(report));
+    }
+
+    pub fn render(&self, report: &Report) -> String {
+        match self.format {
+            OutputFormat::Table => self.render_table(report),
+            OutputFormat::Json => render_json(report),
+            OutputFormat::Csv => render_csv(report),
+            OutputFormat::Markdown => self.render_markdown(report),
+        }
+    }
+
+    fn render_table(&self, report: &Report) -> String {
+        let mut out = String::new();
+
+        for s in report.sections() {
+            let table = match &s.content {
+                Content::Table { header, rows } => {
+                    let mut table = self.create_table();
+                    let header: Vec<Cell> = header.iter().map(|h| Cell::new(h).add_attribute(Attribute::Bold).fg(Color::Green)).collect();
+                    table.set_header(Row::from(header));
+
+                    for r in rows {
+                        table.add_row(r.iter().map(|v| self.cell(v)).collect::<Vec<Cell>>());
+                    }
+
+                    table
+                },
+                Content::Summary(values) => {
+                    let mut table = self.create_table();
+
+                    for (k, v) in values {
+                        table.add_row(vec![Cell::new(k).add_attribute(Attribute::Bold), self.cell(v)]);
+                    }
+
+                    table
+                },
+            };
+
+            if self.color {
+                let _ = writeln!(out, "\x1b[1m{}:\x1b[0m\n{}", s.title, table);
+            } else {
+                let _ = writeln!(out, "{}:\n{}", s.title, table);
+            }
+        }
+
+        out
+    }
+
+    fn render_markdown(&self, report: &Report) -> String {
+        let mut out = String::new();
+
+        for s in report.sections() {
+            let _ = writeln!(out, "### {}\n", s.title);
+
+            match &s.content {
+                Content::Table { header, rows } => {
+                    let _ = writeln!(out, "| {} |", header.iter().map(|h| markdown_escape(h)).collect::<Vec<String>>().join(" | "));
+
+                    let align: Vec<&str> = (0..header.len()).map(|i| {
+                        match rows.first().and_then(|r| r.get(i)) {
+                            Some(v) if v.is_numeric() => "---:",
+                            _ => "---",
+                        }
+                    }).collect();
+                    let _ = writeln!(out, "|{}|", align.join("|"));
+
+                    for r in rows {
+                        let _ = writeln!(out, "| {} |", r.iter().map(|v| markdown_escape(&self.formats.apply(v))).collect::<Vec<String>>().join(" | "));
+                    }
+                },
+                Content::Summary(values) => {
+                    for (k, v) in values {
+                        let _ = writeln!(out, "- **{}**: {}", markdown_escape(k), markdown_escape(&self.formats.apply(v)));
+                    }
+                },
+            }
+
+            out.push('\n');
+        }
+
+        out
+    }
+
+    fn create_table(&self) -> Table {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS)
+            .set_content_arrangement(ContentArrangement::Dynamic);
+
+        // comfy-table only styles real terminals, so this also keeps escape codes out of pipes and files.
+        if !self.color {
+            table.force_no_tty();
+        }
+
+        if let Some(w) = self.width {
+            table.set_table_width(w);
+        }
+
+        table
+    }
+
+    fn cell(&self, value: &Value) -> Cell {
+        let mut cell = Cell::new(self.formats.apply(value));
+
+        if value.is_numeric() {
+            cell = cell.set_alignment(CellAlignment::Right);
+        }
+
+        if value.is_negative() {
+            cell = cell.fg(Color::Red);
+        }
+
+        cell
+    }
+}
+
+fn no_color_env() -> bool {
+    std::env::var("NO_COLOR").map(|v| !v.is_empty()).unwrap_or(false)
+}
+
+fn terminal_width(tty: bool) -> Option<u16> {
+    // Pseudo terminals without a size report a zero width, which would squash every table.
+    let from_tty = if tty { Table::new().get_table_width().filter(|w| *w > 0) } else { None };
+
+    from_tty.or_else(|| std::env::var("COLUMNS").ok().and_then(|c| c.parse::<u16>().ok()))
+}
+
+fn render_json(report: &Report) -> String {
+    let mut out = serde_json::to_string_pretty(&report_json(report)).unwrap_or_default();
+    out.push('\n');
+    out
+}
+
+// One key per section: tables become arrays of objects and summaries a single object.
+pub fn report_json(report: &Report) -> JsonValue {
+    let mut doc = Map::new();
+
+    for s in report.sections() {
+        let value = match &s.content {
+            Content::Table { header, rows } => {
+                let keys: Vec<String> = header.iter().map(|h| json_key(h)).collect();
+                JsonValue::Array(rows.iter().map(|r| {
+                    let obj: Map<String, JsonValue> = keys.iter().cloned().zip(r.iter().map(|v| v.to_json())).collect();
+                    JsonValue::Object(obj)
+                }).collect())
+            },
+            Content::Summary(values) => {
+                JsonValue::Object(values.iter().map(|(k, v)| (json_key(k), v.to_json())).collect())
+            },
+        };
+
+        doc.insert(s.key.clone(), value);
+    }
+
+    JsonValue::Object(doc)
+}
+
+fn render_csv(report: &Report) -> String {
+    let mut out = String::new();
+    let sections = report.sections();
+
+    for (i, s) in sections.iter().enumerate() {
+        if sections.len() > 1 {
+            if i > 0 {
+                out.push('\n');
+            }
+            let _ = writeln!(out, "# {}", s.title);
+        }
+
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        match &s.content {
+            Content::Table { header, rows } => {
+                let _ = writer.write_record(header);
+                for r in rows {
+                    let _ = writer.write_record(r.iter().map(|v| v.to_plain()));
+                }
+            },
+            Content::Summary(values) => {
+                let _ = writer.write_record(["Key", "Value"]);
+                for (k, v) in values {
+                    let _ = writer.write_record([k.clone(), v.to_plain()]);
+                }
+            },
+        }
+
+        let bytes = writer.into_inner().unwrap_or_default();
+        out += &String::from_utf8_lossy(&bytes);
+    }
+
+    out
+}
+
+fn json_key(header: &str) -> String {
+    // "Delta %" must not end up with the same key as "Delta".
+    let key: String = header.to_lowercase().replace('%', " pct").chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect();
+    key.split('_').filter(|p| !p.is_empty()).collect::<Vec<&str>>().join("_")
+}
+
+fn markdown_escape(value: &str) -> String {
+    value.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spanish() -> Formats {
+        Formats { decimal_separator: Some(','), thousands_separator: Some('.'), date_format: Some(String::from("%d/%m/%Y")) }
+    }
+
+    #[test]
+    fn numbers_are_grouped_with_their_sign() {
+        let formats = spanish();
+        assert_eq!(formats.number("1234567.89"), "1.234.567,89");
+        assert_eq!(formats.number("-1234.50"), "-1.234,50");
+        assert_eq!(formats.number("-123.00"), "-123,00");
+        assert_eq!(formats.number("999"), "999");
+        assert_eq!(formats.number("12.50%"), "12,50%");
+        assert_eq!(Formats::default().number("-1234.50"), "-1234.50");
+
+        let grouping = Formats { thousands_separator: Some(' '), ..Formats::default() };
+        assert_eq!(grouping.number("1234.50"), "1 234.50");
+    }
+
+    #[test]
+    fn values_use_the_formats() {
+        let formats = spanish();
+        assert_eq!(formats.apply(&Value::Number(-1234.5)), "-1.234,50");
+        assert_eq!(formats.apply(&Value::Percent(1234.5)), "1.234,50%");
+        assert_eq!(formats.apply(&Value::Int(1234)), "1234");
+        assert_eq!(formats.apply(&Value::date("2022-03-05")), "05/03/2022");
+        assert_eq!(formats.apply(&Value::date("not a date")), "not a date");
+        assert_eq!(formats.apply(&Value::from("1234.5")), "1234.5");
+    }
+
+    #[test]
+    fn json_keys_are_snake_case_and_distinct() {
+        assert_eq!(json_key("Net amount"), "net_amount");
+        assert_eq!(json_key("Tag / Category"), "tag_category");
+        assert_eq!(json_key("Delta"), "delta");
+        assert_eq!(json_key("Delta %"), "delta_pct");
+        assert_eq!(json_key("  Año (€) "), "año");
+    }
+}
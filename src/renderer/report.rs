@@ -0,0 +1,156 @@
+use serde_json::Value as JsonValue;
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Text(String),
+    // YYYY-MM-DD, shown with the configured date format.
+    Date(String),
+    Int(i64),
+    Number(f32),
+    Percent(f32),
+}
+
+impl Value {
+    pub fn text(value: &str) -> Self {
+        Value::Text(String::from(value))
+    }
+
+    pub fn date(value: &str) -> Self {
+        Value::Date(String::from(value))
+    }
+    
+    pub fn is_numeric(&self) -> bool {
+        !matches!(self, Value::Text(_) | Value::Date(_))
+    }
+
+    pub fn is_negative(&self) -> bool {
+        match self {
+            Value::Text(_) | Value::Date(_) => false,
+            Value::Int(i) => *i < 0,
+            Value::Number(n) | Value::Percent(n) => *n < -0.005,
+        }
+    }
+
+    pub fn to_plain(&self) -> String {
+        match self {
+            Value::Text(t) | Value::Date(t) => t.clone(),
+            Value::Int(i) => i.to_string(),
+            Value::Number(n) => format!("{:.2}", n),
+            Value::Percent(p) => format!("{:.2}%", p),
+        }
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        match self {
+            Value::Text(t) | Value::Date(t) => JsonValue::from(t.as_str()),
+            Value::Int(i) => JsonValue::from(*i),
+            Value::Number(n) | Value::Percent(n) => JsonValue::from((*n as f64 * 100.0).round() / 100.0),
+        }
+    }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::text(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(value: usize) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+pub enum Content {
+    Table {
+        header: Vec<String>,
+        rows: Vec<Vec<Value>>,
+    },
+    Summary(Vec<(String, Value)>),
+}
+
+pub struct Section {
+    pub key: String,
+    pub title: String,
+    pub content: Content,
+}
+
+// View models push their data here and the renderer decides how it ends up on screen or in a file.
+#[derive(Default)]
+pub struct Report {
+    sections: Vec<Section>,
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Self { sections: Vec::new() }
+    }
+
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    pub fn add_table(&mut self, key: &str, title: &str, header: Vec<&str>, rows: Vec<Vec<Value>>) {
+        self.push(Section {
+            key: String::from(key),
+            title: String::from(title),
+            content: Content::Table {
+                header: header.iter().map(|h| String::from(*h)).collect(),
+                rows,
+            },
+        });
+    }
+
+    pub fn add_summary(&mut self, key: &str, title: &str, values: Vec<(&str, Value)>) {
+        self.push(Section {
+            key: String::from(key),
+            title: String::from(title),
+            content: Content::Summary(values.into_iter().map(|(k, v)| (String::from(k), v)).collect()),
+        });
+    }
+
+    // Keys name the sections in json output, so a repeated one gets a `_2`, `_3`... suffix instead
+    // of replacing the earlier section.
+    fn push(&mut self, mut section: Section) {
+        let taken = |key: &str| self.sections.iter().any(|s| s.key == key);
+        if taken(&section.key) {
+            let n = (2..).find(|n| !taken(&format!("{}_{}", section.key, n))).unwrap_or(2);
+            section.key = format!("{}_{}", section.key, n);
+        }
+        self.sections.push(section);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_keys_get_a_suffix() {
+        let mut report = Report::new();
+        report.add_table("totals", "2021", vec!["Amount"], vec![]);
+        report.add_summary("totals", "2022", vec![]);
+        report.add_table("totals", "2023", vec!["Amount"], vec![]);
+
+        let keys: Vec<&str> = report.sections().iter().map(|s| s.key.as_str()).collect();
+        assert_eq!(keys, vec!["totals", "totals_2", "totals_3"]);
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::net::SocketAddr;
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value as JsonValue};
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::commons::DEFAULT_PAYROLL_TOLERANCE;
+use crate::models::Db;
+
+mod routes;
+
+// Larger bodies are rejected, nothing the api accepts comes close.
+const MAX_BODY: usize = 64 * 1024;
+
+// Json api over the same db the cli uses. The connection can not be shared between threads, so
+// requests are answered one at a time, which is plenty for a household.
+pub struct Server {
+    http: tiny_http::Server,
+    token: String,
+    payroll_tolerance: f32,
+}
+
+pub struct ApiError {
+    pub status: u16,
+    pub message: String,
+}
+
+pub type ApiResult = Result<(u16, JsonValue), ApiError>;
+
+impl ApiError {
+    pub fn new(status: u16, message: impl Into<String>) -> Self {
+        Self { status, message: message.into() }
+    }
+
+    pub fn not_found(what: &str) -> Self {
+        Self::new(404, format!("{} not found", what))
+    }
+}
+
+impl From<rusqlite::Error> for ApiError {
+    fn from(e: rusqlite::Error) -> Self {
+        match &e {
+            rusqlite::Error::QueryReturnedNoRows => ApiError::not_found("Resource"),
+            rusqlite::Error::InvalidParameterName(p) => ApiError::new(400, format!("Invalid parameter [{}]", p)),
+            rusqlite::Error::SqliteFailure(f, msg) if f.code == rusqlite::ErrorCode::ConstraintViolation => {
+                ApiError::new(409, msg.clone().unwrap_or_else(|| e.to_string()))
+            },
+            _ => ApiError::new(500, e.to_string()),
+        }
+    }
+}
+
+// What the handlers need from a request, already split and decoded.
+pub struct ApiRequest<'a> {
+    pub method: &'a Method,
+    pub segments: Vec<&'a str>,
+    pub query: HashMap<String, String>,
+    pub body: String,
+}
+
+impl ApiRequest<'_> {
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, ApiError> {
+        serde_json::from_str(&self.body).map_err(|e| ApiError::new(400, format!("Invalid body: {}", e)))
+    }
+
+    pub fn param<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>, ApiError> {
+        match self.query.get(name) {
+            None => Ok(None),
+            Some(v) => v.parse::<T>().map(Some).map_err(|_| ApiError::new(400, format!("Invalid value [{}] for {}", v, name))),
+        }
+    }
+}
+
+impl Server {
+    pub fn bind(addr: &str, token: &str) -> Result<Self, String> {
+        if token.is_empty() {
+            return Err(String::from("The server token can not be empty"));
+        }
+
+        let http = tiny_http::Server::http(addr).map_err(|e| e.to_string())?;
+        Ok(Self { http, token: String::from(token), payroll_tolerance: DEFAULT_PAYROLL_TOLERANCE })
+    }
+
+    // Allowed difference between the totals of the payrolls clients send.
+    pub fn with_payroll_tolerance(mut self, tolerance: f32) -> Self {
+        self.payroll_tolerance = tolerance;
+        self
+    }
+
+    pub fn addr(&self) -> Option<SocketAddr> {
+        self.http.server_addr().to_ip()
+    }
+
+    // Blocks answering requests until the process ends.
+    pub fn run(&self, db: &Db) {
+        for request in self.http.incoming_requests() {
+            self.answer(db, request);
+        }
+    }
+
+    fn answer(&self, db: &Db, mut request: Request) {
+        db.start_batch(&format!("serve {} {}", request.method(), request.url()));
+        let (mut status, mut body) = match self.handle(db, &mut request) {
+            Ok((status, body)) => (status, body),
+            Err(e) => (e.status, json!({ "error": e.message })),
+        };
+
+        // Encrypted dbs are only written back on save, and the server usually ends killed. A change
+        // that could not be written is not reported as done.
+        if let Err(e) = db.save() {
+            log::error!("Error saving db [{}]: {}", db.get_name(), e);
+            status = 500;
+            body = json!({ "error": format!("Error saving the database: {}", e) });
+        }
+        log::info!("{} {} -> {}", request.method(), request.url(), status);
+
+        let response = match status {
+            204 => Response::from_data(Vec::new()).with_status_code(status),
+            _ => {
+                let mut out = serde_json::to_string_pretty(&body).unwrap_or_default();
+                out.push('\n');
+                json_response(out, status)
+            },
+        };
+
+        if let Err(e) = request.respond(response) {
+            log::warn!("Error answering request: {}", e);
+        }
+    }
+
+    fn handle(&self, db: &Db, request: &mut Request) -> ApiResult {
+        if !self.authorized(request) {
+            return Err(ApiError::new(401, "Missing or invalid token"));
+        }
+
+        if request.body_length().unwrap_or(0) > MAX_BODY {
+            return Err(ApiError::new(413, "Body too large"));
+        }
+        let mut body = String::new();
+        request.as_reader().take(MAX_BODY as u64 + 1).read_to_string(&mut body)
+            .map_err(|e| ApiError::new(400, format!("Invalid body: {}", e)))?;
+        if body.len() > MAX_BODY {
+            return Err(ApiError::new(413, "Body too large"));
+        }
+
+        let url = request.url();
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let api = ApiRequest {
+            method: request.method(),
+            segments: path.split('/').filter(|s| !s.is_empty()).collect(),
+            query: parse_query(query),
+            body,
+        };
+
+        routes::route(db, &api, self.payroll_tolerance)
+    }
+
+    fn authorized(&self, request: &Request) -> bool {
+        let expected = format!("Bearer {}", self.token);
+        request.headers().iter()
+            .filter(|h| h.field.equiv("Authorization"))
+            .any(|h| same_bytes(h.value.as_str().as_bytes(), expected.as_bytes()))
+    }
+}
+
+fn json_response(body: String, status: u16) -> Response<Cursor<Vec<u8>>> {
+    let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).expect("Valid header");
+    Response::from_string(body).with_status_code(status).with_header(header)
+}
+
+// Compares the whole token so the time taken does not tell how much of it matched.
+fn same_bytes(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn parse_query(query: &str) -> HashMap<String, String> {
+    query.split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let (k, v) = p.split_once('=').unwrap_or((p, ""));
+            (decode(k), decode(v))
+        })
+        .collect()
+}
+
+// Percent decoding for query values, '+' being a space.
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[i + 1..i + 3]).ok().and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 2;
+                    },
+                    None => out.push(b'%'),
+                }
+            },
+            b => out.push(b),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -0,0 +1,507 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use tiny_http::Method;
+
+use crate::models::{Db, Name};
+use crate::models::account::Account;
+use crate::models::payroll::Payroll;
+use crate::models::transaction::Transaction;
+use crate::models::validation::PayrollValidator;
+use crate::renderer::{report_json, Report};
+use crate::server::{ApiError, ApiRequest, ApiResult};
+use crate::view_models::balance_summary_vm::BalanceSummaryVm;
+use crate::view_models::transaction_data_vm::TransactionDataVm;
+
+// Bodies for create and update. Create needs the fields without a default, update only changes the given ones.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransactionBody {
+    name: Option<String>,
+    date: Option<String>,
+    amount: Option<f32>,
+    tag: Option<String>,
+    notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PayrollBody {
+    date: Option<String>,
+    gross: Option<f32>,
+    net: Option<f32>,
+    ss: Option<f32>,
+    irpf: Option<f32>,
+    other: Option<f32>,
+    company: Option<String>,
+    category: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AccountBody {
+    name: Option<String>,
+    amount: Option<f32>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NameBody {
+    name: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+enum NameKind {
+    Tags,
+    Companies,
+    Categories,
+}
+
+// Tolerance is the allowed difference between the totals of the payrolls sent.
+pub fn route(db: &Db, req: &ApiRequest, tolerance: f32) -> ApiResult {
+    let method = req.method;
+    match req.segments.as_slice() {
+        ["transactions"] => match method {
+            Method::Get => list_transactions(db, req),
+            Method::Post => create_transaction(db, req),
+            _ => not_allowed(),
+        },
+        ["transactions", id] => {
+            let id = parse_id(id)?;
+            match method {
+                Method::Get => Ok((200, transaction_json(db, &db.get_transaction(id).map_err(|_| ApiError::not_found("Transaction"))?))),
+                Method::Put | Method::Patch => update_transaction(db, req, id),
+                Method::Delete => deleted(db.delete_transaction(id)?, "Transaction"),
+                _ => not_allowed(),
+            }
+        },
+        ["payrolls"] => match method {
+            Method::Get => list_payrolls(db, req),
+            Method::Post => create_payroll(db, req, tolerance),
+            _ => not_allowed(),
+        },
+        ["payrolls", id] => {
+            let id = parse_id(id)?;
+            match method {
+                Method::Get => Ok((200, payroll_json(db, &db.get_payroll(id).map_err(|_| ApiError::not_found("Payroll"))?))),
+                Method::Put | Method::Patch => update_payroll(db, req, id, tolerance),
+                Method::Delete => deleted(db.delete_payroll(id)?, "Payroll"),
+                _ => not_allowed(),
+            }
+        },
+        ["accounts"] => match method {
+            Method::Get => Ok((200, JsonValue::Array(db.get_all_accounts()?.iter().map(account_json).collect()))),
+            Method::Post => create_account(db, req),
+            _ => not_allowed(),
+        },
+        ["accounts", id] => {
+            let id = parse_id(id)?;
+            match method {
+                Method::Get => Ok((200, account_json(&db.get_account_by_id(id).map_err(|_| ApiError::not_found("Account"))?))),
+                Method::Put | Method::Patch => update_account(db, req, id),
+                Method::Delete => deleted(db.delete_account(id)?, "Account"),
+                _ => not_allowed(),
+            }
+        },
+        [kind] if NameKind::parse(kind).is_some() => {
+            let kind = NameKind::parse(kind).unwrap();
+            match method {
+                Method::Get => Ok((200, JsonValue::Array(kind.all(db)?.iter().map(name_json).collect()))),
+                Method::Post => create_name(db, req, kind),
+                _ => not_allowed(),
+            }
+        },
+        [kind, id] if NameKind::parse(kind).is_some() => {
+            let kind = NameKind::parse(kind).unwrap();
+            let id = parse_id(id)?;
+            match method {
+                Method::Get => Ok((200, name_json(&kind.find(db, id)?))),
+                Method::Put | Method::Patch => update_name(db, req, kind, id),
+                Method::Delete => deleted(kind.delete(db, id)?, kind.label()),
+                _ => not_allowed(),
+            }
+        },
+        ["reports", "balance"] => match method {
+            Method::Get => balance_report(db, req),
+            _ => not_allowed(),
+        },
+        ["reports", "transactions"] => match method {
+            Method::Get => transactions_report(db, req),
+            _ => not_allowed(),
+        },
+        _ => Err(ApiError::new(404, "Unknown endpoint")),
+    }
+}
+
+fn not_allowed() -> ApiResult {
+    Err(ApiError::new(405, "Method not allowed"))
+}
+
+fn parse_id(id: &str) -> Result<i32, ApiError> {
+    id.parse::<i32>().map_err(|_| ApiError::new(400, format!("Invalid id [{}]", id)))
+}
+
+fn deleted(rows: usize, what: &str) -> ApiResult {
+    match rows {
+        0 => Err(ApiError::not_found(what)),
+        _ => Ok((204, JsonValue::Null)),
+    }
+}
+
+fn required<T>(value: Option<T>, name: &str) -> Result<T, ApiError> {
+    value.ok_or_else(|| ApiError::new(422, format!("Missing field [{}]", name)))
+}
+
+fn check_date(date: &str) -> Result<String, ApiError> {
+    NaiveDate::parse_from_str(&date.trim().replace('/', "-"), "%Y-%m-%d")
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .map_err(|_| ApiError::new(422, format!("Invalid date [{}], expected YYYY-MM-DD", date)))
+}
+
+fn check_amount(amount: f32, name: &str) -> Result<f32, ApiError> {
+    match amount.is_finite() {
+        true => Ok(amount),
+        false => Err(ApiError::new(422, format!("Invalid amount for [{}]", name))),
+    }
+}
+
+fn check_text(text: String, name: &str) -> Result<String, ApiError> {
+    match text.trim().is_empty() {
+        true => Err(ApiError::new(422, format!("Field [{}] can not be empty", name))),
+        false => Ok(text),
+    }
+}
+
+// Filters are plain query parameters: year, month and a name to match.
+fn period(req: &ApiRequest) -> Result<(Option<u32>, Option<u32>), ApiError> {
+    Ok((req.param::<u32>("year")?, req.param::<u32>("month")?))
+}
+
+fn filter_id(req: &ApiRequest, param: &str, lookup: impl Fn(&str) -> rusqlite::Result<i32>) -> Result<Option<i32>, ApiError> {
+    match req.query.get(param) {
+        None => Ok(None),
+        Some(name) => lookup(name).map(Some).map_err(|_| ApiError::new(400, format!("Unknown {} [{}]", param, name))),
+    }
+}
+
+fn transaction_json(db: &Db, t: &Transaction) -> JsonValue {
+    json!({
+        "id": t._id,
+        "name": t.name,
+        "date": t.date,
+        "amount": t.amount,
+        "tag": db.get_tag_str(t.tag_id).ok(),
+        "notes": t.notes,
+    })
+}
+
+fn list_transactions(db: &Db, req: &ApiRequest) -> ApiResult {
+    let (year, month) = period(req)?;
+    let tag = filter_id(req, "tag", |n| db.get_tag_id(n))?;
+
+    let list = db.get_transaction_data(year, month)?.iter()
+        .filter(|t| tag.is_none_or(|id| t.tag_id == id))
+        .map(|t| transaction_json(db, t))
+        .collect();
+
+    Ok((200, JsonValue::Array(list)))
+}
+
+fn create_transaction(db: &Db, req: &ApiRequest) -> ApiResult {
+    let body: TransactionBody = req.json()?;
+    let mut transaction = Transaction::new(
+        &check_text(required(body.name, "name")?, "name")?,
+        &check_date(&required(body.date, "date")?)?,
+        check_amount(required(body.amount, "amount")?, "amount")?,
+        tag_id(db, &required(body.tag, "tag")?)?,
+    );
+    transaction.notes = body.notes.filter(|n| !n.trim().is_empty());
+
+    db.insert_transaction(&transaction)?;
+    let created = db.get_transaction(db.last_insert_id())?;
+
+    Ok((201, transaction_json(db, &created)))
+}
+
+fn update_transaction(db: &Db, req: &ApiRequest, id: i32) -> ApiResult {
+    let body: TransactionBody = req.json()?;
+    let mut transaction = db.get_transaction(id).map_err(|_| ApiError::not_found("Transaction"))?;
+
+    if let Some(name) = body.name {
+        transaction.name = check_text(name, "name")?;
+    }
+    if let Some(date) = body.date {
+        transaction.date = check_date(&date)?;
+    }
+    if let Some(amount) = body.amount {
+        transaction.amount = check_amount(amount, "amount")?;
+    }
+    if let Some(tag) = body.tag {
+        transaction.tag_id = tag_id(db, &tag)?;
+    }
+    // An empty string clears them.
+    if let Some(notes) = body.notes {
+        transaction.notes = Some(notes).filter(|n| !n.trim().is_empty());
+    }
+
+    db.update_transaction(&transaction)?;
+    Ok((200, transaction_json(db, &transaction)))
+}
+
+fn tag_id(db: &Db, tag: &str) -> Result<i32, ApiError> {
+    db.get_tag_id(tag).map_err(|_| ApiError::new(422, format!("Unknown tag [{}]", tag)))
+}
+
+fn payroll_json(db: &Db, p: &Payroll) -> JsonValue {
+    json!({
+        "id": p._id,
+        "date": p.date,
+        "gross": p.gross,
+        "net": p.net,
+        "ss": p.ss,
+        "irpf": p.irpf,
+        "other": p.other,
+        "company": db.get_company_str(p.company_id).ok(),
+        "category": db.get_category_str(p.category_id).ok(),
+    })
+}
+
+fn list_payrolls(db: &Db, req: &ApiRequest) -> ApiResult {
+    let (year, month) = period(req)?;
+    let company = filter_id(req, "company", |n| db.get_company_id(n))?;
+    let category = filter_id(req, "category", |n| db.get_category_id(n))?;
+
+    let list = db.get_payroll_data(year, month)?.iter()
+        .filter(|p| company.is_none_or(|id| p.company_id == id))
+        .filter(|p| category.is_none_or(|id| p.category_id == id))
+        .map(|p| payroll_json(db, p))
+        .collect();
+
+    Ok((200, JsonValue::Array(list)))
+}
+
+fn create_payroll(db: &Db, req: &ApiRequest, tolerance: f32) -> ApiResult {
+    let body: PayrollBody = req.json()?;
+    let mut payroll = Payroll::new(
+        &required(body.date, "date")?,
+        required(body.gross, "gross")?,
+        required(body.net, "net")?,
+        required(body.ss, "ss")?,
+        required(body.irpf, "irpf")?,
+        company_id(db, &required(body.company, "company")?)?,
+        category_id(db, &required(body.category, "category")?)?,
+    );
+    payroll.other = body.other.unwrap_or(0.0);
+
+    let warnings = validate_payroll(db, &mut payroll, tolerance)?;
+    db.insert_payroll(&payroll)?;
+    let created = db.get_payroll(db.last_insert_id())?;
+
+    Ok((201, with_warnings(payroll_json(db, &created), warnings)))
+}
+
+fn update_payroll(db: &Db, req: &ApiRequest, id: i32, tolerance: f32) -> ApiResult {
+    let body: PayrollBody = req.json()?;
+    let mut payroll = db.get_payroll(id).map_err(|_| ApiError::not_found("Payroll"))?;
+
+    if let Some(date) = body.date {
+        payroll.date = date;
+    }
+    payroll.gross = body.gross.unwrap_or(payroll.gross);
+    payroll.net = body.net.unwrap_or(payroll.net);
+    payroll.ss = body.ss.unwrap_or(payroll.ss);
+    payroll.irpf = body.irpf.unwrap_or(payroll.irpf);
+    payroll.other = body.other.unwrap_or(payroll.other);
+    if let Some(company) = body.company {
+        payroll.company_id = company_id(db, &company)?;
+    }
+    if let Some(category) = body.category {
+        payroll.category_id = category_id(db, &category)?;
+    }
+
+    let warnings = validate_payroll(db, &mut payroll, tolerance)?;
+    db.update_payroll(&payroll)?;
+
+    Ok((200, with_warnings(payroll_json(db, &payroll), warnings)))
+}
+
+// Same checks as add-payroll. Rejections answer 422 with every error, warnings go along with the payroll.
+fn validate_payroll(db: &Db, payroll: &mut Payroll, tolerance: f32) -> Result<Vec<String>, ApiError> {
+    let validator = PayrollValidator { tolerance, record_other: false };
+    validator.validate(db, payroll).map_err(|errors| ApiError::new(422, errors.join("; ")))
+}
+
+fn with_warnings(mut value: JsonValue, warnings: Vec<String>) -> JsonValue {
+    if !warnings.is_empty() {
+        value["warnings"] = json!(warnings);
+    }
+    value
+}
+
+fn company_id(db: &Db, company: &str) -> Result<i32, ApiError> {
+    db.get_company_id(company).map_err(|_| ApiError::new(422, format!("Unknown company [{}]", company)))
+}
+
+fn category_id(db: &Db, category: &str) -> Result<i32, ApiError> {
+    db.get_category_id(category).map_err(|_| ApiError::new(422, format!("Unknown category [{}]", category)))
+}
+
+fn account_json(a: &Account) -> JsonValue {
+    json!({
+        "id": a._id,
+        "name": a.name,
+        "amount": a.amount,
+        "description": a.description,
+    })
+}
+
+fn create_account(db: &Db, req: &ApiRequest) -> ApiResult {
+    let body: AccountBody = req.json()?;
+    let account = Account::new(
+        &check_text(required(body.name, "name")?, "name")?,
+        check_amount(required(body.amount, "amount")?, "amount")?,
+        &body.description.unwrap_or_default(),
+    );
+
+    db.insert_account(&account)?;
+    let created = db.get_account_by_id(db.last_insert_id())?;
+
+    Ok((201, account_json(&created)))
+}
+
+fn update_account(db: &Db, req: &ApiRequest, id: i32) -> ApiResult {
+    let body: AccountBody = req.json()?;
+    let mut account = db.get_account_by_id(id).map_err(|_| ApiError::not_found("Account"))?;
+
+    if let Some(name) = body.name {
+        account.name = check_text(name, "name")?;
+    }
+    if let Some(amount) = body.amount {
+        account.amount = check_amount(amount, "amount")?;
+    }
+    if let Some(description) = body.description {
+        account.description = description;
+    }
+
+    db.update_account(&account)?;
+    Ok((200, account_json(&account)))
+}
+
+impl NameKind {
+    fn parse(segment: &str) -> Option<Self> {
+        match segment {
+            "tags" => Some(NameKind::Tags),
+            "companies" => Some(NameKind::Companies),
+            "categories" => Some(NameKind::Categories),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            NameKind::Tags => "Tag",
+            NameKind::Companies => "Company",
+            NameKind::Categories => "Category",
+        }
+    }
+
+    fn all(self, db: &Db) -> rusqlite::Result<Vec<Name>> {
+        match self {
+            NameKind::Tags => db.get_all_tags(),
+            NameKind::Companies => db.get_all_companies(),
+            NameKind::Categories => db.get_all_categories(),
+        }
+    }
+
+    fn find(self, db: &Db, id: i32) -> Result<Name, ApiError> {
+        self.all(db)?.into_iter().find(|n| n.id == id).ok_or_else(|| ApiError::not_found(self.label()))
+    }
+
+    fn insert(self, db: &Db, name: &str, description: &str) -> rusqlite::Result<usize> {
+        match self {
+            NameKind::Tags => db.insert_tag(name, description),
+            NameKind::Companies => db.insert_company(name, description),
+            NameKind::Categories => db.insert_category(name, description),
+        }
+    }
+
+    fn update(self, db: &Db, name: &Name) -> rusqlite::Result<usize> {
+        match self {
+            NameKind::Tags => db.update_tag(name),
+            NameKind::Companies => db.update_company(name),
+            NameKind::Categories => db.update_category(name),
+        }
+    }
+
+    fn delete(self, db: &Db, id: i32) -> rusqlite::Result<usize> {
+        match self {
+            NameKind::Tags => db.delete_tag(id),
+            NameKind::Companies => db.delete_company(id),
+            NameKind::Categories => db.delete_category(id),
+        }
+    }
+}
+
+fn name_json(n: &Name) -> JsonValue {
+    json!({
+        "id": n.id,
+        "name": n.name,
+        "description": n.description,
+    })
+}
+
+// Names are looked up by name everywhere else, so they have to stay unique.
+fn check_unique(db: &Db, kind: NameKind, name: &str, id: i32) -> Result<(), ApiError> {
+    match kind.all(db)?.iter().any(|n| n.id != id && n.name == name) {
+        true => Err(ApiError::new(409, format!("{} [{}] already exists", kind.label(), name))),
+        false => Ok(()),
+    }
+}
+
+fn create_name(db: &Db, req: &ApiRequest, kind: NameKind) -> ApiResult {
+    let body: NameBody = req.json()?;
+    let name = check_text(required(body.name, "name")?, "name")?;
+    check_unique(db, kind, &name, 0)?;
+
+    kind.insert(db, &name, &body.description.unwrap_or_default())?;
+    Ok((201, name_json(&kind.find(db, db.last_insert_id())?)))
+}
+
+fn update_name(db: &Db, req: &ApiRequest, kind: NameKind, id: i32) -> ApiResult {
+    let body: NameBody = req.json()?;
+    let mut name = kind.find(db, id)?;
+
+    if let Some(value) = body.name {
+        name.name = check_text(value, "name")?;
+        check_unique(db, kind, &name.name, id)?;
+    }
+    if let Some(description) = body.description {
+        name.description = description;
+    }
+
+    kind.update(db, &name)?;
+    Ok((200, name_json(&name)))
+}
+
+fn balance_report(db: &Db, req: &ApiRequest) -> ApiResult {
+    let (year, month) = period(req)?;
+    let payrolls = db.get_payroll_data(year, month)?;
+    let transactions = db.get_transaction_data(year, month)?;
+
+    let mut report = Report::new();
+    BalanceSummaryVm::generate(&payrolls, &transactions).render(db, &mut report);
+
+    Ok((200, report_json(&report)))
+}
+
+fn transactions_report(db: &Db, req: &ApiRequest) -> ApiResult {
+    let (year, month) = period(req)?;
+    let transactions = db.get_transaction_data(year, month)?;
+
+    let mut report = Report::new();
+    TransactionDataVm::generate(&transactions).render(db, &mut report);
+
+    Ok((200, report_json(&report)))
+}
@@ -0,0 +1,281 @@
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+// Progressive scale as (upper limit, rate %) pairs sorted by limit. The last limit is usually infinite.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaxBrackets {
+    brackets: Vec<(f32, f32)>,
+}
+
+impl TaxBrackets {
+    // 2021 state half of the general scale (art. 63 LIRPF). Regions without their own scale apply the same rates.
+    pub fn state_2021() -> Self {
+        Self {
+            brackets: vec![
+                (12450.0, 9.5),
+                (20200.0, 12.0),
+                (35200.0, 15.0),
+                (60000.0, 18.5),
+                (300000.0, 22.5),
+                (f32::INFINITY, 24.5),
+            ],
+        }
+    }
+    
+    pub fn apply(&self, base: f32) -> f32 {
+        let mut tax = 0.0;
+        let mut lower = 0.0;
+        
+        for (limit, rate) in &self.brackets {
+            if base <= lower {
+                break;
+            }
+            
+            tax += (base.min(*limit) - lower) * rate / 100.0;
+            lower = *limit;
+        }
+        
+        tax
+    }
+}
+
+// Parses "12450:9.5,20200:12,inf:24.5". A missing last "inf" bracket is treated as the last rate going on forever.
+impl FromStr for TaxBrackets {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut brackets = Vec::new();
+        
+        for part in s.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            let error = || format!("Invalid bracket [{}]. Expected limit:rate, e.g. 12450:9.5", part);
+            let (limit, rate) = part.split_once(':').ok_or_else(error)?;
+            
+            let limit = match limit.trim().to_lowercase().as_str() {
+                "inf" | "" => f32::INFINITY,
+                l => l.parse::<f32>().map_err(|_| error())?,
+            };
+            let rate = rate.trim().trim_end_matches('%').parse::<f32>().map_err(|_| error())?;
+            
+            if let Some((last, _)) = brackets.last() {
+                if limit <= *last {
+                    return Err(format!("Bracket limits must be increasing [{}]", s));
+                }
+            }
+            
+            brackets.push((limit, rate));
+        }
+        
+        match brackets.last_mut() {
+            Some(last) => last.0 = f32::INFINITY,
+            None => return Err(String::from("Empty tax brackets")),
+        }
+        
+        Ok(Self { brackets })
+    }
+}
+
+impl std::fmt::Display for TaxBrackets {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = self.brackets.iter().map(|(limit, rate)| {
+            if limit.is_infinite() { format!("inf:{}", rate) } else { format!("{}:{}", limit, rate) }
+        }).collect();
+        
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IrpfEstimate {
+    pub gross: f32,
+    pub ss: f32,
+    pub other_expenses: f32,
+    pub work_reduction: f32,
+    pub deductions: f32,
+    pub taxable_base: f32,
+    pub minimum: f32,
+    pub state_tax: f32,
+    pub region_tax: f32,
+}
+
+impl IrpfEstimate {
+    pub fn tax(&self) -> f32 {
+        self.state_tax + self.region_tax
+    }
+    
+    pub fn rate(&self) -> f32 {
+        if self.gross > 0.0 { self.tax() / self.gross * 100.0 } else { 0.0 }
+    }
+}
+
+// Simplified yearly IRPF for employment income only, good enough to compare with what was withheld.
+#[derive(Debug, Clone)]
+pub struct IrpfModel {
+    pub state: TaxBrackets,
+    pub region: TaxBrackets,
+    pub personal_minimum: f32,
+    // Descendants, ascendants and disability minimums, see IrpfModel::descendants_minimum.
+    pub family_minimum: f32,
+    pub other_expenses: f32,
+}
+
+impl Default for IrpfModel {
+    fn default() -> Self {
+        Self {
+            state: TaxBrackets::state_2021(),
+            region: TaxBrackets::state_2021(),
+            personal_minimum: 5550.0,
+            family_minimum: 0.0,
+            other_expenses: 2000.0,
+        }
+    }
+}
+
+impl IrpfModel {
+    // Each scale applies to the taxable base, minus the same scale applied to the personal and family minimum.
+    pub fn estimate(&self, gross: f32, ss: f32, deductions: f32) -> IrpfEstimate {
+        let net_income = (gross - ss - self.other_expenses).max(0.0);
+        let work_reduction = IrpfModel::work_reduction(gross - ss);
+        let taxable_base = (net_income - work_reduction - deductions).max(0.0);
+        let minimum = self.personal_minimum + self.family_minimum;
+        
+        let quota = |scale: &TaxBrackets| (scale.apply(taxable_base) - scale.apply(minimum.min(taxable_base))).max(0.0);
+        
+        IrpfEstimate {
+            gross,
+            ss,
+            other_expenses: self.other_expenses.min((gross - ss).max(0.0)),
+            work_reduction,
+            deductions,
+            taxable_base,
+            minimum,
+            state_tax: quota(&self.state),
+            region_tax: quota(&self.region),
+        }
+    }
+    
+    // Minimum for descendants (art. 58 LIRPF): 2400, 2700, 4000 and 4500 from the fourth on, plus 2800 for each one under three.
+    pub fn descendants_minimum(descendants: u32, under_three: u32) -> f32 {
+        let per_child = (1..=descendants).map(|i| match i {
+            1 => 2400.0,
+            2 => 2700.0,
+            3 => 4000.0,
+            _ => 4500.0,
+        }).sum::<f32>();
+        
+        per_child + 2800.0 * under_three.min(descendants) as f32
+    }
+    
+    // Reduction for low employment income (art. 20 LIRPF, 2021 amounts), based on income after SS.
+    fn work_reduction(income: f32) -> f32 {
+        if income <= 13115.0 {
+            5565.0
+        } else if income <= 16825.0 {
+            5565.0 - 1.5 * (income - 13115.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+// Employee social security contributions.
+#[derive(Debug, Clone)]
+pub struct SsModel {
+    // Common contingencies, unemployment and training, in %.
+    pub rate: f32,
+    // Monthly contribution base limits. Extra payments are prorated into the twelve monthly bases.
+    pub min_base: f32,
+    pub max_base: f32,
+}
+
+impl Default for SsModel {
+    // 2021 values for a permanent contract: 4.70 + 1.55 + 0.10.
+    fn default() -> Self {
+        Self {
+            rate: 6.35,
+            min_base: 1108.33,
+            max_base: 4070.10,
+        }
+    }
+}
+
+impl SsModel {
+    // Nothing is paid without a salary, otherwise the minimum base applies.
+    pub fn yearly(&self, gross: f32) -> f32 {
+        if gross <= 0.0 {
+            return 0.0;
+        }
+        
+        let base = (gross / 12.0).max(self.min_base).min(self.max_base);
+        base * 12.0 * self.rate / 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn brackets_parse_and_end_in_infinity() {
+        let brackets: TaxBrackets = "12450:9.5, 20200:12%".parse().unwrap();
+        assert_eq!(brackets.to_string(), "12450:9.5,inf:12");
+        assert_eq!("inf:10".parse::<TaxBrackets>().unwrap().to_string(), "inf:10");
+
+        assert!("".parse::<TaxBrackets>().is_err());
+        assert!("12450".parse::<TaxBrackets>().is_err());
+        assert!("abc:9.5".parse::<TaxBrackets>().is_err());
+        assert!("20200:12,12450:9.5".parse::<TaxBrackets>().is_err());
+    }
+
+    #[test]
+    fn brackets_apply_each_rate_to_its_slice() {
+        let brackets = TaxBrackets::state_2021();
+        assert_eq!(brackets.apply(0.0), 0.0);
+        assert!(close(brackets.apply(12450.0), 1182.75));
+        assert!(close(brackets.apply(20000.0), 1182.75 + 7550.0 * 0.12));
+        // Past 300000 the last bracket, set to infinity, takes the rest.
+        assert!(close(brackets.apply(400000.0), 87450.75));
+
+        let open: TaxBrackets = "1000:10,2000:20".parse().unwrap();
+        assert!(close(open.apply(5000.0), 100.0 + 4000.0 * 0.2));
+    }
+
+    #[test]
+    fn work_reduction_fades_between_limits() {
+        assert_eq!(IrpfModel::work_reduction(10000.0), 5565.0);
+        assert_eq!(IrpfModel::work_reduction(13115.0), 5565.0);
+        assert!(close(IrpfModel::work_reduction(15000.0), 2737.5));
+        assert!(close(IrpfModel::work_reduction(16825.0), 0.0));
+        assert_eq!(IrpfModel::work_reduction(30000.0), 0.0);
+    }
+
+    #[test]
+    fn estimate_leaves_income_under_the_minimum_untaxed() {
+        let model = IrpfModel::default();
+
+        let low = model.estimate(10000.0, 635.0, 0.0);
+        assert!(close(low.taxable_base, 1800.0));
+        assert_eq!(low.tax(), 0.0);
+
+        let high = model.estimate(40000.0, 2540.0, 0.0);
+        let base = 40000.0 - 2540.0 - 2000.0;
+        assert!(close(high.taxable_base, base));
+        let quota = TaxBrackets::state_2021().apply(base) - TaxBrackets::state_2021().apply(5550.0);
+        assert!(close(high.tax(), 2.0 * quota));
+        assert!(close(high.rate(), high.tax() / 400.0));
+
+        assert_eq!(model.estimate(0.0, 0.0, 0.0).rate(), 0.0);
+    }
+
+    #[test]
+    fn ss_keeps_the_base_between_limits_and_is_zero_without_salary() {
+        let model = SsModel::default();
+        assert_eq!(model.yearly(0.0), 0.0);
+        assert!(close(model.yearly(6000.0), 1108.33 * 12.0 * 0.0635));
+        assert!(close(model.yearly(24000.0), 24000.0 * 0.0635));
+        assert!(close(model.yearly(100000.0), 4070.10 * 12.0 * 0.0635));
+    }
+}
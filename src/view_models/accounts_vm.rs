@@ -0,0 +1,31 @@
+use crate::models::account::Account;
+use crate::renderer::{Report, Value};
+
+pub struct AccountsVm<'a> {
+    accounts: &'a [Account],
+    total: f32,
+}
+
+impl<'a> AccountsVm<'a> {
+    pub fn generate(from: &'a [Account]) -> Self {
+        Self {
+            accounts: from,
+            total: from.iter().map(|a| a.amount).sum(),
+        }
+    }
+    
+    pub fn render(&self, report: &mut Report) {
+        let rows = self.accounts.iter().map(|a| vec![
+            Value::from(a._id),
+            Value::from(a.name.as_str()),
+            Value::from(a.amount),
+            Value::from(a.description.as_str()),
+        ]).collect();
+        
+        report.add_table("accounts", "Accounts", vec!["Id", "Name", "Amount", "Description"], rows);
+        
+        if self.accounts.len() > 1 {
+            report.add_summary("total", "Total", vec![("Total", Value::from(self.total))]);
+        }
+    }
+}
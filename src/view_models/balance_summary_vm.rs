@@ -6,49 +6,54 @@ use crate::models::payroll::Payroll;
 use crate::view_models::payroll_data_vm::PayrollDataVm;
 use crate::view_models::transaction_data_vm::TransactionDataVm;
 
-use comfy_table::{Table, Row, ContentArrangement, Cell, Attribute, Color};
-use comfy_table::presets::UTF8_FULL;
-use comfy_table::modifiers::UTF8_ROUND_CORNERS;
+use crate::renderer::{Report, Value};
+use crate::charts::{Chart, ChartKind, Series, monthly_totals, monthly_values};
 
 pub struct BalanceSummaryVm<'a> {
     payroll: PayrollDataVm<'a>,
     transactions: TransactionDataVm<'a>,
+    payroll_list: &'a [Payroll],
+    transaction_list: &'a [Transaction],
 }
 
 impl<'a> BalanceSummaryVm<'a> {
-    pub fn generate(payrolls: &'a Vec<Payroll>, transactions: &'a Vec<Transaction>) -> Self {
+    pub fn generate(payrolls: &'a [Payroll], transactions: &'a [Transaction]) -> Self {
         Self {
             payroll: PayrollDataVm::generate(payrolls),
             transactions: TransactionDataVm::generate(transactions),
+            payroll_list: payrolls,
+            transaction_list: transactions,
         }
     }
     
-    pub fn render(&self, db: &Db) {
-        self.payroll.render(db);
-        self.transactions.render(db);
-        
-        let header = vec![
-            Cell::new("Income").fg(Color::Green),
-            Cell::new("Expenses").fg(Color::Red),
-            Cell::new("Total").add_attribute(Attribute::Bold),
-        ];
-        let mut table = Table::new();
-        table
-            .load_preset(UTF8_FULL)
-            .apply_modifier(UTF8_ROUND_CORNERS)
-            .set_content_arrangement(ContentArrangement::Dynamic)
-            .set_header(Row::from(header));
+    pub fn render(&self, db: &Db, report: &mut Report) {
+        self.payroll.render(db, report);
+        self.transactions.render(db, report);
         
         let income = self.payroll.get_net();
         let expenses = self.transactions.total();
         let total = income - expenses;
         
-        table.add_row(vec![
-            Cell::new(income),
-            Cell::new(expenses),
-            Cell::new(total),
+        report.add_summary("balance", "Balance", vec![
+            ("Income", Value::from(income)),
+            ("Expenses", Value::from(expenses)),
+            ("Total", Value::from(total)),
         ]);
+    }
+    
+    pub fn charts(&self) -> Vec<Chart> {
+        let dates = self.payroll_list.iter().map(|p| p.date.as_str())
+            .chain(self.transaction_list.iter().map(|t| t.date.as_str()));
+        let (months, _) = monthly_totals(dates.map(|d| (d, 0.0)));
+        
+        let income = monthly_values(&months, self.payroll_list.iter().map(|p| (p.date.as_str(), p.net)));
+        let expenses = monthly_values(&months, self.transaction_list.iter().map(|t| (t.date.as_str(), t.amount)));
         
-        log::info!("Summary:\n{}", table);
+        vec![
+            Chart::new("Income vs expenses", ChartKind::Line, months, vec![
+                Series::new("Income", income),
+                Series::new("Expenses", expenses),
+            ]),
+        ]
     }
 }
@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+
+use crate::Db;
+use crate::commons::Period;
+use crate::renderer::{Report, Value};
+use crate::view_models::payroll_data_vm::PayrollDataVm;
+use crate::view_models::transaction_data_vm::TransactionDataVm;
+
+struct Delta {
+    from: f32,
+    to: f32,
+}
+
+impl Delta {
+    fn diff(&self) -> f32 {
+        self.to - self.from
+    }
+    
+    fn percent(&self) -> Value {
+        if self.from.abs() > f32::EPSILON {
+            Value::Percent(self.diff() / self.from.abs() * 100.0)
+        } else {
+            Value::text("-")
+        }
+    }
+    
+    fn status(&self) -> &'static str {
+        match (self.from.abs() > f32::EPSILON, self.to.abs() > f32::EPSILON) {
+            (false, true) => "New",
+            (true, false) => "Gone",
+            _ => "",
+        }
+    }
+}
+
+pub struct CompareVm {
+    from: Period,
+    to: Period,
+    income: Delta,
+    expenses: Delta,
+    tags: BTreeMap<i32, Delta>,
+}
+
+impl CompareVm {
+    pub fn generate(from: Period, to: Period, from_payrolls: &PayrollDataVm, to_payrolls: &PayrollDataVm,
+                    from_transactions: &TransactionDataVm, to_transactions: &TransactionDataVm) -> Self {
+        let mut tags: BTreeMap<i32, Delta> = BTreeMap::new();
+        
+        for (id, _, amount) in from_transactions.tag_groups() {
+            tags.entry(id).or_insert(Delta { from: 0.0, to: 0.0 }).from = amount;
+        }
+        
+        for (id, _, amount) in to_transactions.tag_groups() {
+            tags.entry(id).or_insert(Delta { from: 0.0, to: 0.0 }).to = amount;
+        }
+        
+        Self {
+            from,
+            to,
+            income: Delta { from: from_payrolls.get_net(), to: to_payrolls.get_net() },
+            expenses: Delta { from: from_transactions.total(), to: to_transactions.total() },
+            tags,
+        }
+    }
+    
+    pub fn render(&self, db: &Db, report: &mut Report, top: usize) {
+        let from = self.from.to_string();
+        let to = self.to.to_string();
+        let header = vec!["Concept", from.as_str(), to.as_str(), "Delta", "Delta %"];
+        
+        let balance = Delta {
+            from: self.income.from - self.expenses.from,
+            to: self.income.to - self.expenses.to,
+        };
+        
+        let rows = vec![("Income", &self.income), ("Expenses", &self.expenses), ("Balance", &balance)].into_iter()
+            .map(|(name, d)| vec![Value::from(name), Value::from(d.from), Value::from(d.to), Value::from(d.diff()), d.percent()])
+            .collect();
+        report.add_table("totals", &format!("Totals {} vs {}", from, to), header, rows);
+        
+        let tag_name = |id: i32| db.get_tag_str(id).unwrap_or_else(|_| String::from("Unknown"));
+        
+        let rows = self.tags.iter().map(|(id, d)| vec![
+            Value::from(tag_name(*id)),
+            Value::from(d.from),
+            Value::from(d.to),
+            Value::from(d.diff()),
+            d.percent(),
+            Value::from(d.status()),
+        ]).collect();
+        report.add_table("tags", "Per tag expenses", vec!["Tag", from.as_str(), to.as_str(), "Delta", "Delta %", "Status"], rows);
+        
+        let mut movers: Vec<(&i32, &Delta)> = self.tags.iter().filter(|(_, d)| d.diff().abs() > 0.005).collect();
+        movers.sort_by(|a, b| b.1.diff().abs().partial_cmp(&a.1.diff().abs()).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(b.0)));
+        
+        let rows = movers.into_iter().take(top).map(|(id, d)| vec![
+            Value::from(tag_name(*id)),
+            Value::from(d.diff()),
+            d.percent(),
+            Value::from(if d.diff() > 0.0 { "Up" } else { "Down" }),
+        ]).collect();
+        report.add_table("movers", "Biggest movers", vec!["Tag", "Delta", "Delta %", "Direction"], rows);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::Content;
+    use crate::models::payroll::Payroll;
+    use crate::models::transaction::Transaction;
+
+    fn table(report: &Report, key: &str) -> Vec<Vec<String>> {
+        match &report.sections().iter().find(|s| s.key == key).unwrap().content {
+            Content::Table { rows, .. } => rows.iter().map(|r| r.iter().map(Value::to_plain).collect()).collect(),
+            Content::Summary(_) => panic!("{} is not a table", key),
+        }
+    }
+
+    fn render(top: usize) -> Report {
+        let db = Db::load(":memory:").unwrap();
+        for tag in ["Food", "Rent", "Gym", "Car"] {
+            db.insert_tag(tag, "").unwrap();
+        }
+
+        let transaction = |date: &str, amount: f32, tag: i32| Transaction::new(&String::from("t"), &String::from(date), amount, tag);
+        let from_transactions = vec![transaction("2021-01-10", 100.0, 1), transaction("2021-02-01", 500.0, 2), transaction("2021-03-05", 50.0, 3)];
+        let to_transactions = vec![transaction("2022-01-10", 150.0, 1), transaction("2022-02-01", 500.0, 2), transaction("2022-03-05", 80.0, 4)];
+        let from_payrolls = vec![Payroll::new(&String::from("2021-01-31"), 1400.0, 1000.0, 100.0, 300.0, 1, 1)];
+        let to_payrolls = vec![Payroll::new(&String::from("2022-01-31"), 1700.0, 1200.0, 120.0, 380.0, 1, 1)];
+
+        let vm = CompareVm::generate(
+            Period { year: 2021, month: None }, Period { year: 2022, month: None },
+            &PayrollDataVm::generate(&from_payrolls), &PayrollDataVm::generate(&to_payrolls),
+            &TransactionDataVm::generate(&from_transactions), &TransactionDataVm::generate(&to_transactions),
+        );
+
+        let mut report = Report::new();
+        vm.render(&db, &mut report, top);
+        report
+    }
+
+    #[test]
+    fn totals_have_the_delta_and_its_percent() {
+        assert_eq!(table(&render(5), "totals"), vec![
+            vec!["Income", "1000.00", "1200.00", "200.00", "20.00%"],
+            vec!["Expenses", "650.00", "730.00", "80.00", "12.31%"],
+            vec!["Balance", "350.00", "470.00", "120.00", "34.29%"],
+        ]);
+    }
+
+    #[test]
+    fn tags_missing_on_one_side_are_new_or_gone() {
+        assert_eq!(table(&render(5), "tags"), vec![
+            vec!["Food", "100.00", "150.00", "50.00", "50.00%", ""],
+            vec!["Rent", "500.00", "500.00", "0.00", "0.00%", ""],
+            vec!["Gym", "50.00", "0.00", "-50.00", "-100.00%", "Gone"],
+            // Nothing to compare a new tag with.
+            vec!["Car", "0.00", "80.00", "80.00", "-", "New"],
+        ]);
+    }
+
+    #[test]
+    fn movers_go_by_the_size_of_the_change() {
+        // Unchanged tags are left out, ties keep the tag order.
+        assert_eq!(table(&render(5), "movers"), vec![
+            vec!["Car", "80.00", "-", "Up"],
+            vec!["Food", "50.00", "50.00%", "Up"],
+            vec!["Gym", "-50.00", "-100.00%", "Down"],
+        ]);
+
+        let names: Vec<String> = table(&render(2), "movers").into_iter().map(|r| r[0].clone()).collect();
+        assert_eq!(names, vec!["Car", "Food"]);
+    }
+}
@@ -0,0 +1,297 @@
+use std::fmt::Write;
+use std::str::FromStr;
+use std::collections::BTreeSet;
+
+use crate::Db;
+use crate::commons::{escape, QUOTE_ESCAPES};
+use crate::models::transaction::Transaction;
+use crate::models::payroll::Payroll;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LedgerDialect {
+    Ledger,
+    Beancount,
+}
+
+impl FromStr for LedgerDialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ledger" | "hledger" => Ok(LedgerDialect::Ledger),
+            "beancount" => Ok(LedgerDialect::Beancount),
+            _ => Err(format!("Invalid dialect [{}]. Available: ledger, hledger, beancount", s)),
+        }
+    }
+}
+
+struct Posting {
+    account: String,
+    amount: f32,
+}
+
+struct Entry {
+    date: String,
+    // Payrolls go before transactions on the same day, then by id, so re-exports are stable.
+    kind: u8,
+    id: i32,
+    payee: String,
+    narration: String,
+    postings: Vec<Posting>,
+}
+
+pub struct LedgerExportVm {
+    dialect: LedgerDialect,
+    currency: String,
+    entries: Vec<Entry>,
+}
+
+impl LedgerExportVm {
+    pub fn generate(payrolls: &[Payroll], transactions: &[Transaction], db: &Db, dialect: LedgerDialect, account: &str, currency: &str) -> Self {
+        let mut entries = Vec::new();
+
+        for p in payrolls {
+            let company = db.get_company_str(p.company_id).unwrap_or_else(|_| String::from("Unknown"));
+            let category = db.get_category_str(p.category_id).unwrap_or_else(|_| String::from("Unknown"));
+
+            let mut postings = vec![
+                Posting { account: String::from(account), amount: p.net },
+                Posting { account: String::from("Expenses:Taxes:SS"), amount: p.ss },
+                Posting { account: String::from("Expenses:Taxes:IRPF"), amount: p.irpf },
+            ];
+
+            // Whatever gross has that net and taxes do not explain, so the entry balances even when
+            // other was not filled in or the payroll does not add up. In cents, as they are printed.
+            let other = cents(p.gross) - cents(p.net) - cents(p.ss) - cents(p.irpf);
+            if other != 0 {
+                postings.push(Posting { account: String::from("Expenses:Deductions:Other"), amount: other as f32 / 100.0 });
+            }
+
+            postings.push(Posting { account: format!("Income:Salary:{}", company), amount: -p.gross });
+
+            entries.push(Entry {
+                date: p.date.clone(),
+                kind: 0,
+                id: p._id,
+                payee: company.clone(),
+                narration: format!("Payroll ({})", category),
+                postings,
+            });
+        }
+
+        for t in transactions {
+            let tag = db.get_tag_str(t.tag_id).unwrap_or_else(|_| String::from("Unknown"));
+
+            entries.push(Entry {
+                date: t.date.clone(),
+                kind: 1,
+                id: t._id,
+                payee: t.name.clone(),
+                narration: tag.clone(),
+                postings: vec![
+                    Posting { account: format!("Expenses:{}", tag), amount: t.amount },
+                    Posting { account: String::from(account), amount: -t.amount },
+                ],
+            });
+        }
+
+        entries.sort_by(|a, b| (&a.date, a.kind, a.id).cmp(&(&b.date, b.kind, b.id)));
+
+        for e in &mut entries {
+            for p in &mut e.postings {
+                p.account = account_name(dialect, &p.account);
+            }
+        }
+
+        Self {
+            dialect,
+            currency: String::from(currency),
+            entries,
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        if self.dialect == LedgerDialect::Beancount {
+            self.render_opens(&mut out);
+        }
+
+        for e in &self.entries {
+            match self.dialect {
+                LedgerDialect::Ledger => {
+                    let _ = writeln!(out, "{} {} | {}", e.date, e.payee, e.narration);
+                },
+                LedgerDialect::Beancount => {
+                    let _ = writeln!(out, "{} * \"{}\" \"{}\"", e.date, escape(&e.payee, &QUOTE_ESCAPES), escape(&e.narration, &QUOTE_ESCAPES));
+                },
+            }
+
+            for p in &e.postings {
+                let _ = writeln!(out, "    {:<40}  {:>12.2} {}", p.account, p.amount, self.currency);
+            }
+
+            out.push('\n');
+        }
+
+        out
+    }
+
+    // Beancount refuses postings to accounts that were never opened.
+    fn render_opens(&self, out: &mut String) {
+        let first_date = match self.entries.first() {
+            Some(e) => &e.date,
+            None => return,
+        };
+
+        let accounts: BTreeSet<&String> = self.entries.iter()
+            .flat_map(|e| e.postings.iter().map(|p| &p.account))
+            .collect();
+
+        for a in accounts {
+            let _ = writeln!(out, "{} open {} {}", first_date, a, self.currency);
+        }
+
+        out.push('\n');
+    }
+}
+
+fn cents(amount: f32) -> i64 {
+    (amount as f64 * 100.0).round() as i64
+}
+
+fn account_name(dialect: LedgerDialect, name: &str) -> String {
+    match dialect {
+        // Two consecutive spaces end the account name in ledger files.
+        LedgerDialect::Ledger => name.split_whitespace().collect::<Vec<&str>>().join(" "),
+        LedgerDialect::Beancount => name.split(':').map(beancount_component).collect::<Vec<String>>().join(":"),
+    }
+}
+
+// Beancount components must start with an uppercase letter or digit and only contain letters, digits or dashes.
+fn beancount_component(component: &str) -> String {
+    let mut ret: String = component.split_whitespace()
+        .map(|w| {
+            let mut chars = w.chars();
+            match chars.next() {
+                Some(c) => c.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect::<String>()
+        .chars()
+        .map(ascii_letters)
+        .collect();
+
+    if !ret.starts_with(|c: char| c.is_ascii_uppercase() || c.is_ascii_digit()) {
+        ret.insert(0, 'X');
+    }
+
+    ret
+}
+
+// Accented letters lose the accent so "Alimentación" stays readable. Other letters become their
+// code point, dropping them could make two different names end up as the same account.
+fn ascii_letters(c: char) -> String {
+    let plain = match c {
+        'á' | 'à' | 'â' | 'ä' | 'ã' | 'å' => 'a',
+        'Á' | 'À' | 'Â' | 'Ä' | 'Ã' | 'Å' => 'A',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'É' | 'È' | 'Ê' | 'Ë' => 'E',
+        'í' | 'ì' | 'î' | 'ï' => 'i',
+        'Í' | 'Ì' | 'Î' | 'Ï' => 'I',
+        'ó' | 'ò' | 'ô' | 'ö' | 'õ' => 'o',
+        'Ó' | 'Ò' | 'Ô' | 'Ö' | 'Õ' => 'O',
+        'ú' | 'ù' | 'û' | 'ü' => 'u',
+        'Ú' | 'Ù' | 'Û' | 'Ü' => 'U',
+        'ñ' => 'n',
+        'Ñ' => 'N',
+        'ç' => 'c',
+        'Ç' => 'C',
+        c if c.is_ascii_alphanumeric() || c == '-' => c,
+        c if c.is_alphanumeric() => return format!("U{:04X}", c as u32),
+        _ => return String::new(),
+    };
+    plain.to_string()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(dialect: LedgerDialect) -> String {
+        let db = Db::load(":memory:").unwrap();
+        db.insert_tag("Comida  rápida", "").unwrap();
+        db.insert_company("ACME S.L.", "").unwrap();
+        db.insert_category("Salary", "").unwrap();
+
+        let mut payroll = Payroll::new(&String::from("2022-01-31"), 2000.0, 1450.0, 100.0, 400.0, 1, 1);
+        payroll.other = 50.0;
+        let transaction = Transaction::new(&String::from("Bar \"Pepe\""), &String::from("2022-01-31"), -12.5, 1);
+
+        LedgerExportVm::generate(&[payroll], &[transaction], &db, dialect, "Assets:Bank", "EUR").render()
+    }
+
+    #[test]
+    fn entries_balance_and_keep_their_sign() {
+        let ledger = render(LedgerDialect::Ledger);
+        assert!(ledger.starts_with("2022-01-31 ACME S.L. | Payroll (Salary)\n"));
+        assert!(ledger.contains("    Expenses:Deductions:Other                        50.00 EUR\n"));
+        assert!(ledger.contains("    Income:Salary:ACME S.L.                       -2000.00 EUR\n"));
+        // Two spaces would end the account name.
+        assert!(ledger.contains("    Expenses:Comida rápida                          -12.50 EUR\n"));
+        assert!(ledger.contains("    Assets:Bank                                      12.50 EUR\n"));
+    }
+
+    #[test]
+    fn payrolls_that_do_not_add_up_still_balance() {
+        let db = Db::load(":memory:").unwrap();
+        db.insert_company("ACME", "").unwrap();
+        db.insert_category("Salary", "").unwrap();
+
+        // 1450 + 100 + 400 + 30 leaves 20 unexplained.
+        let mut payroll = Payroll::new(&String::from("2022-01-31"), 2000.0, 1450.0, 100.0, 400.0, 1, 1);
+        payroll.other = 30.0;
+        let ledger = LedgerExportVm::generate(&[payroll], &[], &db, LedgerDialect::Ledger, "Assets:Bank", "EUR").render();
+        assert!(ledger.contains("    Expenses:Deductions:Other                        50.00 EUR\n"));
+
+        let total: f64 = ledger.lines()
+            .filter_map(|l| l.split_whitespace().nth(1))
+            .filter_map(|a| a.parse::<f64>().ok())
+            .sum();
+        assert!(total.abs() < 0.001, "{}", ledger);
+
+        // Nothing left over, no posting.
+        let payroll = Payroll::new(&String::from("2022-02-28"), 2000.0, 1500.0, 100.0, 400.0, 1, 1);
+        let ledger = LedgerExportVm::generate(&[payroll], &[], &db, LedgerDialect::Ledger, "Assets:Bank", "EUR").render();
+        assert!(!ledger.contains("Deductions"));
+    }
+
+    #[test]
+    fn beancount_opens_accounts_and_quotes_strings() {
+        let beancount = render(LedgerDialect::Beancount);
+        assert!(beancount.starts_with("2022-01-31 open Assets:Bank EUR\n2022-01-31 open Expenses:ComidaRapida EUR\n"));
+        assert!(beancount.contains("2022-01-31 open Income:Salary:ACMESL EUR\n"));
+        assert!(beancount.contains("2022-01-31 * \"Bar \\\"Pepe\\\"\" \"Comida  rápida\"\n"));
+    }
+
+    #[test]
+    fn beancount_components_are_valid_and_distinct() {
+        assert_eq!(beancount_component("eating out"), "EatingOut");
+        assert_eq!(beancount_component("Alimentación"), "Alimentacion");
+        assert_eq!(beancount_component("niño & co."), "NinoCo");
+        assert_eq!(beancount_component("2022 trips"), "2022Trips");
+        assert_eq!(beancount_component("-x"), "X-x");
+        assert_eq!(beancount_component(""), "X");
+        assert_ne!(beancount_component("日本"), beancount_component("中国"));
+        assert_eq!(beancount_component("日本"), "U65E5U672C");
+    }
+
+    #[test]
+    fn dialects_parse() {
+        assert_eq!("hledger".parse::<LedgerDialect>(), Ok(LedgerDialect::Ledger));
+        assert_eq!("Beancount".parse::<LedgerDialect>(), Ok(LedgerDialect::Beancount));
+        assert!("gnucash".parse::<LedgerDialect>().is_err());
+    }
+}
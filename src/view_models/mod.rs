@@ -1,3 +1,9 @@
 pub mod transaction_data_vm;
 pub mod payroll_data_vm;
-pub mod balance_summary_vm;
\ No newline at end of file
+pub mod balance_summary_vm;
+pub mod ledger_export_vm;
+pub mod accounts_vm;
+pub mod compare_vm;
+pub mod pivot_vm;
+pub mod tax_summary_vm;
+pub mod salary_simulation_vm;
@@ -1,11 +1,12 @@
-use std::cmp::max;
 use std::collections::HashMap;
 
 use crate::Db;
+use crate::models::attachment;
 use crate::models::payroll::Payroll;
-use comfy_table::{Table, Row, ContentArrangement, Cell, Attribute, Color};
-use comfy_table::presets::UTF8_FULL;
-use comfy_table::modifiers::UTF8_ROUND_CORNERS;
+use crate::models::payroll_item::{PayrollItem, ItemKind};
+use crate::models::validation::PayrollValidator;
+use crate::renderer::{Report, Value};
+use crate::charts::{Chart, ChartKind, Series, TerminalPlotter, monthly_totals, month_label};
 
 struct PairInfo {
     id: i32,
@@ -28,8 +29,18 @@ impl PairInfo {
     }
 }
 
+// Items of the same concept and type added up. Taxable only if every one of them is.
+struct ConceptTotal {
+    concept: String,
+    kind: ItemKind,
+    total: f32,
+    count: usize,
+    irpf_taxable: bool,
+    ss_taxable: bool,
+}
+
 pub struct PayrollDataVm<'a> {
-    payrolls: &'a Vec<Payroll>,
+    payrolls: &'a [Payroll],
     gross_total: f32,
     net_total: f32,
     ss_total: f32,
@@ -43,7 +54,25 @@ impl<'a> PayrollDataVm<'a> {
         self.net_total
     }
     
-    pub fn generate(from: &'a Vec<Payroll>) -> Self {
+    pub fn count(&self) -> usize {
+        self.payrolls.len()
+    }
+    
+    // Gross, net, ss and irpf totals.
+    pub fn totals(&self) -> (f32, f32, f32, f32) {
+        (self.gross_total, self.net_total, self.ss_total, self.irpf_total)
+    }
+    
+    // Company id, payroll count and gross total per company, sorted by id.
+    pub fn company_groups(&self) -> Vec<(i32, usize, f32)> {
+        groups(&self.companies_info)
+    }
+    
+    pub fn category_groups(&self) -> Vec<(i32, usize, f32)> {
+        groups(&self.categories_info)
+    }
+    
+    pub fn generate(from: &'a [Payroll]) -> Self {
         let mut gross_total = 0.0;
         let mut net_total = 0.0;
         let mut ss_total = 0.0;
@@ -57,11 +86,8 @@ impl<'a> PayrollDataVm<'a> {
             ss_total += p.ss;
             irpf_total += p.irpf;
             
-            company_total.entry(p.company_id).or_insert(PairInfo::empty(p.company_id));
-            company_total.get_mut(&p.company_id).unwrap().add_count(p.gross);
-            
-            category_total.entry(p.category_id).or_insert(PairInfo::empty(p.category_id));
-            category_total.get_mut(&p.category_id).unwrap().add_count(p.gross);
+            company_total.entry(p.company_id).or_insert_with(|| PairInfo::empty(p.company_id)).add_count(p.gross);
+            category_total.entry(p.category_id).or_insert_with(|| PairInfo::empty(p.category_id)).add_count(p.gross);
         }
         
         Self {
@@ -75,106 +101,188 @@ impl<'a> PayrollDataVm<'a> {
         }
     }
     
-    pub fn render(&self, db: &Db) {
-        self.recap();
-        self.companies_and_categories(db);
+    pub fn render(&self, db: &Db, report: &mut Report) {
+        self.recap(report);
+        self.companies_and_categories(db, report);
     }
     
-    pub fn full_list(&self, db: &Db) {
-        let mut table = PayrollDataVm::create_table(vec![
-            "Id", "Date", "Gross", "Net", "SS", "Irpf", "Company", "Category"
-        ]);
+    pub fn full_list(&self, db: &Db, report: &mut Report) {
+        let mut rows = Vec::new();
 
         for p in self.payrolls {
-            let company = db.get_company_str(p.company_id).unwrap_or(String::from("Unknown"));
-            let category = db.get_category_str(p.category_id).unwrap_or(String::from("Unknown"));
-
-            table.add_row(vec![
-                Cell::new(p._id),
-                Cell::new(&p.date),
-                Cell::new(p.gross),
-                Cell::new(p.net),
-                Cell::new(p.ss),
-                Cell::new(p.irpf),
-                Cell::new(&company),
-                Cell::new(&category),
+            let company = db.get_company_str(p.company_id).unwrap_or_else(|_| String::from("Unknown"));
+            let category = db.get_category_str(p.category_id).unwrap_or_else(|_| String::from("Unknown"));
+            let attachments = db.get_payroll_attachments(p._id).unwrap_or_default();
+
+            rows.push(vec![
+                Value::from(p._id),
+                Value::date(&p.date),
+                Value::from(p.gross),
+                Value::from(p.net),
+                Value::from(p.ss),
+                Value::from(p.irpf),
+                Value::from(p.other),
+                Value::from(company),
+                Value::from(category),
+                Value::from(attachment::labels(&attachments)),
             ]);
         }
 
-        log::info!("Payrolls:\n{}", table);
+        report.add_table("payrolls", "Payrolls", vec![
+            "Id", "Date", "Gross", "Net", "SS", "Irpf", "Other", "Company", "Category", "Attachments"
+        ], rows);
     }
 
-    pub fn plot(&self, _db: &Db) {
+    // Totals per item concept and the payrolls whose items do not match their totals.
+    pub fn items(&self, items: &[PayrollItem], validator: &PayrollValidator, report: &mut Report) {
+        let mut concepts: Vec<ConceptTotal> = Vec::new();
         
-    }
-    
-    fn recap(&self) {
-        let count_str = self.payrolls.len().to_string();
-        let mut table = PayrollDataVm::create_table(vec![&count_str, "Gross", "Net", "SS", "Irpf"]);
+        for i in items.iter().filter(|i| self.payrolls.iter().any(|p| p._id == i.payroll_id)) {
+            match concepts.iter_mut().find(|c| c.concept == i.concept && c.kind == i.kind) {
+                Some(c) => {
+                    c.total += i.amount;
+                    c.count += 1;
+                    c.irpf_taxable &= i.irpf_taxable;
+                    c.ss_taxable &= i.ss_taxable;
+                },
+                None => concepts.push(ConceptTotal {
+                    concept: i.concept.clone(),
+                    kind: i.kind,
+                    total: i.amount,
+                    count: 1,
+                    irpf_taxable: i.irpf_taxable,
+                    ss_taxable: i.ss_taxable,
+                }),
+            }
+        }
         
-        table.add_row(vec![
-            Cell::new("Total").add_attribute(Attribute::Bold),
-            Cell::new(format!("{}", self.gross_total)),
-            Cell::new(format!("{}", self.net_total)),
-            Cell::new(format!("{}", self.ss_total)),
-            Cell::new(format!("{}", self.irpf_total)),
-        ]);
+        concepts.sort_by(|a, b| a.kind.to_string().cmp(&b.kind.to_string()).then(a.concept.cmp(&b.concept)));
+        let yes_no = |v: bool| Value::from(if v { "Yes" } else { "No" });
+        let rows = concepts.into_iter().map(|c| vec![
+            Value::from(c.concept),
+            Value::from(c.kind.to_string()),
+            Value::from(c.total),
+            Value::from(c.count),
+            Value::from(c.total / c.count as f32),
+            yes_no(c.irpf_taxable),
+            yes_no(c.ss_taxable),
+        ]).collect();
         
-        if self.payrolls.len() > 1 {
-            table.add_row(vec![
-                Cell::new("Avg.").add_attribute(Attribute::Bold),
-                Cell::new(format!("{}", self.gross_avg())),
-                Cell::new(format!("{}", self.net_avg())),
-                Cell::new(format!("{}", self.ss_avg())),
-                Cell::new(format!("{}", self.irpf_avg())),
-            ]);
+        report.add_table("concepts", "Per concept data", vec!["Concept", "Type", "Total", "Count", "Avg.", "Irpf taxable", "SS taxable"], rows);
+        
+        let mut issues = Vec::new();
+        for p in self.payrolls {
+            let own: Vec<PayrollItem> = items.iter().filter(|i| i.payroll_id == p._id).cloned().collect();
+            for issue in validator.validate_items(p, &own) {
+                issues.push(vec![Value::from(p._id), Value::date(&p.date), Value::from(issue)]);
+            }
         }
         
-        log::info!("Summary:\n{}", table);
+        if !issues.is_empty() {
+            report.add_table("item_issues", "Payrolls with mismatching items", vec!["Id", "Date", "Issue"], issues);
+        }
     }
     
-    fn companies_and_categories(&self, db: &Db) {
-        let mut table = PayrollDataVm::create_table(vec![
-            "Id", "Name", "Gross", "Avg.", "Count",
-            "",
-            "Id", "Name", "Gross", "Avg.", "Count",
-        ]);
+    pub fn plot(&self, db: &Db, plotter: &TerminalPlotter) {
+        print!("{}", plotter.draw_all(&self.charts(db)));
+    }
+    
+    pub fn charts(&self, db: &Db) -> Vec<Chart> {
+        let monthly = |f: fn(&Payroll) -> f32| monthly_totals(self.payrolls.iter().map(|p| (p.date.as_str(), f(p))));
         
-        let company_keys: Vec<i32> = self.companies_info.keys().cloned().collect();
-        let category_keys: Vec<i32> = self.categories_info.keys().cloned().collect();
-        let count = max(self.companies_info.len(), self.categories_info.len());
+        let (months, gross) = monthly(|p| p.gross);
+        let (_, net) = monthly(|p| p.net);
+        let (_, ss) = monthly(|p| p.ss);
+        let (_, irpf) = monthly(|p| p.irpf);
+        let (_, other) = monthly(|p| p.other);
         
-        for i in 0..count {
-            let company_info = self.companies_info.get(&company_keys[i]).unwrap();
-            let category_info = self.companies_info.get(&category_keys[i]).unwrap();
-            
-            let mut row = Row::new();
-            self.insert_company(&mut row, company_info, db);
-            row.add_cell(Cell::new(""));
-            self.insert_category(&mut row, category_info, db);
+        let rate = gross.iter().zip(irpf.iter())
+            .map(|(g, i)| if *g > 0.0 { i / g * 100.0 } else { 0.0 })
+            .collect();
+        
+        // Callers may pass the payrolls in any order, changes only make sense by date.
+        let mut by_date: Vec<&Payroll> = self.payrolls.iter().collect();
+        by_date.sort_by(|a, b| a.date.cmp(&b.date).then(a._id.cmp(&b._id)));
+        
+        let mut markers = Vec::new();
+        let mut company = None;
+        for p in by_date {
+            if company == Some(p.company_id) {
+                continue;
+            }
             
-            table.add_row(row);
+            company = Some(p.company_id);
+            let month = month_label(&p.date);
+            if let Some(i) = months.iter().position(|m| Some(m) == month.as_ref()) {
+                markers.push((i, db.get_company_str(p.company_id).unwrap_or_else(|_| String::from("Unknown"))));
+            }
         }
-
-        log::info!("Summarized data:\n{}", table);
+        
+        vec![
+            Chart::new("Net salary", ChartKind::Line, months.clone(), vec![Series::new("Net", net.clone())])
+                .with_markers(markers.clone()),
+            Chart::new("Gross breakdown", ChartKind::Stacked, months.clone(), vec![
+                Series::new("Net", net),
+                Series::new("SS", ss),
+                Series::new("IRPF", irpf),
+                Series::new("Other", other),
+            ]).with_markers(markers.clone()),
+            Chart::new("Effective IRPF rate", ChartKind::Line, months, vec![Series::new("IRPF", rate)])
+                .with_markers(markers)
+                .with_unit("%"),
+        ]
     }
     
-    fn insert_company(&self, row: &mut Row, info: &PairInfo, db: &Db) {
-        let name = db.get_company_str(info.id).unwrap_or(String::from("Unknown"));
-        row.add_cell(Cell::new(format!("{:02}", info.id)));
-        row.add_cell(Cell::new(format!("{}", name)));
-        row.add_cell(Cell::new(format!("{:.2}", info.amount)));
-        row.add_cell(Cell::new(format!("{:.2}", info.avg())));
-        row.add_cell(Cell::new(format!("{}", info.count)));
+    fn recap(&self, report: &mut Report) {
+        let count = self.payrolls.len();
+        let mut rows = vec![vec![
+            Value::from("Total"),
+            Value::from(count),
+            Value::from(self.gross_total),
+            Value::from(self.net_total),
+            Value::from(self.ss_total),
+            Value::from(self.irpf_total),
+        ]];
+        
+        if count > 1 {
+            rows.push(vec![
+                Value::from("Avg."),
+                Value::from(count),
+                Value::from(self.gross_avg()),
+                Value::from(self.net_avg()),
+                Value::from(self.ss_avg()),
+                Value::from(self.irpf_avg()),
+            ]);
+        }
+        
+        report.add_table("payroll_summary", "Payroll summary", vec!["Stat", "Count", "Gross", "Net", "SS", "Irpf"], rows);
+    }
+    
+    fn companies_and_categories(&self, db: &Db, report: &mut Report) {
+        let header = vec!["Id", "Name", "Gross", "Avg.", "Count"];
+
+        let rows = PayrollDataVm::pair_rows(&self.companies_info, |id| db.get_company_str(id));
+        report.add_table("companies", "Per company data", header.clone(), rows);
+
+        let rows = PayrollDataVm::pair_rows(&self.categories_info, |id| db.get_category_str(id));
+        report.add_table("categories", "Per category data", header, rows);
     }
+    
+    fn pair_rows<F>(infos: &HashMap<i32, PairInfo>, get_name: F) -> Vec<Vec<Value>>
+        where F: Fn(i32) -> Result<String, rusqlite::Error> {
+        let mut infos: Vec<&PairInfo> = infos.values().collect();
+        infos.sort_by_key(|i| i.id);
 
-    fn insert_category(&self, row: &mut Row, info: &PairInfo, db: &Db) {
-        let name = db.get_category_str(info.id).unwrap_or(String::from("Unknown"));
-        row.add_cell(Cell::new(format!("{:02}", info.id)));
-        row.add_cell(Cell::new(format!("{}", name)));
-        row.add_cell(Cell::new(format!("{:.2}", info.amount)));
-        row.add_cell(Cell::new(format!("{:.2}", info.avg())));
-        row.add_cell(Cell::new(format!("{}", info.count)));
+        infos.iter().map(|info| {
+            let name = get_name(info.id).unwrap_or_else(|_| String::from("Unknown"));
+            vec![
+                Value::from(info.id),
+                Value::from(name),
+                Value::from(info.amount),
+                Value::from(info.avg()),
+                Value::from(info.count),
+            ]
+        }).collect()
     }
 
     fn gross_avg(&self) -> f32 {
@@ -192,18 +300,87 @@ impl<'a> PayrollDataVm<'a> {
     fn irpf_avg(&self) -> f32 {
         self.irpf_total / (self.payrolls.len() as f32)
     }
-    
-    // TODO: Abstract this
-    fn create_table(header: Vec<&str>) -> Table {
-        let mut table = Table::new();
-        let cells: Vec<Cell> = header.iter().map(|h| { Cell::new(h).add_attribute(Attribute::Bold).fg(Color::Green) }).collect();
-        let header = Row::from(cells);
-        table
-            .load_preset(UTF8_FULL)
-            .apply_modifier(UTF8_ROUND_CORNERS)
-            .set_content_arrangement(ContentArrangement::Dynamic)
-            .set_header(header);
-
-        table
-    }
-}
\ No newline at end of file
+}
+
+fn groups(info: &HashMap<i32, PairInfo>) -> Vec<(i32, usize, f32)> {
+    let mut ret: Vec<(i32, usize, f32)> = info.values().map(|i| (i.id, i.count, i.amount)).collect();
+    ret.sort_by_key(|(id, _, _)| *id);
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::Content;
+
+    fn table(report: &Report, key: &str) -> Option<Vec<Vec<String>>> {
+        match &report.sections().iter().find(|s| s.key == key)?.content {
+            Content::Table { rows, .. } => Some(rows.iter().map(|r| r.iter().map(Value::to_plain).collect()).collect()),
+            Content::Summary(_) => panic!("{} is not a table", key),
+        }
+    }
+
+    fn payroll(id: i32, date: &str, gross: f32) -> Payroll {
+        let mut p = Payroll::new(&String::from(date), gross, gross * 0.75, gross * 0.05, gross * 0.2, 1, 1);
+        p._id = id;
+        p
+    }
+
+    #[test]
+    fn items_add_up_per_concept_and_type() {
+        let payrolls = vec![payroll(1, "2022-01-31", 2100.0), payroll(2, "2022-02-28", 2000.0)];
+        let item = |payroll_id, concept, kind, amount, irpf, ss| PayrollItem::new(payroll_id, concept, kind, amount, irpf, ss);
+        let items = vec![
+            item(1, "Base", ItemKind::Earning, 1900.0, true, true),
+            item(1, "Meals", ItemKind::Earning, 200.0, false, true),
+            item(2, "Base", ItemKind::Earning, 1900.0, true, true),
+            item(2, "Meals", ItemKind::Earning, 100.0, true, false),
+            // Same concept but a deduction, kept apart.
+            item(2, "Meals", ItemKind::Deduction, 20.0, true, true),
+            // Payroll outside the period.
+            item(3, "Base", ItemKind::Earning, 5000.0, true, true),
+        ];
+
+        let mut report = Report::new();
+        let validator = PayrollValidator { tolerance: 0.01, record_other: false };
+        PayrollDataVm::generate(&payrolls).items(&items, &validator, &mut report);
+
+        // Taxable only when every item of the concept is.
+        assert_eq!(table(&report, "concepts").unwrap(), vec![
+            ["Meals", "deduction", "20.00", "1", "20.00", "Yes", "Yes"].map(String::from).to_vec(),
+            ["Base", "earning", "3800.00", "2", "1900.00", "Yes", "Yes"].map(String::from).to_vec(),
+            ["Meals", "earning", "300.00", "2", "150.00", "No", "No"].map(String::from).to_vec(),
+        ]);
+
+        // The second payroll has deductions but no other deductions to match them.
+        let issues = table(&report, "item_issues").unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0][0], "2");
+        assert!(issues[0][2].starts_with("Deduction items add up to 20.00"));
+    }
+
+    #[test]
+    fn company_changes_are_marked_in_date_order() {
+        let db = Db::load(":memory:").unwrap();
+        db.insert_company("Acme", "").unwrap();
+        db.insert_company("Initech", "").unwrap();
+
+        let with_company = |id, date, company| {
+            let mut p = payroll(id, date, 2000.0);
+            p.company_id = company;
+            p
+        };
+        let payrolls = vec![
+            with_company(3, "2022-03-31", 2),
+            with_company(1, "2022-01-31", 1),
+            with_company(4, "2022-04-30", 2),
+            with_company(2, "2022-02-28", 1),
+        ];
+
+        let charts = PayrollDataVm::generate(&payrolls).charts(&db);
+        assert_eq!(charts[0].labels.len(), 4);
+        for chart in &charts {
+            assert_eq!(chart.markers, vec![(0, String::from("Acme")), (2, String::from("Initech"))]);
+        }
+    }
+}
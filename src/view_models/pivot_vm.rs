@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::Db;
+use crate::models::payroll::Payroll;
+use crate::models::transaction::Transaction;
+use crate::renderer::{Report, Value};
+use crate::charts::{Charset, monthly_totals, month_label, sparkline};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PivotSource {
+    Tags,
+    Companies,
+    Categories,
+}
+
+impl FromStr for PivotSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "tags" | "tag" => Ok(PivotSource::Tags),
+            "companies" | "company" => Ok(PivotSource::Companies),
+            "categories" | "category" => Ok(PivotSource::Categories),
+            _ => Err(format!("Invalid pivot source [{}]. Available: tags, companies, categories", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PivotPeriod {
+    Month,
+    Quarter,
+}
+
+impl FromStr for PivotPeriod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "month" | "monthly" => Ok(PivotPeriod::Month),
+            "quarter" | "quarterly" => Ok(PivotPeriod::Quarter),
+            _ => Err(format!("Invalid pivot period [{}]. Available: month, quarter", s)),
+        }
+    }
+}
+
+// Row ids (tag, company or category) by period columns.
+pub struct PivotVm {
+    source: PivotSource,
+    columns: Vec<String>,
+    rows: BTreeMap<i32, Vec<f32>>,
+}
+
+impl PivotVm {
+    pub fn from_transactions(transactions: &[Transaction], by: PivotPeriod) -> Self {
+        let entries: Vec<(i32, &str, f32)> = transactions.iter().map(|t| (t.tag_id, t.date.as_str(), t.amount)).collect();
+        PivotVm::generate(PivotSource::Tags, &entries, by)
+    }
+    
+    // Payrolls are pivoted by their net amount, same as the income in the balance summary.
+    pub fn from_payrolls(payrolls: &[Payroll], source: PivotSource, by: PivotPeriod) -> Self {
+        let entries: Vec<(i32, &str, f32)> = payrolls.iter().map(|p| {
+            let id = if source == PivotSource::Categories { p.category_id } else { p.company_id };
+            (id, p.date.as_str(), p.net)
+        }).collect();
+        PivotVm::generate(source, &entries, by)
+    }
+    
+    fn generate(source: PivotSource, entries: &[(i32, &str, f32)], by: PivotPeriod) -> Self {
+        let (months, _) = monthly_totals(entries.iter().map(|(_, d, _)| (*d, 0.0)));
+        
+        let mut columns: Vec<String> = Vec::new();
+        for m in &months {
+            let column = column_label(m, by);
+            if !columns.contains(&column) {
+                columns.push(column);
+            }
+        }
+        
+        let mut rows: BTreeMap<i32, Vec<f32>> = BTreeMap::new();
+        for (id, date, amount) in entries {
+            let column = match month_label(date) {
+                Some(m) => column_label(&m, by),
+                None => continue,
+            };
+            
+            if let Some(i) = columns.iter().position(|c| *c == column) {
+                rows.entry(*id).or_insert_with(|| vec![0.0; columns.len()])[i] += amount;
+            }
+        }
+        
+        Self { source, columns, rows }
+    }
+    
+    pub fn render(&self, db: &Db, charset: Charset, report: &mut Report) {
+        let (key, name) = match self.source {
+            PivotSource::Tags => ("tags", "Tag"),
+            PivotSource::Companies => ("companies", "Company"),
+            PivotSource::Categories => ("categories", "Category"),
+        };
+        
+        let mut header = vec![name];
+        header.extend(self.columns.iter().map(|c| c.as_str()));
+        header.extend(vec!["Total", "Avg.", "Trend"]);
+        
+        let mut rows = Vec::new();
+        for (id, values) in &self.rows {
+            let label = match self.source {
+                PivotSource::Tags => db.get_tag_str(*id),
+                PivotSource::Companies => db.get_company_str(*id),
+                PivotSource::Categories => db.get_category_str(*id),
+            }.unwrap_or_else(|_| String::from("Unknown"));
+            
+            rows.push(self.row(Value::from(label), values, charset));
+        }
+        
+        let totals: Vec<f32> = (0..self.columns.len()).map(|i| self.rows.values().map(|v| v[i]).sum()).collect();
+        if !self.rows.is_empty() {
+            rows.push(self.row(Value::from("Total"), &totals, charset));
+        }
+        
+        report.add_table(key, &format!("{} by period", name), header, rows);
+    }
+    
+    fn row(&self, label: Value, values: &[f32], charset: Charset) -> Vec<Value> {
+        let total: f32 = values.iter().sum();
+        
+        let mut row = vec![label];
+        row.extend(values.iter().map(|v| Value::from(*v)));
+        row.push(Value::from(total));
+        row.push(Value::from(total / values.len() as f32));
+        row.push(Value::from(sparkline(values, charset)));
+        row
+    }
+}
+
+fn column_label(month: &str, by: PivotPeriod) -> String {
+    match by {
+        PivotPeriod::Month => String::from(month),
+        PivotPeriod::Quarter => {
+            let (year, m) = month.split_at(4);
+            let m = m.trim_start_matches('-').parse::<u32>().unwrap_or(1);
+            format!("{}-Q{}", year, (m - 1) / 3 + 1)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::Content;
+
+    // Header and rows without the trend column, which only holds the sparkline.
+    fn render(vm: &PivotVm) -> (Vec<String>, Vec<Vec<String>>) {
+        let db = Db::load(":memory:").unwrap();
+        db.insert_tag("Food", "").unwrap();
+        db.insert_tag("Rent", "").unwrap();
+
+        let mut report = Report::new();
+        vm.render(&db, Charset::Ascii, &mut report);
+        match &report.sections()[0].content {
+            Content::Table { header, rows } => (
+                header[..header.len() - 1].to_vec(),
+                rows.iter().map(|r| r[..r.len() - 1].iter().map(Value::to_plain).collect()).collect(),
+            ),
+            Content::Summary(_) => panic!("Pivot renders a table"),
+        }
+    }
+
+    fn transactions() -> Vec<Transaction> {
+        let transaction = |date: &str, amount: f32, tag: i32| Transaction::new(&String::from("t"), &String::from(date), amount, tag);
+        vec![
+            transaction("2021-11-05", 10.0, 1),
+            transaction("2021-11-20", 5.0, 1),
+            transaction("2022-01-10", 30.0, 2),
+            transaction("2022-02-01", 20.0, 1),
+        ]
+    }
+
+    #[test]
+    fn months_without_data_are_kept_as_columns() {
+        let (header, rows) = render(&PivotVm::from_transactions(&transactions(), PivotPeriod::Month));
+        assert_eq!(header, vec!["Tag", "2021-11", "2021-12", "2022-01", "2022-02", "Total", "Avg."]);
+        assert_eq!(rows, vec![
+            vec!["Food", "15.00", "0.00", "0.00", "20.00", "35.00", "8.75"],
+            vec!["Rent", "0.00", "0.00", "30.00", "0.00", "30.00", "7.50"],
+            vec!["Total", "15.00", "0.00", "30.00", "20.00", "65.00", "16.25"],
+        ]);
+    }
+
+    #[test]
+    fn quarters_add_up_their_months() {
+        let (header, rows) = render(&PivotVm::from_transactions(&transactions(), PivotPeriod::Quarter));
+        assert_eq!(header, vec!["Tag", "2021-Q4", "2022-Q1", "Total", "Avg."]);
+        assert_eq!(rows, vec![
+            vec!["Food", "15.00", "20.00", "35.00", "17.50"],
+            vec!["Rent", "0.00", "30.00", "30.00", "15.00"],
+            vec!["Total", "15.00", "50.00", "65.00", "32.50"],
+        ]);
+    }
+
+    #[test]
+    fn nothing_to_pivot_has_no_total_row() {
+        let (header, rows) = render(&PivotVm::from_transactions(&[], PivotPeriod::Month));
+        assert_eq!(header, vec!["Tag", "Total", "Avg."]);
+        assert!(rows.is_empty());
+    }
+
+    #[test]
+    fn quarter_labels() {
+        assert_eq!(column_label("2022-01", PivotPeriod::Quarter), "2022-Q1");
+        assert_eq!(column_label("2022-06", PivotPeriod::Quarter), "2022-Q2");
+        assert_eq!(column_label("2022-12", PivotPeriod::Quarter), "2022-Q4");
+        assert_eq!(column_label("2022-12", PivotPeriod::Month), "2022-12");
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+
+use crate::models::payroll::Payroll;
+use crate::renderer::{Report, Value};
+use crate::tax::{IrpfModel, IrpfEstimate, SsModel};
+
+struct YearInfo {
+    count: usize,
+    gross: f32,
+    net: f32,
+    ss: f32,
+    irpf: f32,
+}
+
+pub struct SalarySimulationVm<'a> {
+    gross: f32,
+    payments: u32,
+    ss: f32,
+    estimate: IrpfEstimate,
+    ss_model: &'a SsModel,
+    irpf_model: &'a IrpfModel,
+}
+
+impl<'a> SalarySimulationVm<'a> {
+    pub fn generate(gross: f32, payments: u32, ss_model: &'a SsModel, irpf_model: &'a IrpfModel) -> Self {
+        let ss = ss_model.yearly(gross);
+        
+        Self {
+            gross,
+            payments,
+            ss,
+            estimate: irpf_model.estimate(gross, ss, 0.0),
+            ss_model,
+            irpf_model,
+        }
+    }
+    
+    pub fn render(&self, report: &mut Report) {
+        let net = self.gross - self.ss - self.estimate.tax();
+        let per_payment = self.gross / self.payments as f32;
+        let withholding = self.estimate.rate();
+        // SS is only deducted in the twelve monthly payslips, extra payments are prorated into their bases.
+        let extra_net = per_payment * (1.0 - withholding / 100.0);
+        let monthly_net = extra_net - self.ss / 12.0;
+        
+        let mut values = vec![
+            ("Gross", Value::from(self.gross)),
+            ("Payments", Value::Int(self.payments as i64)),
+            ("SS", Value::from(self.ss)),
+            ("Taxable base", Value::from(self.estimate.taxable_base)),
+            ("Personal and family minimum", Value::from(self.estimate.minimum)),
+            ("IRPF", Value::from(self.estimate.tax())),
+            ("Withholding rate", Value::Percent(withholding)),
+            ("Net", Value::from(net)),
+            ("Gross per payment", Value::from(per_payment)),
+            ("Net per monthly payment", Value::from(monthly_net)),
+        ];
+        
+        if self.payments > 12 {
+            values.push(("Net per extra payment", Value::from(extra_net)));
+        }
+        
+        report.add_summary("simulation", "Salary simulation", values);
+    }
+    
+    // Runs the model on the yearly gross of the real payrolls and shows how far it is from what was actually paid.
+    // A year with fewer than twelve payslips is annualised from the ones it has and scaled back, so the yearly
+    // base limits are not applied to a few months of salary, and it is left out of the mean error.
+    pub fn calibrate(&self, payrolls: &[Payroll], report: &mut Report) {
+        let mut years: BTreeMap<String, YearInfo> = BTreeMap::new();
+        
+        for p in payrolls {
+            let year = p.date.chars().take(4).collect();
+            let info = years.entry(year).or_insert(YearInfo { count: 0, gross: 0.0, net: 0.0, ss: 0.0, irpf: 0.0 });
+            info.count += 1;
+            info.gross += p.gross;
+            info.net += p.net;
+            info.ss += p.ss;
+            info.irpf += p.irpf;
+        }
+        
+        let mut rows = Vec::new();
+        let mut errors = Vec::new();
+        let mut partial_years: usize = 0;
+        
+        for (year, info) in &years {
+            let partial = info.count < 12;
+            let share = if partial { info.count as f32 / 12.0 } else { 1.0 };
+            let yearly_gross = info.gross / share;
+            let yearly_ss = self.ss_model.yearly(yearly_gross);
+            let ss = yearly_ss * share;
+            let irpf = self.irpf_model.estimate(yearly_gross, yearly_ss, 0.0).tax() * share;
+            let net = info.gross - ss - irpf;
+            let diff = net - info.net;
+            let diff_percent = if info.net > 0.0 { diff / info.net * 100.0 } else { 0.0 };
+            
+            if partial {
+                partial_years += 1;
+            } else {
+                errors.push(diff_percent.abs());
+            }
+            
+            rows.push(vec![
+                Value::from(year.as_str()),
+                Value::from(info.count),
+                Value::from(if partial { "Yes" } else { "" }),
+                Value::from(info.gross),
+                Value::from(info.net),
+                Value::from(net),
+                Value::from(diff),
+                Value::Percent(diff_percent),
+                Value::from(info.ss),
+                Value::from(ss),
+                Value::from(info.irpf),
+                Value::from(irpf),
+            ]);
+        }
+        
+        report.add_table("calibration", "Model vs payroll history", vec![
+            "Year", "Payslips", "Partial", "Gross", "Net", "Model net", "Diff", "Diff %", "SS", "Model SS", "Irpf", "Model irpf"
+        ], rows);
+        
+        if !errors.is_empty() {
+            let mean = errors.iter().sum::<f32>() / errors.len() as f32;
+            report.add_summary("calibration_error", "Calibration", vec![
+                ("Years", Value::from(errors.len())),
+                ("Partial years left out", Value::from(partial_years)),
+                ("Mean net error", Value::Percent(mean)),
+            ]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::Content;
+
+    fn section<'r>(report: &'r Report, key: &str) -> &'r Content {
+        &report.sections().iter().find(|s| s.key == key).unwrap().content
+    }
+
+    fn payslips(year: u32, count: u32) -> Vec<Payroll> {
+        (1..=count).map(|m| Payroll::new(&format!("{}-{:02}-28", year, m), 2000.0, 1600.0, 127.0, 273.0, 1, 1)).collect()
+    }
+
+    #[test]
+    fn calibrate_models_a_partial_year_from_its_own_payslips() {
+        let ss_model = SsModel::default();
+        let irpf_model = IrpfModel::default();
+        let vm = SalarySimulationVm::generate(24000.0, 12, &ss_model, &irpf_model);
+
+        let mut payrolls = payslips(2020, 2);
+        payrolls.extend(payslips(2021, 12));
+        let mut report = Report::new();
+        vm.calibrate(&payrolls, &mut report);
+
+        let rows: Vec<Vec<String>> = match section(&report, "calibration") {
+            Content::Table { rows, .. } => rows.iter().map(|r| r.iter().map(Value::to_plain).collect()).collect(),
+            Content::Summary(_) => panic!("calibration is not a table"),
+        };
+        // Two 2000 payslips pay SS on 4000, not on the twelve month minimum base.
+        assert_eq!(rows[0][..3], ["2020", "2", "Yes"]);
+        assert_eq!(rows[0][9], "254.00");
+        assert_eq!(rows[1][..3], ["2021", "12", ""]);
+        assert_eq!(rows[1][9], "1524.00");
+
+        // Only the full year counts towards the mean error.
+        let values: Vec<(String, String)> = match section(&report, "calibration_error") {
+            Content::Summary(values) => values.iter().map(|(k, v)| (k.clone(), v.to_plain())).collect(),
+            Content::Table { .. } => panic!("calibration_error is not a summary"),
+        };
+        assert_eq!(values[0], (String::from("Years"), String::from("1")));
+        assert_eq!(values[1], (String::from("Partial years left out"), String::from("1")));
+        assert_eq!(values[2].1, rows[1][7].trim_start_matches('-'));
+    }
+}
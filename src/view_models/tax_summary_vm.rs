@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+
+use crate::Db;
+use crate::models::payroll::Payroll;
+use crate::renderer::{Report, Value};
+use crate::tax::{IrpfModel, IrpfEstimate};
+
+#[derive(Default)]
+struct CompanyInfo {
+    count: usize,
+    gross: f32,
+    ss: f32,
+    irpf: f32,
+    net: f32,
+}
+
+impl CompanyInfo {
+    fn add(&mut self, p: &Payroll) {
+        self.count += 1;
+        self.gross += p.gross;
+        self.ss += p.ss;
+        self.irpf += p.irpf;
+        self.net += p.net;
+    }
+    
+    fn rate(&self) -> f32 {
+        if self.gross > 0.0 { self.irpf / self.gross * 100.0 } else { 0.0 }
+    }
+}
+
+pub struct TaxSummaryVm {
+    year: u32,
+    companies: BTreeMap<i32, CompanyInfo>,
+    deductions: Vec<(String, f32)>,
+    withheld: f32,
+    estimate: IrpfEstimate,
+}
+
+impl TaxSummaryVm {
+    // Deductions are the tag name and total amount of every tag marked as deductible.
+    pub fn generate(year: u32, payrolls: &[Payroll], deductions: Vec<(String, f32)>, model: &IrpfModel) -> Self {
+        let mut companies: BTreeMap<i32, CompanyInfo> = BTreeMap::new();
+        let mut total = CompanyInfo::default();
+        
+        for p in payrolls {
+            companies.entry(p.company_id).or_default().add(p);
+            total.add(p);
+        }
+        
+        let deducted: f32 = deductions.iter().map(|(_, amount)| amount).sum();
+        
+        Self {
+            year,
+            companies,
+            deductions,
+            withheld: total.irpf,
+            estimate: model.estimate(total.gross, total.ss, deducted),
+        }
+    }
+    
+    pub fn render(&self, db: &Db, report: &mut Report) {
+        let rows = self.companies.iter().map(|(id, info)| vec![
+            Value::from(db.get_company_str(*id).unwrap_or_else(|_| String::from("Unknown"))),
+            Value::from(info.count),
+            Value::from(info.gross),
+            Value::from(info.ss),
+            Value::from(info.irpf),
+            Value::from(info.net),
+            Value::Percent(info.rate()),
+        ]).collect();
+        
+        report.add_table("companies", &format!("Income by company {}", self.year), vec![
+            "Company", "Payslips", "Gross", "SS", "Irpf", "Net", "Withholding %"
+        ], rows);
+        
+        if !self.deductions.is_empty() {
+            let rows = self.deductions.iter().map(|(tag, amount)| vec![Value::from(tag.as_str()), Value::from(*amount)]).collect();
+            report.add_table("deductions", "Deductible items", vec!["Tag", "Amount"], rows);
+        }
+        
+        let e = &self.estimate;
+        report.add_summary("estimate", "IRPF estimate", vec![
+            ("Gross", Value::from(e.gross)),
+            ("SS contributions", Value::from(e.ss)),
+            ("Other expenses", Value::from(e.other_expenses)),
+            ("Work income reduction", Value::from(e.work_reduction)),
+            ("Deductions", Value::from(e.deductions)),
+            ("Taxable base", Value::from(e.taxable_base)),
+            ("Personal and family minimum", Value::from(e.minimum)),
+            ("State tax", Value::from(e.state_tax)),
+            ("Regional tax", Value::from(e.region_tax)),
+            ("Estimated tax", Value::from(e.tax())),
+            ("Effective rate", Value::Percent(e.rate())),
+            ("Withheld", Value::from(self.withheld)),
+            // Positive means there is still tax to pay, negative means a refund.
+            ("Result", Value::from(e.tax() - self.withheld)),
+        ]);
+    }
+}
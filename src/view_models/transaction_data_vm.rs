@@ -1,10 +1,10 @@
 use std::collections::HashMap;
 
 use crate::Db;
+use crate::models::attachment;
 use crate::models::transaction::Transaction;
-use comfy_table::{Table, Row, ContentArrangement, Cell, Attribute, Color};
-use comfy_table::presets::UTF8_FULL;
-use comfy_table::modifiers::UTF8_ROUND_CORNERS;
+use crate::renderer::{Report, Value};
+use crate::charts::{Chart, ChartKind, Series, TerminalPlotter, monthly_totals};
 
 struct TagInfo {
     id: i32,
@@ -28,7 +28,7 @@ impl TagInfo {
 }
 
 pub struct TransactionDataVm<'a> {
-    transactions: &'a Vec<Transaction>,
+    transactions: &'a [Transaction],
     total_amount: f32,
     amount_avg: f32,
     tags_info: HashMap<i32, TagInfo>,
@@ -39,89 +39,105 @@ impl<'a> TransactionDataVm<'a> {
         self.total_amount
     }
     
-    pub fn generate(from: &'a Vec<Transaction>) -> Self {
+    pub fn count(&self) -> usize {
+        self.transactions.len()
+    }
+    
+    pub fn average(&self) -> f32 {
+        self.amount_avg
+    }
+    
+    // Tag id, transaction count and total amount per tag, sorted by id.
+    pub fn tag_groups(&self) -> Vec<(i32, usize, f32)> {
+        let mut ret: Vec<(i32, usize, f32)> = self.tags_info.values().map(|i| (i.id, i.count, i.amount)).collect();
+        ret.sort_by_key(|(id, _, _)| *id);
+        ret
+    }
+    
+    pub fn generate(from: &'a [Transaction]) -> Self {
         let mut total_amount = 0.0;
         let mut tags_info: HashMap<i32, TagInfo> = HashMap::new();
         
         for t in from {
             total_amount += t.amount;
-            tags_info.entry(t.tag_id).or_insert(TagInfo::empty(t.tag_id));
-            tags_info.get_mut(&t.tag_id).unwrap().add_count(t.amount);
+            tags_info.entry(t.tag_id).or_insert_with(|| TagInfo::empty(t.tag_id)).add_count(t.amount);
         }
 
         TransactionDataVm {
             transactions: from,
             total_amount,
-            amount_avg: (total_amount / (from.len() as f32)),
+            amount_avg: if from.is_empty() { 0.0 } else { total_amount / (from.len() as f32) },
             tags_info,
         }
     }
     
-    pub fn render(&self, db: &Db) {
-        self.recap();
-        self.tags(db);
+    pub fn render(&self, db: &Db, report: &mut Report) {
+        self.recap(report);
+        self.tags(db, report);
     }
     
-    pub fn full_list(&self, db: &Db) {
-        let mut table = TransactionDataVm::create_table(vec!["Id", "Name", "Date", "Amount", "Tag"]);
+    pub fn full_list(&self, db: &Db, report: &mut Report) {
+        let mut rows = Vec::new();
 
         for t in self.transactions {
-            let tag = db.get_tag_str(t.tag_id).unwrap_or(String::from("Unknown"));
-            table.add_row(vec![
-                Cell::new(t._id),
-                Cell::new(&t.name),
-                Cell::new(&t.date),
-                Cell::new(t.amount),
-                Cell::new(&tag),
+            let tag = db.get_tag_str(t.tag_id).unwrap_or_else(|_| String::from("Unknown"));
+            let attachments = db.get_transaction_attachments(t._id).unwrap_or_default();
+            rows.push(vec![
+                Value::from(t._id),
+                Value::from(t.name.as_str()),
+                Value::date(&t.date),
+                Value::from(t.amount),
+                Value::from(tag),
+                Value::from(attachment::labels(&attachments)),
             ]);
         }
 
-        log::info!("Transactions:\n{}", table);
+        report.add_table("transactions", "Transactions", vec!["Id", "Name", "Date", "Amount", "Tag", "Attachments"], rows);
+    }
+    
+    pub fn plot(&self, db: &Db, plotter: &TerminalPlotter) {
+        print!("{}", plotter.draw_all(&self.charts(db)));
     }
     
-    pub fn plot(&self, _db: &Db) {
+    pub fn charts(&self, db: &Db) -> Vec<Chart> {
+        let (months, totals) = monthly_totals(self.transactions.iter().map(|t| (t.date.as_str(), t.amount)));
         
+        let mut infos: Vec<&TagInfo> = self.tags_info.values().collect();
+        infos.sort_by(|a, b| b.amount.partial_cmp(&a.amount).unwrap_or(std::cmp::Ordering::Equal).then(a.id.cmp(&b.id)));
+        let tags = infos.iter().map(|i| db.get_tag_str(i.id).unwrap_or_else(|_| String::from("Unknown"))).collect();
+        let amounts = infos.iter().map(|i| i.amount).collect();
+        
+        vec![
+            Chart::new("Monthly spending", ChartKind::Columns, months, vec![Series::new("Spending", totals)]),
+            Chart::new("Spending per tag", ChartKind::Bars, tags, vec![Series::new("Spending", amounts)]),
+        ]
     }
     
-    fn recap(&self) {
-        let mut table = TransactionDataVm::create_table(vec!["Total", "Tags count", "Avg. price"]);
-        table.add_row(vec![
-            Cell::new(format!("{:.2}", self.total_amount)),
-            Cell::new(format!("{}", self.tags_info.len())),
-            Cell::new(format!("{:.2}", self.amount_avg)),
+    fn recap(&self, report: &mut Report) {
+        report.add_summary("transaction_summary", "Transactions summary", vec![
+            ("Total", Value::from(self.total_amount)),
+            ("Tags count", Value::from(self.tags_info.len())),
+            ("Avg. price", Value::from(self.amount_avg)),
         ]);
-        
-        log::info!("Summary:\n{}", table);
     }
     
-    fn tags(&self, db: &Db) {
-        let mut table = TransactionDataVm::create_table(vec!["Id", "Tag", "Total", "Count", "Avg."]);
-        for (_, info) in &self.tags_info {
-            let tag = db.get_tag_str(info.id).unwrap_or(String::from("Unknown"));
+    fn tags(&self, db: &Db, report: &mut Report) {
+        let mut infos: Vec<&TagInfo> = self.tags_info.values().collect();
+        infos.sort_by_key(|i| i.id);
 
-            table.add_row(vec![
-                Cell::new(format!("{:02}", info.id)),
-                Cell::new(format!("{}", tag)),
-                Cell::new(format!("{:.2}", info.amount)),
-                Cell::new(format!("{:}", info.count)),
-                Cell::new(format!("{:.2}", info.avg())),
+        let mut rows = Vec::new();
+        for info in infos {
+            let tag = db.get_tag_str(info.id).unwrap_or_else(|_| String::from("Unknown"));
+
+            rows.push(vec![
+                Value::from(info.id),
+                Value::from(tag),
+                Value::from(info.amount),
+                Value::from(info.count),
+                Value::from(info.avg()),
             ]);
         }
         
-        log::info!("Per tags data:\n{}", table);
+        report.add_table("tags", "Per tags data", vec!["Id", "Tag", "Total", "Count", "Avg."], rows);
     }
-
-    // TODO: Abstract this
-    fn create_table(header: Vec<&str>) -> Table {
-        let mut table = Table::new();
-        let cells: Vec<Cell> = header.iter().map(|h| { Cell::new(h).add_attribute(Attribute::Bold).fg(Color::Green) }).collect();
-        let header = Row::from(cells);
-        table
-            .load_preset(UTF8_FULL)
-            .apply_modifier(UTF8_ROUND_CORNERS)
-            .set_content_arrangement(ContentArrangement::Dynamic)
-            .set_header(header);
-        
-        table
-    }
-}
\ No newline at end of file
+}
@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+
+mod common;
+
+use gitgud_greedy::{Attachment, Db, Transaction};
+use gitgud_greedy::attachment;
+
+use common::TempDir;
+
+fn walk(dir: &std::path::Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir).into_iter().flatten().flatten()
+        .flat_map(|e| if e.path().is_dir() { walk(&e.path()) } else { vec![e.path()] })
+        .collect()
+}
+
+#[test]
+fn attachments_are_stored_once_and_checked() {
+    let tmp = TempDir::new("attachments");
+    let db = Db::load(tmp.0.join("money.db").to_str().unwrap()).unwrap();
+    db.insert_tag("Food", "").unwrap();
+    db.insert_transaction(&Transaction::new(&String::from("Market"), &String::from("2022-03-05"), 42.5, 1)).unwrap();
+    let transaction = db.last_insert_id();
+
+    let receipt = tmp.0.join("receipt.pdf");
+    std::fs::write(&receipt, b"%PDF receipt").unwrap();
+
+    let dir = db.attachments_dir().unwrap();
+    assert_eq!(dir, tmp.0.join("money-attachments"));
+    for _ in 0..2 {
+        let (hash, size) = attachment::store(&dir, &receipt).unwrap();
+        db.insert_attachment(&Attachment::new("transactions", transaction, "receipt.pdf", &hash, size as i64)).unwrap();
+    }
+
+    let attachments = db.get_transaction_attachments(transaction).unwrap();
+    assert_eq!(attachment::labels(&attachments), "#1 receipt.pdf, #2 receipt.pdf");
+    assert_eq!(walk(&dir).len(), 1);
+    assert!(attachment::verify(&dir, &attachments[0]).is_none());
+
+    assert!(db.insert_attachment(&Attachment::new("transactions", 99, "x", &attachments[0].hash, 1)).is_err());
+
+    // Deleting the transaction takes its attachments, undo brings them back.
+    db.start_batch("delete");
+    db.delete_transaction(transaction).unwrap();
+    assert!(db.get_all_attachments().unwrap().is_empty());
+    db.undo(1).unwrap();
+    assert_eq!(db.get_transaction_attachments(transaction).unwrap().len(), 2);
+
+    let stored = attachment::stored_path(&dir, &attachments[0].hash);
+    std::fs::remove_file(&stored).unwrap();
+    assert!(attachment::verify(&dir, &attachments[0]).unwrap().starts_with("Missing"));
+}
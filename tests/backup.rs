@@ -0,0 +1,63 @@
+mod common;
+
+use gitgud_greedy::{Db, LoadError, SCHEMA_VERSION};
+
+use common::TempDb;
+
+fn tag_names(db: &Db) -> Vec<String> {
+    db.get_all_tags().unwrap().into_iter().map(|t| t.name).collect()
+}
+
+#[test]
+fn restore_brings_back_the_backup() {
+    let file = TempDb::new("backup-source");
+    let copy = TempDb::new("backup-copy");
+
+    let db = Db::load(file.path()).unwrap();
+    db.insert_tag("Food", "").unwrap();
+    db.backup_to(copy.path()).unwrap();
+
+    db.insert_tag("Rent", "").unwrap();
+    assert_eq!(tag_names(&db), vec!["Food", "Rent"]);
+
+    assert_eq!(db.restore_from(copy.path(), || None).unwrap(), SCHEMA_VERSION);
+    assert_eq!(tag_names(&db), vec!["Food"]);
+    drop(db);
+
+    assert_eq!(tag_names(&Db::load(file.path()).unwrap()), vec!["Food"]);
+}
+
+#[test]
+fn restore_rejects_newer_or_foreign_files() {
+    let file = TempDb::new("backup-target");
+    let newer = TempDb::new("backup-newer");
+    let foreign = TempDb::new("backup-foreign");
+
+    let db = Db::load(file.path()).unwrap();
+    db.insert_tag("Food", "").unwrap();
+
+    db.backup_to(newer.path()).unwrap();
+    rusqlite::Connection::open(newer.path()).unwrap().pragma_update(None, "user_version", 99).unwrap();
+    assert!(matches!(db.check_backup(newer.path(), || None), Err(LoadError::Incompatible(_))));
+
+    rusqlite::Connection::open(foreign.path()).unwrap().execute("CREATE TABLE other (id INTEGER)", []).unwrap();
+    assert!(matches!(db.restore_from(foreign.path(), || None), Err(LoadError::Incompatible(_))));
+
+    assert_eq!(tag_names(&db), vec!["Food"]);
+}
+
+#[test]
+fn restored_db_can_still_be_written() {
+    let file = TempDb::new("backup-live");
+    let copy = TempDb::new("backup-live-copy");
+
+    let db = Db::load(file.path()).unwrap();
+    db.insert_tag("Food", "").unwrap();
+    db.backup_to(copy.path()).unwrap();
+
+    db.restore_from(copy.path(), || None).unwrap();
+    db.insert_tag("Rent", "").unwrap();
+    drop(db);
+
+    assert_eq!(tag_names(&Db::load(file.path()).unwrap()), vec!["Food", "Rent"]);
+}
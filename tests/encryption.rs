@@ -0,0 +1,59 @@
+mod common;
+
+use gitgud_greedy::{ClientError, Db, GreedyClient, LoadError};
+
+use common::TempDb;
+
+#[test]
+fn encrypted_db_keeps_changes() {
+    let file = TempDb::new("encrypted");
+    {
+        let db = Db::load(file.path()).unwrap();
+        db.insert_tag("Food", "").unwrap();
+        db.encrypt("hunter2").unwrap();
+        db.insert_tag("Rent", "").unwrap();
+    }
+
+    let data = std::fs::read(file.path()).unwrap();
+    assert!(data.starts_with(b"GGCRYPT1"));
+    assert!(!data.windows(4).any(|w| w == b"Food"));
+
+    let client = GreedyClient::open_encrypted(file.path(), "hunter2").unwrap();
+    let tags: Vec<String> = client.tags().unwrap().into_iter().map(|t| t.name).collect();
+    assert_eq!(tags, vec!["Food", "Rent"]);
+
+    client.db().decrypt().unwrap();
+    drop(client);
+    assert_eq!(GreedyClient::open(file.path()).unwrap().tags().unwrap().len(), 2);
+}
+
+#[test]
+fn wrong_or_missing_key_is_reported() {
+    let file = TempDb::new("wrong-key");
+    Db::load(file.path()).unwrap().encrypt("hunter2").unwrap();
+
+    assert!(matches!(Db::load(file.path()), Err(LoadError::KeyRequired)));
+    assert!(matches!(Db::open(file.path(), || Some(String::from("nope"))), Err(LoadError::WrongKey)));
+    assert!(matches!(GreedyClient::open_encrypted(file.path(), "nope"), Err(ClientError::Open(LoadError::WrongKey))));
+}
+
+#[test]
+fn save_refuses_to_overwrite_changes_from_another_process() {
+    let file = TempDb::new("concurrent");
+    Db::load(file.path()).unwrap().encrypt("hunter2").unwrap();
+
+    let key = || Some(String::from("hunter2"));
+    let server = Db::open(file.path(), key).unwrap();
+    let cli = Db::open(file.path(), key).unwrap();
+
+    cli.insert_tag("Food", "").unwrap();
+    cli.save().unwrap();
+
+    server.insert_tag("Rent", "").unwrap();
+    assert!(matches!(server.save(), Err(LoadError::Changed)));
+    drop(server);
+    drop(cli);
+
+    let tags: Vec<String> = Db::open(file.path(), key).unwrap().get_all_tags().unwrap().into_iter().map(|t| t.name).collect();
+    assert_eq!(tags, vec!["Food"]);
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use gitgud_greedy::{Db, ItemKind, Name, Payroll, PayrollItem, Transaction};
+
+use common::TempDb;
+
+fn memory_db() -> Db {
+    let db = Db::load(":memory:").unwrap();
+    db.start_batch("setup");
+    db.insert_tag("Food", "").unwrap();
+    db.insert_company("ACME", "").unwrap();
+    db.insert_category("Salary", "").unwrap();
+    db
+}
+
+#[test]
+fn undo_reverts_whole_batches() {
+    let db = memory_db();
+
+    db.start_batch("import");
+    for name in ["Bread", "Milk"] {
+        db.insert_transaction(&Transaction::new(&String::from(name), &String::from("2022-05-01"), 2.0, 1)).unwrap();
+    }
+    db.start_batch("rename");
+    db.update_tag(&Name::new(1, String::from("Groceries"), String::from(""))).unwrap();
+
+    let history = db.get_history(None, None, None, false, 10).unwrap();
+    assert_eq!(history.len(), 6);
+    assert_eq!(history[0].command, "rename");
+    assert_eq!(history[0].changes(), "name: Food -> Groceries");
+
+    let undone = db.undo(2).unwrap();
+    assert_eq!(undone.len(), 3);
+    assert_eq!(db.get_tag_str(1).unwrap(), "Food");
+    assert!(db.get_transaction_data(None, None).unwrap().is_empty());
+
+    // Undone changes are not undone twice.
+    assert_eq!(db.get_last_batches(1).unwrap()[0].command, "setup");
+}
+
+#[test]
+fn undo_restores_deleted_and_updated_rows() {
+    let db = memory_db();
+
+    let mut payroll = Payroll::new(&String::from("2022-01-31"), 2000.0, 1500.0, 100.0, 400.0, 1, 1);
+    db.insert_payroll(&payroll).unwrap();
+    payroll._id = db.last_insert_id();
+    db.insert_payroll_item(&PayrollItem::new(payroll._id, "Base", ItemKind::Earning, 2000.0, true, true)).unwrap();
+
+    db.start_batch("delete");
+    db.delete_payroll(payroll._id).unwrap();
+    db.undo(1).unwrap();
+    assert_eq!(db.get_payroll(payroll._id).unwrap().gross, 2000.0);
+    assert_eq!(db.get_payroll_items(payroll._id).unwrap()[0].concept, "Base");
+
+    db.start_batch("raise");
+    payroll.gross = 2100.0;
+    db.update_payroll(&payroll).unwrap();
+    db.undo(1).unwrap();
+    assert_eq!(db.get_payroll(payroll._id).unwrap().gross, 2000.0);
+}
+
+#[test]
+fn undo_stops_when_rows_changed_outside_the_audit() {
+    let file = TempDb::new("history");
+    let path = file.path();
+
+    let db = Db::load(path).unwrap();
+    db.start_batch("add");
+    db.insert_tag("Food", "").unwrap();
+    rusqlite::Connection::open(path).unwrap().execute("UPDATE tags SET name = 'Other'", []).unwrap();
+
+    assert!(db.undo(1).is_err());
+    assert_eq!(db.get_tag_str(1).unwrap(), "Other");
+    assert_eq!(db.get_last_batches(1).unwrap().len(), 1);
+
+}
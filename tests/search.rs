@@ -0,0 +1,54 @@
+use gitgud_greedy::{Db, Payroll, SearchFilter, SearchHit, Transaction};
+use gitgud_greedy::search;
+
+fn find(db: &Db, text: &str, filter: &SearchFilter) -> Vec<SearchHit> {
+    db.search(&search::match_query(text), filter, ("[", "]")).unwrap()
+}
+
+fn ids(hits: &[SearchHit]) -> Vec<(String, i32)> {
+    hits.iter().map(|h| (h.kind.clone(), h.id)).collect()
+}
+
+#[test]
+fn search_finds_names_notes_and_related_names() {
+    let db = Db::load(":memory:").unwrap();
+    db.insert_tag("Leisure", "").unwrap();
+    db.insert_company("ACME", "").unwrap();
+    db.insert_category("Salary", "").unwrap();
+
+    let mut book = Transaction::new(&String::from("Amazón Marketplace"), &String::from("2022-03-05"), 23.5, 1);
+    book.notes = Some(String::from("Libro de cocina"));
+    db.insert_transaction(&book).unwrap();
+    db.insert_transaction(&Transaction::new(&String::from("Amazon Prime"), &String::from("2022-05-01"), 49.9, 1)).unwrap();
+    db.insert_payroll(&Payroll::new(&String::from("2022-03-31"), 2000.0, 1500.0, 100.0, 400.0, 1, 1)).unwrap();
+
+    // Accents are ignored and every word is a prefix.
+    let hits = find(&db, "amazon", &SearchFilter::default());
+    assert_eq!(hits.len(), 2);
+    assert!(hits.iter().any(|h| h.name.as_deref() == Some("[Amazón] Marketplace")));
+
+    let hits = find(&db, "amaz cocin", &SearchFilter::default());
+    assert_eq!(ids(&hits), vec![(String::from("transaction"), 1)]);
+    assert_eq!(hits[0].notes.as_deref(), Some("Libro de [cocina]"));
+
+    // Filters combine with the words.
+    let filter = SearchFilter { to: Some(String::from("2022-04-30")), ..SearchFilter::default() };
+    assert_eq!(ids(&find(&db, "amazon", &filter)), vec![(String::from("transaction"), 1)]);
+    let filter = SearchFilter { min: Some(30.0), ..SearchFilter::default() };
+    assert_eq!(ids(&find(&db, "amazon", &filter)), vec![(String::from("transaction"), 2)]);
+    let filter = SearchFilter { kind: Some(String::from("payroll")), year: Some(2022), month: Some(3), ..SearchFilter::default() };
+    assert_eq!(ids(&find(&db, "acme salary", &filter)), vec![(String::from("payroll"), 1)]);
+
+    // Renaming a tag is searchable right away, deleted rows are gone.
+    let mut tag = db.get_all_tags().unwrap().remove(0);
+    tag.name = String::from("Ocio");
+    db.update_tag(&tag).unwrap();
+    assert_eq!(find(&db, "ocio", &SearchFilter::default()).len(), 2);
+    assert!(find(&db, "leisure", &SearchFilter::default()).is_empty());
+
+    db.delete_transaction(1).unwrap();
+    assert_eq!(ids(&find(&db, "amazon", &SearchFilter::default())), vec![(String::from("transaction"), 2)]);
+
+    // Quotes and fts5 operators in what is typed are plain text.
+    assert!(find(&db, "\"prime OR -", &SearchFilter::default()).is_empty());
+}
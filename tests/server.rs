@@ -0,0 +1,193 @@
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+
+use serde_json::{json, Value};
+
+use gitgud_greedy::{Db, Server};
+
+const TOKEN: &str = "secret";
+
+// Each test gets its own server on a free port over an empty in-memory db.
+fn start() -> SocketAddr {
+    start_with_tolerance(0.01)
+}
+
+fn start_with_tolerance(tolerance: f32) -> SocketAddr {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let db = Db::load(":memory:").expect("In-memory db");
+        let server = Server::bind("127.0.0.1:0", TOKEN).expect("Server bound").with_payroll_tolerance(tolerance);
+        tx.send(server.addr().expect("Ip address")).unwrap();
+        server.run(&db);
+    });
+
+    rx.recv().unwrap()
+}
+
+fn send(addr: SocketAddr, method: &str, path: &str, token: Option<&str>, body: Option<Value>) -> (u16, Value) {
+    let body = body.map(|b| b.to_string()).unwrap_or_default();
+    let auth = token.map(|t| format!("Authorization: Bearer {}\r\n", t)).unwrap_or_default();
+    let request = format!(
+        "{} {} HTTP/1.1\r\nHost: localhost\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method, path, auth, body.len(), body
+    );
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let (head, content) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    let value = if content.trim().is_empty() { Value::Null } else { serde_json::from_str(content).unwrap() };
+    (status, value)
+}
+
+fn call(addr: SocketAddr, method: &str, path: &str, body: Option<Value>) -> (u16, Value) {
+    send(addr, method, path, Some(TOKEN), body)
+}
+
+#[test]
+fn rejects_requests_without_the_token() {
+    let addr = start();
+
+    assert_eq!(send(addr, "GET", "/tags", None, None).0, 401);
+    assert_eq!(send(addr, "GET", "/tags", Some("wrong"), None).0, 401);
+    assert_eq!(call(addr, "GET", "/tags", None).0, 200);
+}
+
+#[test]
+fn names_crud() {
+    let addr = start();
+
+    let (status, tag) = call(addr, "POST", "/tags", Some(json!({ "name": "Food", "description": "Groceries" })));
+    assert_eq!(status, 201);
+    let id = tag["id"].as_i64().unwrap();
+
+    assert_eq!(call(addr, "POST", "/tags", Some(json!({ "name": "Food" }))).0, 409);
+
+    let (status, tag) = call(addr, "PUT", &format!("/tags/{}", id), Some(json!({ "description": "Supermarket" })));
+    assert_eq!(status, 200);
+    assert_eq!(tag["name"], "Food");
+    assert_eq!(tag["description"], "Supermarket");
+
+    let (_, tags) = call(addr, "GET", "/tags", None);
+    assert_eq!(tags.as_array().unwrap().len(), 1);
+
+    assert_eq!(call(addr, "DELETE", &format!("/tags/{}", id), None).0, 204);
+    assert_eq!(call(addr, "GET", &format!("/tags/{}", id), None).0, 404);
+    assert_eq!(call(addr, "POST", "/companies", Some(json!({ "description": "No name" }))).0, 422);
+}
+
+#[test]
+fn transactions_crud_and_filters() {
+    let addr = start();
+    call(addr, "POST", "/tags", Some(json!({ "name": "Food" })));
+    call(addr, "POST", "/tags", Some(json!({ "name": "Rent" })));
+
+    let (status, created) = call(addr, "POST", "/transactions", Some(json!({ "name": "Market", "date": "2022/03/05", "amount": 42.5, "tag": "Food" })));
+    assert_eq!(status, 201);
+    assert_eq!(created["date"], "2022-03-05");
+    let id = created["id"].as_i64().unwrap();
+
+    call(addr, "POST", "/transactions", Some(json!({ "name": "Flat", "date": "2022-03-01", "amount": 600.0, "tag": "Rent" })));
+    call(addr, "POST", "/transactions", Some(json!({ "name": "Flat", "date": "2022-04-01", "amount": 600.0, "tag": "Rent" })));
+
+    assert_eq!(call(addr, "POST", "/transactions", Some(json!({ "name": "X", "date": "2022-03-01", "amount": 1.0, "tag": "Nope" }))).0, 422);
+    assert_eq!(call(addr, "POST", "/transactions", Some(json!({ "name": "X", "date": "tomorrow", "amount": 1.0, "tag": "Food" }))).0, 422);
+
+    let (_, march) = call(addr, "GET", "/transactions?year=2022&month=3", None);
+    assert_eq!(march.as_array().unwrap().len(), 2);
+    let (_, rent) = call(addr, "GET", "/transactions?year=2022&tag=Rent", None);
+    assert_eq!(rent.as_array().unwrap().len(), 2);
+    assert_eq!(call(addr, "GET", "/transactions?month=3", None).0, 400);
+
+    let (status, updated) = call(addr, "PATCH", &format!("/transactions/{}", id), Some(json!({ "amount": 40.0, "tag": "Rent" })));
+    assert_eq!(status, 200);
+    assert_eq!(updated["tag"], "Rent");
+    assert_eq!(updated["name"], "Market");
+
+    let (_, rent_tag) = call(addr, "GET", "/tags", None);
+    let rent_id = rent_tag.as_array().unwrap().iter().find(|t| t["name"] == "Rent").unwrap()["id"].as_i64().unwrap();
+    assert_eq!(call(addr, "DELETE", &format!("/tags/{}", rent_id), None).0, 409);
+
+    assert_eq!(call(addr, "DELETE", &format!("/transactions/{}", id), None).0, 204);
+    assert_eq!(call(addr, "DELETE", &format!("/transactions/{}", id), None).0, 404);
+}
+
+#[test]
+fn payrolls_are_validated() {
+    let addr = start();
+    call(addr, "POST", "/companies", Some(json!({ "name": "ACME" })));
+    call(addr, "POST", "/categories", Some(json!({ "name": "Salary" })));
+
+    let payroll = json!({ "date": "2022-03-31", "gross": 3000.0, "net": 2300.0, "ss": 200.0, "irpf": 500.0, "company": "ACME", "category": "Salary" });
+    let (status, created) = call(addr, "POST", "/payrolls", Some(payroll));
+    assert_eq!(status, 201);
+    assert_eq!(created["company"], "ACME");
+
+    let wrong = json!({ "date": "2022-04-30", "gross": 3000.0, "net": 2500.0, "ss": 200.0, "irpf": 500.0, "company": "ACME", "category": "Salary" });
+    assert_eq!(call(addr, "POST", "/payrolls", Some(wrong)).0, 422);
+
+    let (_, list) = call(addr, "GET", "/payrolls?year=2022&company=ACME", None);
+    assert_eq!(list.as_array().unwrap().len(), 1);
+
+    let id = created["id"].as_i64().unwrap();
+    assert_eq!(call(addr, "PUT", &format!("/payrolls/{}", id), Some(json!({ "net": 2000.0 }))).0, 422);
+    assert_eq!(call(addr, "DELETE", &format!("/payrolls/{}", id), None).0, 204);
+}
+
+#[test]
+fn payrolls_use_the_server_tolerance() {
+    let addr = start_with_tolerance(5.0);
+    call(addr, "POST", "/companies", Some(json!({ "name": "ACME" })));
+    call(addr, "POST", "/categories", Some(json!({ "name": "Salary" })));
+
+    let close = json!({ "date": "2022-03-31", "gross": 3000.0, "net": 2297.0, "ss": 200.0, "irpf": 500.0, "company": "ACME", "category": "Salary" });
+    assert_eq!(call(addr, "POST", "/payrolls", Some(close)).0, 201);
+
+    let far = json!({ "date": "2022-04-30", "gross": 3000.0, "net": 2290.0, "ss": 200.0, "irpf": 500.0, "company": "ACME", "category": "Salary" });
+    assert_eq!(call(addr, "POST", "/payrolls", Some(far)).0, 422);
+}
+
+#[test]
+fn accounts_crud() {
+    let addr = start();
+
+    let (status, account) = call(addr, "POST", "/accounts", Some(json!({ "name": "Bank", "amount": 1500.0 })));
+    assert_eq!(status, 201);
+    let id = account["id"].as_i64().unwrap();
+
+    let (_, account) = call(addr, "PUT", &format!("/accounts/{}", id), Some(json!({ "amount": 1750.0 })));
+    assert_eq!(account["amount"], 1750.0);
+    assert_eq!(account["name"], "Bank");
+
+    assert_eq!(call(addr, "POST", "/accounts", Some(json!({ "name": "Bank", "amount": 1.0, "iban": "ES00" }))).0, 400);
+    assert_eq!(call(addr, "DELETE", &format!("/accounts/{}", id), None).0, 204);
+}
+
+#[test]
+fn reports_match_the_data() {
+    let addr = start();
+    call(addr, "POST", "/tags", Some(json!({ "name": "Food" })));
+    call(addr, "POST", "/companies", Some(json!({ "name": "ACME" })));
+    call(addr, "POST", "/categories", Some(json!({ "name": "Salary" })));
+    call(addr, "POST", "/transactions", Some(json!({ "name": "Market", "date": "2022-03-05", "amount": 100.0, "tag": "Food" })));
+    call(addr, "POST", "/transactions", Some(json!({ "name": "Market", "date": "2022-03-12", "amount": 50.0, "tag": "Food" })));
+    call(addr, "POST", "/payrolls", Some(json!({ "date": "2022-03-31", "gross": 3000.0, "net": 2300.0, "ss": 200.0, "irpf": 500.0, "company": "ACME", "category": "Salary" })));
+
+    let (status, balance) = call(addr, "GET", "/reports/balance?year=2022&month=3", None);
+    assert_eq!(status, 200);
+    assert_eq!(balance["balance"]["income"], 2300.0);
+    assert_eq!(balance["balance"]["expenses"], 150.0);
+    assert_eq!(balance["balance"]["total"], 2150.0);
+
+    let (status, report) = call(addr, "GET", "/reports/transactions?year=2022", None);
+    assert_eq!(status, 200);
+    assert!(report.as_object().unwrap().values().any(|s| s.to_string().contains("Food")));
+
+    assert_eq!(call(addr, "GET", "/reports/unknown", None).0, 404);
+    assert_eq!(call(addr, "POST", "/reports/balance", None).0, 405);
+}